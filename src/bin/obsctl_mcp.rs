@@ -1,19 +1,84 @@
-use obsctl::{config::AppContext, mcp::ObsctlMcpServer};
+use clap::{Parser, ValueEnum};
+use obsctl::{
+    config::AppContext,
+    mcp::{http, ObsctlMcpServer},
+};
 use rmcp::{
     service::{QuitReason, ServiceExt},
     transport,
 };
 
+/// Serve the obsctl vault over the Model Context Protocol.
+#[derive(Parser)]
+#[command(name = "obsctl_mcp", version)]
+struct Args {
+    /// How clients connect: spawn the server over stdio, or share one over
+    /// streamable HTTP.
+    #[arg(long, value_enum, default_value_t = Transport::Stdio)]
+    transport: Transport,
+    /// Address to listen on with `--transport http` [default: `[mcp] bind`].
+    #[arg(long, value_name = "ADDR")]
+    bind: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    Stdio,
+    Http,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
     let ctx = AppContext::load()?;
-    let service = ObsctlMcpServer::new(ctx);
-    let transport = transport::stdio();
+    match args.transport {
+        Transport::Stdio => serve_stdio(ctx).await,
+        Transport::Http => {
+            let bind = args.bind.unwrap_or_else(|| ctx.config().mcp.bind.clone());
+            let listener = tokio::net::TcpListener::bind(&bind)
+                .await
+                .map_err(|err| format!("bind {bind}: {err}"))?;
+            let local = listener.local_addr()?;
+            http::check_address(&ctx, local)?;
+            eprintln!("obsctl_mcp listening on http://{local}{}", http::MCP_PATH);
+            if let Some(warning) = http::host_warning(&ctx, local) {
+                eprintln!("warning: {warning}");
+            }
+            http::serve(ctx, listener, shutdown_signal()).await?;
+            Ok(())
+        }
+    }
+}
 
-    let running = service.serve(transport).await?;
+async fn serve_stdio(ctx: AppContext) -> Result<(), Box<dyn std::error::Error>> {
+    let service = ObsctlMcpServer::new(ctx);
+    let running = service.serve(transport::stdio()).await?;
     match running.waiting().await {
         Ok(QuitReason::JoinError(err)) => Err(err.into()),
         Err(err) => Err(err.into()),
         _ => Ok(()),
     }
 }
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let interrupt = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
@@ -1,7 +1,9 @@
 use std::path::PathBuf;
 
 use clap::{Args, Subcommand};
+use serde_json::json;
 
+use super::Output;
 use crate::config::{AppContext, ConfigManager};
 
 #[derive(Subcommand)]
@@ -26,23 +28,35 @@ pub struct ConfigPathArgs {
     pub set: Option<PathBuf>,
 }
 
-pub fn handle(cmd: ConfigCommand, ctx: &AppContext) -> anyhow::Result<()> {
+pub fn handle(cmd: ConfigCommand, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
+    let manager = ConfigManager::new(ctx.config_file().to_path_buf());
     match cmd {
         ConfigCommand::Init(args) => {
-            let manager = ConfigManager::new(ctx.config_file().to_path_buf());
             manager.ensure_initialized(args.vault.as_deref())?;
-            println!(
-                "Vault initialized at {}",
-                PathBuf::from(manager.load()?.vault.path).display()
-            );
+            let vault = PathBuf::from(manager.load()?.vault.path);
+            out.record(&json!({ "vault": vault }), || {
+                if out.is_plain() {
+                    println!("{}", vault.display());
+                } else {
+                    println!("Vault initialized at {}", vault.display());
+                }
+            })?;
         }
         ConfigCommand::Path(args) => {
-            let manager = ConfigManager::new(ctx.config_file().to_path_buf());
             if let Some(path) = args.set {
                 manager.update_vault_path(&path)?;
-                println!("Updated vault path to {}", path.display());
+                out.record(&json!({ "vault": path }), || {
+                    if out.is_plain() {
+                        println!("{}", path.display());
+                    } else {
+                        println!("Updated vault path to {}", path.display());
+                    }
+                })?;
             } else {
-                println!("{}", ctx.vault_root().display());
+                let vault = ctx.vault_root();
+                out.record(&json!({ "vault": vault }), || {
+                    println!("{}", vault.display())
+                })?;
             }
         }
     }
@@ -0,0 +1,62 @@
+use clap::Args;
+use serde_json::json;
+
+use super::Output;
+use crate::config::AppContext;
+use crate::core::links::LinkGraph;
+use crate::error::Error;
+
+#[derive(Args)]
+pub struct NoteArgs {
+    /// Note path relative to the vault (`Projects/alpha.md`) or link name (`alpha`).
+    pub note: String,
+}
+
+pub fn links(args: NoteArgs, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
+    let graph = LinkGraph::build(ctx.vault_root())?;
+    let note = find_note(&graph, &args.note)?;
+    let edges = graph.links(note);
+    out.list(&edges, || {
+        for edge in &edges {
+            println!("{edge}");
+        }
+    })
+}
+
+pub fn backlinks(args: NoteArgs, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
+    let graph = LinkGraph::build(ctx.vault_root())?;
+    let note = find_note(&graph, &args.note)?;
+    let edges = graph.backlinks(note);
+    out.list(&edges, || {
+        for edge in &edges {
+            println!("{}:{}: {}", edge.source, edge.link.line, edge.context);
+        }
+    })
+}
+
+pub fn orphans(ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
+    let graph = LinkGraph::build(ctx.vault_root())?;
+    let notes = graph.orphans();
+    let listed: Vec<_> = notes.iter().map(|note| json!({ "path": note })).collect();
+    out.list(&listed, || {
+        for note in &notes {
+            println!("{note}");
+        }
+    })
+}
+
+pub fn broken_links(ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
+    let graph = LinkGraph::build(ctx.vault_root())?;
+    let edges = graph.broken_links();
+    out.list(&edges, || {
+        for edge in &edges {
+            println!("{edge}");
+        }
+    })
+}
+
+fn find_note<'a>(graph: &'a LinkGraph, name: &str) -> anyhow::Result<&'a str> {
+    graph
+        .find_note(name)
+        .ok_or_else(|| Error::not_found(format!("note not found: {name}")).into())
+}
@@ -1,15 +1,30 @@
 mod config_cmd;
+mod links;
 mod note;
+mod output;
 mod search;
+mod tags;
 mod task;
+mod task_table;
+mod template;
 mod version;
 
+use std::process::ExitCode;
+
 use clap::{Parser, Subcommand};
 
+use crate::config::AppContext;
+use crate::error::ErrorKind;
+pub use output::{Output, OutputFormat};
+
 /// CLI entry point for obsctl.
 #[derive(Parser)]
 #[command(name = "obsctl", version, about = "Local AI knowledge and task CLI")]
 pub struct Cli {
+    /// Output format: human-readable table, bare plain values, JSON or
+    /// newline-delimited JSON.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
     #[command(subcommand)]
     command: Commands,
 }
@@ -23,32 +38,49 @@ enum Commands {
     #[command(subcommand)]
     Search(search::SearchCommand),
     #[command(subcommand)]
+    Tags(tags::TagsCommand),
+    #[command(subcommand)]
+    Template(template::TemplateCommand),
+    #[command(subcommand)]
     Config(config_cmd::ConfigCommand),
+    /// List the links going out of a note.
+    Links(links::NoteArgs),
+    /// List the notes linking to a note.
+    Backlinks(links::NoteArgs),
+    /// List notes that no other note links to.
+    Orphans,
+    /// List links whose note, heading or block does not exist.
+    BrokenLinks,
     /// Display version information.
     Version(version::VersionCommand),
 }
 
-/// Parse CLI args and execute the command.
-pub fn run() -> anyhow::Result<()> {
+/// Parse CLI args and execute the command, returning an exit code that
+/// reflects the kind of error (see [`ErrorKind::exit_code`]).
+pub fn run() -> ExitCode {
     let cli = Cli::parse();
-
-    match cli.command {
-        Commands::Version(cmd) => version::handle(cmd),
-        Commands::Note(cmd) => {
-            let ctx = crate::config::AppContext::load()?;
-            note::handle(cmd, &ctx)
-        }
-        Commands::Task(cmd) => {
-            let ctx = crate::config::AppContext::load()?;
-            task::handle(cmd, &ctx)
-        }
-        Commands::Search(cmd) => {
-            let ctx = crate::config::AppContext::load()?;
-            search::handle(cmd, &ctx)
-        }
-        Commands::Config(cmd) => {
-            let ctx = crate::config::AppContext::load()?;
-            config_cmd::handle(cmd, &ctx)
+    let out = Output::new(cli.format);
+    match execute(cli.command, &out) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            out.error(&err);
+            ExitCode::from(ErrorKind::of(&err).exit_code())
         }
     }
 }
+
+fn execute(command: Commands, out: &Output) -> anyhow::Result<()> {
+    match command {
+        Commands::Version(cmd) => version::handle(cmd, out),
+        Commands::Note(cmd) => note::handle(cmd, &AppContext::load()?, out),
+        Commands::Task(cmd) => task::handle(cmd, &AppContext::load()?, out),
+        Commands::Search(cmd) => search::handle(cmd, &AppContext::load()?, out),
+        Commands::Tags(cmd) => tags::handle(cmd, &AppContext::load()?, out),
+        Commands::Template(cmd) => template::handle(cmd, &AppContext::load()?, out),
+        Commands::Config(cmd) => config_cmd::handle(cmd, &AppContext::load_unchecked()?, out),
+        Commands::Links(args) => links::links(args, &AppContext::load()?, out),
+        Commands::Backlinks(args) => links::backlinks(args, &AppContext::load()?, out),
+        Commands::Orphans => links::orphans(&AppContext::load()?, out),
+        Commands::BrokenLinks => links::broken_links(&AppContext::load()?, out),
+    }
+}
@@ -1,7 +1,21 @@
-use clap::{Args, Subcommand};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
 
+use anyhow::Context;
+use chrono::{Local, NaiveDate};
+use clap::{ArgGroup, Args, Subcommand};
+use serde_json::json;
+
+use super::template::{self, TemplateVarArgs};
+use super::Output;
 use crate::config::AppContext;
+use crate::core::frontmatter::PropertyValue;
+use crate::core::periodic::Period;
 use crate::core::vault::VaultService;
+use crate::error::Error;
 
 #[derive(Subcommand)]
 pub enum NoteCommand {
@@ -11,6 +25,44 @@ pub enum NoteCommand {
     Open(NoteOpenArgs),
     /// List the most recent daily notes.
     List(NoteListArgs),
+    /// Create a note at a vault-relative path or title, optionally from a template.
+    New(NoteNewArgs),
+    /// Print a note's content.
+    Show(NoteNameArgs),
+    /// Open a note in `$VISUAL` / `$EDITOR`.
+    Edit(NoteNameArgs),
+    /// Rename or move a note, rewriting wikilinks that point to it.
+    Move(NoteMoveArgs),
+    /// Read or edit frontmatter properties of a note.
+    #[command(subcommand)]
+    Prop(PropCommand),
+}
+
+#[derive(Subcommand)]
+pub enum PropCommand {
+    /// Print a property value (one list item per line), or every property.
+    Get {
+        /// Note path relative to the vault, or its file name.
+        note: String,
+        key: Option<String>,
+    },
+    /// Set a property; several values (or `--list`) store a list.
+    Set {
+        /// Note path relative to the vault, or its file name.
+        note: String,
+        key: String,
+        #[arg(required = true)]
+        values: Vec<String>,
+        /// Store a list even when a single value is given.
+        #[arg(long)]
+        list: bool,
+    },
+    /// Remove a property.
+    Unset {
+        /// Note path relative to the vault, or its file name.
+        note: String,
+        key: String,
+    },
 }
 
 #[derive(Args)]
@@ -18,13 +70,96 @@ pub struct NoteAddArgs {
     /// Content to append into the daily note.
     #[arg(required = true)]
     pub entry: Vec<String>,
+    /// Insert under this heading (created if missing) instead of at the end.
+    #[arg(long)]
+    pub section: Option<String>,
+}
+
+#[derive(Args)]
+pub struct NoteNewArgs {
+    /// Vault-relative path or title, e.g. `Projects/alpha` or `Team sync`.
+    #[arg(required = true)]
+    pub name: Vec<String>,
+    /// Template name from `[templates.named.note]` or the vault's
+    /// `templates/` folder, e.g. `meeting`.
+    #[arg(long)]
+    pub template: Option<String>,
+    /// Open the new note in the editor.
+    #[arg(long)]
+    pub edit: bool,
+    #[command(flatten)]
+    pub vars: TemplateVarArgs,
+}
+
+#[derive(Args)]
+pub struct NoteNameArgs {
+    /// Note path relative to the vault, or its file name.
+    pub note: String,
+}
+
+#[derive(Args)]
+pub struct NoteMoveArgs {
+    /// Note path relative to the vault, or its file name.
+    pub note: String,
+    /// New vault-relative path or title; end with `/` to move into a folder.
+    pub destination: String,
 }
 
 #[derive(Args)]
+#[command(group(ArgGroup::new("period").args(["week", "month", "quarter", "year"])))]
+#[command(group(ArgGroup::new("step").args(["prev", "next", "offset"])))]
 pub struct NoteOpenArgs {
-    /// Optional ISO date (YYYY-MM-DD) to open.
+    /// Optional ISO date (YYYY-MM-DD) to open; defaults to today.
     #[arg(long)]
     pub date: Option<String>,
+    /// Open the weekly note instead of the daily note.
+    #[arg(long)]
+    pub week: bool,
+    /// Open the monthly note.
+    #[arg(long)]
+    pub month: bool,
+    /// Open the quarterly note.
+    #[arg(long)]
+    pub quarter: bool,
+    /// Open the yearly note.
+    #[arg(long)]
+    pub year: bool,
+    /// Open the previous period's note.
+    #[arg(long)]
+    pub prev: bool,
+    /// Open the next period's note.
+    #[arg(long)]
+    pub next: bool,
+    /// Move this many periods from the date, e.g. `--offset -2`.
+    #[arg(long, allow_hyphen_values = true)]
+    pub offset: Option<i64>,
+    /// Named template to create the note from if it does not exist yet.
+    #[arg(long)]
+    pub template: Option<String>,
+}
+
+impl NoteOpenArgs {
+    fn period(&self) -> Period {
+        if self.week {
+            Period::Week
+        } else if self.month {
+            Period::Month
+        } else if self.quarter {
+            Period::Quarter
+        } else if self.year {
+            Period::Year
+        } else {
+            Period::Day
+        }
+    }
+
+    fn offset(&self) -> i64 {
+        match (self.prev, self.next) {
+            (true, _) => -1,
+            (_, true) => 1,
+            _ => self.offset.unwrap_or(0),
+        }
+    }
 }
 
 #[derive(Args)]
@@ -34,23 +169,198 @@ pub struct NoteListArgs {
     pub limit: usize,
 }
 
-pub fn handle(cmd: NoteCommand, ctx: &AppContext) -> anyhow::Result<()> {
+pub fn handle(cmd: NoteCommand, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
     let service = VaultService::new(ctx)?;
     match cmd {
         NoteCommand::Add(args) => {
             let text = args.entry.join(" ");
-            service.append_today(&text)?;
-            println!("Appended to {}", service.today_path().display());
+            service.append_today(&text, args.section.as_deref())?;
+            let path = service.today_path();
+            out.record(&json!({ "path": path }), || {
+                if out.is_plain() {
+                    println!("{}", path.display());
+                } else {
+                    println!("Appended to {}", path.display());
+                }
+            })?;
         }
         NoteCommand::Open(args) => {
-            let path = service.path_for(args.date.as_deref())?;
-            println!("{}", path.display());
+            let date = match args.date.as_deref() {
+                Some(text) => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                    .with_context(|| format!("invalid date format: {text}"))?,
+                None => Local::now().date_naive(),
+            };
+            let period = args.period();
+            let date = period
+                .shift(date, args.offset())
+                .ok_or_else(|| Error::usage("date out of range"))?;
+            let path = service.periodic_note(period, date, args.template.as_deref())?;
+            let note = json!({
+                "path": path,
+                "period": period.to_string(),
+                "start": period.start(date),
+                "end": period.end(date),
+            });
+            out.record(&note, || println!("{}", path.display()))?;
         }
         NoteCommand::List(args) => {
-            for path in service.list_recent(args.limit)? {
-                println!("{}", path.display());
+            let notes = service.list_recent(args.limit)?;
+            out.list(&notes, || {
+                for note in &notes {
+                    println!("{}", note.path.display());
+                }
+            })?;
+        }
+        NoteCommand::New(args) => {
+            let service = VaultService::new(ctx)?
+                .with_variables(args.vars.into_map())
+                .with_prompt(template::prompt);
+            let path = service.create_note(&args.name.join(" "), args.template.as_deref())?;
+            out.record(&json!({ "path": path }), || {
+                if out.is_plain() {
+                    println!("{}", path.display());
+                } else {
+                    println!("Created {}", path.display());
+                }
+            })?;
+            if args.edit {
+                open_in_editor(&path)?;
             }
         }
+        NoteCommand::Show(args) => {
+            let path = service.resolve_note(&args.note)?;
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("read note {}", path.display()))?;
+            let properties: BTreeMap<_, _> = service
+                .read_document(&path)?
+                .properties()
+                .into_iter()
+                .collect();
+            let note = json!({ "path": path, "properties": properties, "content": content });
+            out.record(&note, || print!("{content}"))?;
+        }
+        NoteCommand::Edit(args) => {
+            let path = service.resolve_note(&args.note)?;
+            open_in_editor(&path)?;
+            out.record(&json!({ "path": path }), || {})?;
+        }
+        NoteCommand::Move(args) => {
+            let from = service.resolve_note(&args.note)?;
+            let moved = service.move_note(&from, &args.destination)?;
+            let result = json!({
+                "from": from,
+                "path": moved.path,
+                "links_updated": moved.links_updated,
+                "notes_updated": moved.notes_updated,
+            });
+            out.record(&result, || {
+                if out.is_plain() {
+                    println!("{}", moved.path.display());
+                    return;
+                }
+                println!(
+                    "Moved {} to {} ({} links updated in {} notes)",
+                    from.display(),
+                    moved.path.display(),
+                    moved.links_updated,
+                    moved.notes_updated
+                );
+            })?;
+        }
+        NoteCommand::Prop(cmd) => handle_prop(cmd, &service, out)?,
+    }
+    Ok(())
+}
+
+fn open_in_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors are often configured with arguments, e.g. `code --wait`.
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| Error::external("$EDITOR is empty"))?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| Error::external(format!("launch editor `{editor}`")))?;
+    if !status.success() {
+        anyhow::bail!(Error::external(format!(
+            "editor `{editor}` exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+fn handle_prop(cmd: PropCommand, service: &VaultService, out: &Output) -> anyhow::Result<()> {
+    match cmd {
+        PropCommand::Get { note, key } => {
+            let path = service.resolve_note(&note)?;
+            let document = service.read_document(&path)?;
+            let properties: Vec<(String, PropertyValue)> = match &key {
+                Some(key) => match document.get(key) {
+                    Some(value) => vec![(key.clone(), value)],
+                    None => anyhow::bail!(Error::not_found(format!(
+                        "property `{key}` is not set in {}",
+                        path.display()
+                    ))),
+                },
+                None => document.properties(),
+            };
+            let map: BTreeMap<_, _> = properties.iter().cloned().collect();
+            out.record(&json!({ "path": path, "properties": map }), || {
+                for (name, value) in &properties {
+                    match (&key, value) {
+                        (Some(_), PropertyValue::List(items)) => {
+                            for item in items {
+                                println!("{item}");
+                            }
+                        }
+                        (Some(_), value) => println!("{value}"),
+                        (None, value) => println!("{name}: {value}"),
+                    }
+                }
+            })?;
+        }
+        PropCommand::Set {
+            note,
+            key,
+            values,
+            list,
+        } => {
+            let path = service.resolve_note(&note)?;
+            let value = if list || values.len() > 1 {
+                PropertyValue::List(values)
+            } else {
+                PropertyValue::parse_scalar(&values[0])
+            };
+            service.set_property(&path, &key, &value)?;
+            out.record(&json!({ "path": path, "key": key, "value": value }), || {
+                if out.is_plain() {
+                    println!("{}", path.display());
+                } else {
+                    println!("Set `{key}` in {}", path.display());
+                }
+            })?;
+        }
+        PropCommand::Unset { note, key } => {
+            let path = service.resolve_note(&note)?;
+            let removed = service.unset_property(&path, &key)?;
+            out.record(
+                &json!({ "path": path, "key": key, "removed": removed }),
+                || {
+                    if out.is_plain() {
+                        println!("{}", path.display());
+                    } else if removed {
+                        println!("Removed `{key}` from {}", path.display());
+                    } else {
+                        println!("`{key}` was not set in {}", path.display());
+                    }
+                },
+            )?;
+        }
     }
     Ok(())
 }
@@ -0,0 +1,103 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::ErrorKind;
+
+/// How commands print their results, chosen with the global `--format`.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable output.
+    #[default]
+    Table,
+    /// Bare values (paths, ids, counts) without headers or messages.
+    Plain,
+    /// One pretty-printed JSON document.
+    Json,
+    /// One compact JSON object per line.
+    Ndjson,
+}
+
+/// Prints command results in the selected [`OutputFormat`]. Commands pass
+/// their serializable result together with a closure printing the text
+/// formats.
+pub struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.format == OutputFormat::Plain
+    }
+
+    pub fn is_structured(&self) -> bool {
+        matches!(self.format, OutputFormat::Json | OutputFormat::Ndjson)
+    }
+
+    /// Whether to color table output: stdout is a terminal and `NO_COLOR`
+    /// is unset or empty.
+    pub fn colors(&self) -> bool {
+        self.format == OutputFormat::Table
+            && io::stdout().is_terminal()
+            && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+    }
+
+    /// Print a single result.
+    pub fn record<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+        text: impl FnOnce(),
+    ) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Json => write_json(value, true),
+            OutputFormat::Ndjson => write_json(value, false),
+            OutputFormat::Table | OutputFormat::Plain => {
+                text();
+                Ok(())
+            }
+        }
+    }
+
+    /// Print a list of results: a JSON array, or one JSON line per item.
+    pub fn list<T: Serialize>(&self, items: &[T], text: impl FnOnce()) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Json => write_json(items, true),
+            OutputFormat::Ndjson => items.iter().try_for_each(|item| write_json(item, false)),
+            OutputFormat::Table | OutputFormat::Plain => {
+                text();
+                Ok(())
+            }
+        }
+    }
+
+    /// Report a failed command on stderr; JSON formats get
+    /// `{"error": {"kind": ..., "message": ...}}`.
+    pub fn error(&self, err: &anyhow::Error) {
+        if self.is_structured() {
+            let value = json!({
+                "error": { "kind": ErrorKind::of(err), "message": format!("{err:#}") }
+            });
+            eprintln!("{value}");
+        } else {
+            eprintln!("Error: {err:?}");
+        }
+    }
+}
+
+fn write_json<T: Serialize + ?Sized>(value: &T, pretty: bool) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    if pretty {
+        serde_json::to_writer_pretty(&mut stdout, value)?;
+    } else {
+        serde_json::to_writer(&mut stdout, value)?;
+    }
+    writeln!(stdout)?;
+    Ok(())
+}
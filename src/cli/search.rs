@@ -1,14 +1,19 @@
 use clap::{Args, Subcommand};
+use serde_json::json;
 
+use super::Output;
 use crate::config::AppContext;
-use crate::search::SearchService;
+use crate::core::frontmatter::PropertyFilter;
+use crate::search::{SearchBackend, SearchService, DEFAULT_LIMIT};
 
 #[derive(Subcommand)]
 pub enum SearchCommand {
-    /// Run a fast literal/regex search using ripgrep.
+    /// Run a ranked full-text search (or ripgrep, depending on `search.tool`).
     Grep(SearchArgs),
-    /// Fuzzy-find note paths using fzf.
+    /// Fuzzy-find note paths (in-process, or via fzf with the ripgrep backend).
     Fzf(SearchArgs),
+    /// Rebuild the native search index from scratch.
+    Reindex,
 }
 
 #[derive(Args)]
@@ -16,16 +21,58 @@ pub struct SearchArgs {
     /// Query string to search for.
     #[arg(required = true)]
     pub query: Vec<String>,
+    /// Maximum number of results to print (native backend only).
+    #[arg(long, default_value_t = DEFAULT_LIMIT)]
+    pub limit: usize,
+    /// Only match notes whose frontmatter has `key=value` (or just `key`); repeatable.
+    #[arg(long = "prop", value_name = "KEY[=VALUE]")]
+    pub props: Vec<PropertyFilter>,
 }
 
-pub fn handle(cmd: SearchCommand, ctx: &AppContext) -> anyhow::Result<()> {
-    let query = match &cmd {
-        SearchCommand::Grep(args) | SearchCommand::Fzf(args) => args.query.join(" "),
-    };
+pub fn handle(cmd: SearchCommand, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
     let service = SearchService::new(ctx)?;
     match cmd {
-        SearchCommand::Grep(_) => service.grep(&query)?,
-        SearchCommand::Fzf(_) => service.fuzzy(&query)?,
+        // Structured output needs the hits themselves, so it bypasses the
+        // interactive ripgrep/fzf passthrough.
+        SearchCommand::Grep(args) if out.is_structured() => {
+            let limit = match service.backend() {
+                SearchBackend::Native => args.limit,
+                SearchBackend::Ripgrep => usize::MAX,
+            };
+            let hits = service
+                .with_filters(args.props)
+                .search(&args.query.join(" "), limit)?;
+            out.list(&hits, || {})?;
+        }
+        SearchCommand::Fzf(args) if out.is_structured() => {
+            let paths: Vec<_> = service
+                .with_filters(args.props)
+                .fuzzy_paths(&args.query.join(" "), args.limit)?
+                .into_iter()
+                .map(|path| json!({ "path": path }))
+                .collect();
+            out.list(&paths, || {})?;
+        }
+        SearchCommand::Grep(args) => service
+            .with_filters(args.props)
+            .grep(&args.query.join(" "), args.limit)?,
+        SearchCommand::Fzf(args) => service
+            .with_filters(args.props)
+            .fuzzy(&args.query.join(" "), args.limit)?,
+        SearchCommand::Reindex => {
+            let backend = service.backend();
+            let stats = service.reindex()?;
+            out.record(&stats, || {
+                if out.is_plain() {
+                    println!("{}", stats.indexed);
+                    return;
+                }
+                if backend != SearchBackend::Native {
+                    println!("Note: search.tool is not `native`; the index is only used by the native backend.");
+                }
+                println!("Indexed {} notes", stats.indexed);
+            })?;
+        }
     }
     Ok(())
 }
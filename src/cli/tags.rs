@@ -0,0 +1,98 @@
+use clap::Subcommand;
+use serde_json::json;
+
+use super::Output;
+use crate::config::AppContext;
+use crate::core::tags;
+use crate::core::tasks::TaskService;
+use crate::search::SearchService;
+
+#[derive(Subcommand)]
+pub enum TagsCommand {
+    /// List every tag with its number of occurrences.
+    List,
+    /// Show the notes and tasks carrying a tag (including nested tags).
+    Show {
+        /// Tag to look up, with or without the leading `#`.
+        tag: String,
+    },
+    /// Rename a tag and the tags nested below it across the vault.
+    Rename {
+        /// Current tag name.
+        from: String,
+        /// New tag name.
+        to: String,
+    },
+}
+
+pub fn handle(cmd: TagsCommand, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
+    match cmd {
+        TagsCommand::List => {
+            let counts = SearchService::new(ctx)?.tag_counts()?;
+            out.list(&counts, || {
+                for entry in &counts {
+                    if out.is_plain() {
+                        println!("{}\t{}", entry.tag, entry.count);
+                    } else {
+                        println!("{:>5}  #{}", entry.count, entry.tag);
+                    }
+                }
+            })?;
+        }
+        TagsCommand::Show { tag } => {
+            let notes = SearchService::new(ctx)?.tagged(&tag)?;
+            let tasks: Vec<_> = TaskService::new(ctx)?
+                .tasks()?
+                .into_iter()
+                .filter(|task| task.tags.iter().any(|t| tags::matches(t, &tag)))
+                .collect();
+            let tag = tags::normalize(&tag);
+            let result = json!({ "tag": tag, "notes": notes, "tasks": tasks });
+            out.record(&result, || {
+                if out.is_plain() {
+                    for hit in &notes {
+                        println!("{}:{}: {}", hit.path, hit.line, hit.context);
+                    }
+                    for task in &tasks {
+                        println!("{}:{}: {}", task.source, task.line_number, task.raw);
+                    }
+                    return;
+                }
+                if notes.is_empty() && tasks.is_empty() {
+                    println!("No notes or tasks tagged #{tag}");
+                    return;
+                }
+                println!("## Notes");
+                for hit in &notes {
+                    println!("{}:{}: {}", hit.path, hit.line, hit.context);
+                }
+                println!();
+                println!("## Tasks");
+                for task in &tasks {
+                    println!("{}:{}: {}", task.source, task.line_number, task.raw);
+                }
+            })?;
+        }
+        TagsCommand::Rename { from, to } => {
+            let stats = tags::rename_tag(ctx.vault_root(), &from, &to)?;
+            let (from, to) = (tags::normalize(&from), tags::normalize(&to));
+            let result = json!({
+                "from": from,
+                "to": to,
+                "notes": stats.notes,
+                "occurrences": stats.occurrences,
+            });
+            out.record(&result, || {
+                if out.is_plain() {
+                    println!("{}", stats.occurrences);
+                } else {
+                    println!(
+                        "Renamed #{from} to #{to} ({} tags in {} notes)",
+                        stats.occurrences, stats.notes
+                    );
+                }
+            })?;
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,14 @@
+use chrono::{Local, NaiveDate};
 use clap::{Args, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
 
+use super::task_table::{self, TableOptions};
+use super::Output;
 use crate::config::AppContext;
-use crate::core::tasks::{TaskFilter, TaskService};
+use crate::core::tasks::{
+    normalize_id, NewTask, Priority, Recurrence, TaskEdit, TaskEntry, TaskQuery, TaskService,
+};
 
 #[derive(Subcommand)]
 pub enum TaskCommand {
@@ -9,10 +16,23 @@ pub enum TaskCommand {
     Add(TaskAddArgs),
     /// Mark an existing task as complete.
     Done(TaskDoneArgs),
+    /// Mark a completed task as open again.
+    Reopen(TaskDoneArgs),
+    /// Change a task's title, dates, priority, recurrence or tags.
+    Edit(TaskEditArgs),
+    /// Delete a task and its subtasks from its note.
+    #[command(alias = "remove")]
+    Rm(TaskDoneArgs),
+    /// Move a task with its subtasks to another note, optionally under a
+    /// heading.
+    Move(TaskMoveArgs),
     /// List tasks, optionally filtered by status.
     List(TaskListArgs),
     /// Remove completed tasks from the task list.
     Clean,
+    /// Write `🆔` ids to checkboxes without one across the vault and convert
+    /// legacy `(N)` task numbers.
+    MigrateIds,
 }
 
 #[derive(Args)]
@@ -22,26 +42,126 @@ pub struct TaskAddArgs {
     pub title: Vec<String>,
     /// Optional due date in YYYY-MM-DD format.
     #[arg(long)]
-    pub due: Option<String>,
-    /// Optional recurrence string (e.g., weekly).
+    pub due: Option<NaiveDate>,
+    /// Optional recurrence rule (e.g., "every 2 weeks on Monday", "every weekday").
     #[arg(long)]
-    pub repeat: Option<String>,
-    /// Optional priority marker (low, medium, high).
+    pub repeat: Option<Recurrence>,
+    /// Optional priority marker (lowest, low, medium, high, highest).
     #[arg(long, value_enum)]
     pub priority: Option<TaskPriority>,
+    /// Nest the task as a subtask of this task id, in the parent's note.
+    #[arg(long, value_name = "ID")]
+    pub parent: Option<String>,
 }
 
 #[derive(ValueEnum, Clone)]
 pub enum TaskPriority {
+    Lowest,
     Low,
     Medium,
     High,
+    Highest,
+}
+
+impl From<TaskPriority> for Priority {
+    fn from(value: TaskPriority) -> Self {
+        match value {
+            TaskPriority::Lowest => Priority::Lowest,
+            TaskPriority::Low => Priority::Low,
+            TaskPriority::Medium => Priority::Medium,
+            TaskPriority::High => Priority::High,
+            TaskPriority::Highest => Priority::Highest,
+        }
+    }
 }
 
 #[derive(Args)]
 pub struct TaskDoneArgs {
-    /// Task identifier to mark as done.
-    pub id: usize,
+    /// Task identifier (the `🆔` value, or a legacy number).
+    pub id: String,
+}
+
+#[derive(Args)]
+pub struct TaskEditArgs {
+    /// Task identifier (the `🆔` value, or a legacy number).
+    pub id: String,
+    /// New task description.
+    #[arg(long)]
+    pub title: Option<String>,
+    /// New due date in YYYY-MM-DD format.
+    #[arg(long, conflicts_with = "no_due")]
+    pub due: Option<NaiveDate>,
+    /// Remove the due date.
+    #[arg(long)]
+    pub no_due: bool,
+    /// New scheduled date in YYYY-MM-DD format.
+    #[arg(long, conflicts_with = "no_scheduled")]
+    pub scheduled: Option<NaiveDate>,
+    /// Remove the scheduled date.
+    #[arg(long)]
+    pub no_scheduled: bool,
+    /// New start date in YYYY-MM-DD format.
+    #[arg(long, conflicts_with = "no_start")]
+    pub start: Option<NaiveDate>,
+    /// Remove the start date.
+    #[arg(long)]
+    pub no_start: bool,
+    /// New priority (lowest, low, medium, high, highest).
+    #[arg(long, value_enum, conflicts_with = "no_priority")]
+    pub priority: Option<TaskPriority>,
+    /// Remove the priority marker.
+    #[arg(long)]
+    pub no_priority: bool,
+    /// New recurrence rule (e.g., "every week").
+    #[arg(long, conflicts_with = "no_repeat")]
+    pub repeat: Option<Recurrence>,
+    /// Stop the task from recurring.
+    #[arg(long)]
+    pub no_repeat: bool,
+    /// Add a tag (repeatable).
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// Remove a tag (repeatable).
+    #[arg(long = "untag", value_name = "TAG")]
+    pub untags: Vec<String>,
+}
+
+impl TaskEditArgs {
+    fn into_edit(self) -> TaskEdit {
+        TaskEdit {
+            title: self.title,
+            due: change(self.due, self.no_due),
+            scheduled: change(self.scheduled, self.no_scheduled),
+            start: change(self.start, self.no_start),
+            priority: change(self.priority.map(Priority::from), self.no_priority),
+            recurrence: change(
+                self.repeat.as_ref().map(Recurrence::to_string),
+                self.no_repeat,
+            ),
+            add_tags: self.tags,
+            remove_tags: self.untags,
+        }
+    }
+}
+
+/// `Some(None)` when `clear` is set, otherwise the new value if any.
+fn change<T>(value: Option<T>, clear: bool) -> Option<Option<T>> {
+    if clear {
+        Some(None)
+    } else {
+        value.map(Some)
+    }
+}
+
+#[derive(Args)]
+pub struct TaskMoveArgs {
+    /// Task identifier (the `🆔` value, or a legacy number).
+    pub id: String,
+    /// Destination note, relative to the vault root (`.md` optional).
+    pub note: String,
+    /// Heading to place the task under; created when missing.
+    #[arg(long)]
+    pub section: Option<String>,
 }
 
 #[derive(Args)]
@@ -49,6 +169,19 @@ pub struct TaskListArgs {
     /// Filter tasks by completion status.
     #[arg(long, value_enum)]
     pub status: Option<TaskStatus>,
+    /// Query expression, e.g. "due before today and priority >= high sort by due".
+    #[arg(long, short)]
+    pub query: Option<TaskQuery>,
+    /// Group tasks by due `bucket` (overdue, today, this week, later),
+    /// `project`, or any other `group by` field.
+    #[arg(long, value_name = "FIELD")]
+    pub group: Option<String>,
+    /// Show absolute due dates and source lines without truncating columns.
+    #[arg(long)]
+    pub wide: bool,
+    /// Show subtasks indented under their parent tasks.
+    #[arg(long)]
+    pub tree: bool,
 }
 
 #[derive(ValueEnum, Clone)]
@@ -57,39 +190,173 @@ pub enum TaskStatus {
     Done,
 }
 
-pub fn handle(cmd: TaskCommand, ctx: &AppContext) -> anyhow::Result<()> {
+/// A task in `task list` JSON output, tagged with its `group by` value and,
+/// with `--tree`, its depth below its listed parent.
+#[derive(Serialize)]
+struct ListedTask<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    depth: Option<usize>,
+    #[serde(flatten)]
+    task: &'a TaskEntry,
+}
+
+pub fn handle(cmd: TaskCommand, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
     let service = TaskService::new(ctx)?;
     match cmd {
         TaskCommand::Add(args) => {
-            let new_task = crate::core::tasks::NewTask {
+            let new_task = NewTask {
                 title: args.title.join(" "),
-                due_date: args.due.clone(),
-                recurrence: args.repeat.clone(),
-                priority: args.priority.map(|p| match p {
-                    TaskPriority::Low => crate::core::tasks::Priority::Low,
-                    TaskPriority::Medium => crate::core::tasks::Priority::Medium,
-                    TaskPriority::High => crate::core::tasks::Priority::High,
-                }),
+                due_date: args.due,
+                recurrence: args.repeat.as_ref().map(Recurrence::to_string),
+                priority: args.priority.map(Priority::from),
+                parent: args.parent,
             };
             let id = service.add_task(new_task)?;
-            println!("Added task #{id}");
+            out.record(&json!({ "id": id }), || {
+                if out.is_plain() {
+                    println!("{id}");
+                } else {
+                    println!("Added task #{id}");
+                }
+            })?;
         }
         TaskCommand::Done(args) => {
-            service.mark_done(args.id)?;
-            println!("Marked task #{} as done", args.id);
+            let id = normalize_id(&args.id);
+            let update = service.mark_done(&id)?;
+            let value = json!({
+                "id": id,
+                "next": update.next,
+                "completed_parents": update.completed_parents,
+            });
+            out.record(&value, || {
+                if out.is_plain() {
+                    println!("{id}");
+                    if let Some(next) = &update.next {
+                        println!("{}", next.id);
+                    }
+                    return;
+                }
+                println!("Marked task #{id} as done");
+                for parent in &update.completed_parents {
+                    println!("Completed parent task #{parent}");
+                }
+                if let Some(next) = &update.next {
+                    match next.due {
+                        Some(due) => println!("Created next occurrence #{} due {due}", next.id),
+                        None => println!("Created next occurrence #{}", next.id),
+                    }
+                }
+            })?;
+        }
+        TaskCommand::Reopen(args) => {
+            let id = normalize_id(&args.id);
+            service.set_status(&id, false)?;
+            out.record(&json!({ "id": id }), || {
+                if out.is_plain() {
+                    println!("{id}");
+                } else {
+                    println!("Reopened task #{id}");
+                }
+            })?;
+        }
+        TaskCommand::Edit(args) => {
+            let id = args.id.clone();
+            let task = service.edit_task(&id, &args.into_edit())?;
+            out.record(&task, || {
+                if out.is_plain() {
+                    println!("{}", task.id);
+                } else {
+                    println!("Updated task #{}: {}", task.id, task.raw.trim());
+                }
+            })?;
+        }
+        TaskCommand::Rm(args) => {
+            let task = service.delete_task(&args.id)?;
+            out.record(&task, || {
+                if out.is_plain() {
+                    println!("{}", task.id);
+                } else {
+                    println!("Deleted task #{} from {}", task.id, task.source);
+                }
+            })?;
+        }
+        TaskCommand::Move(args) => {
+            let task = service.move_task(&args.id, &args.note, args.section.as_deref())?;
+            out.record(&task, || {
+                if out.is_plain() {
+                    println!("{}", task.id);
+                } else {
+                    println!(
+                        "Moved task #{} to {}:{}",
+                        task.id, task.source, task.line_number
+                    );
+                }
+            })?;
         }
         TaskCommand::List(args) => {
-            let filter = match args.status {
-                Some(TaskStatus::Open) => TaskFilter::Open,
-                Some(TaskStatus::Done) => TaskFilter::Done,
-                None => TaskFilter::All,
-            };
-            for rendered in service.list_tasks(filter)? {
-                println!("{rendered}");
+            let mut query = args.query.unwrap_or_default();
+            match args.status {
+                Some(TaskStatus::Open) => query = query.with_status(false),
+                Some(TaskStatus::Done) => query = query.with_status(true),
+                None => {}
             }
+            if let Some(field) = &args.group {
+                query = query.with_group(field)?;
+            }
+            let groups = service.query(&query)?;
+            let listed: Vec<ListedTask> = groups
+                .iter()
+                .flat_map(|group| {
+                    task_table::ordered(&group.tasks, args.tree)
+                        .into_iter()
+                        .map(|(depth, task)| ListedTask {
+                            group: group.name.as_deref(),
+                            depth: args.tree.then_some(depth),
+                            task,
+                        })
+                })
+                .collect();
+            out.list(&listed, || {
+                if out.is_plain() {
+                    for listed in &listed {
+                        let task = listed.task;
+                        let status = if task.done { "done" } else { "open" };
+                        let due = task.due.map(|due| due.to_string()).unwrap_or_default();
+                        let indent = "  ".repeat(listed.depth.unwrap_or(0));
+                        println!("{}\t{status}\t{due}\t{indent}{}", task.id, task.title);
+                    }
+                    return;
+                }
+                let options = TableOptions {
+                    today: Local::now().date_naive(),
+                    wide: args.wide,
+                    tree: args.tree,
+                    colors: out.colors(),
+                };
+                task_table::print(&groups, &options);
+            })?;
         }
         TaskCommand::Clean => {
-            service.clean_completed()?;
+            let removed = service.clean_completed()?;
+            out.record(&json!({ "removed": removed }), || {
+                if out.is_plain() {
+                    println!("{removed}");
+                } else {
+                    println!("Removed {removed} completed tasks");
+                }
+            })?;
+        }
+        TaskCommand::MigrateIds => {
+            let migrated = service.migrate_ids()?;
+            out.record(&json!({ "migrated": migrated }), || {
+                if out.is_plain() {
+                    println!("{migrated}");
+                } else {
+                    println!("Migrated {migrated} tasks to 🆔 ids");
+                }
+            })?;
         }
     }
     Ok(())
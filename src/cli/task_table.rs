@@ -0,0 +1,262 @@
+use std::cmp::Ordering;
+use std::env;
+
+use anstyle::{AnsiColor, Style};
+use chrono::NaiveDate;
+
+use crate::core::tasks::{self, Priority, TaskEntry, TaskGroup};
+
+/// Terminal width assumed when `COLUMNS` is not set.
+const DEFAULT_WIDTH: usize = 100;
+/// Narrowest the title column shrinks to before rows are allowed to wrap.
+const MIN_TITLE_WIDTH: usize = 20;
+const GAP: &str = "  ";
+
+const HEADER: Style = Style::new().bold();
+const DIM: Style = Style::new().dimmed();
+const OVERDUE: Style = Style::new()
+    .fg_color(Some(anstyle::Color::Ansi(AnsiColor::Red)))
+    .bold();
+const TODAY: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Yellow)));
+const URGENT: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Red)));
+const MEDIUM: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Yellow)));
+
+/// How `task list` renders its table.
+pub struct TableOptions {
+    pub today: NaiveDate,
+    /// Show absolute dates and source lines, and never truncate.
+    pub wide: bool,
+    /// Indent subtasks under their parents.
+    pub tree: bool,
+    pub colors: bool,
+}
+
+/// A rendered cell: its text and the style applied when colors are on.
+struct Cell {
+    text: String,
+    style: Style,
+}
+
+impl Cell {
+    fn new(text: impl Into<String>, style: Style) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+const COLUMNS: [&str; 7] = ["", "ID", "PRI", "TITLE", "DUE", "REPEAT", "SOURCE"];
+const TITLE: usize = 3;
+const SOURCE: usize = 6;
+
+/// Print task groups as an aligned table with one header row; group names
+/// become `## name (count)` headings.
+pub fn print(groups: &[TaskGroup], options: &TableOptions) {
+    let rows: Vec<Vec<Vec<Cell>>> = groups
+        .iter()
+        .map(|group| {
+            ordered(&group.tasks, options.tree)
+                .into_iter()
+                .map(|(depth, task)| row(task, depth, options))
+                .collect()
+        })
+        .collect();
+    if rows.iter().all(Vec::is_empty) {
+        println!("No matching tasks");
+        return;
+    }
+
+    let mut widths: Vec<usize> = COLUMNS.iter().map(|name| width(name)).collect();
+    for cells in rows.iter().flatten() {
+        for (idx, cell) in cells.iter().enumerate() {
+            widths[idx] = widths[idx].max(width(&cell.text));
+        }
+    }
+    if !options.wide {
+        fit(&mut widths, terminal_width());
+    }
+
+    let header: Vec<Cell> = COLUMNS
+        .iter()
+        .map(|name| Cell::new(*name, HEADER))
+        .collect();
+    print_row(&header, &widths, options.colors);
+    for (idx, (group, cells)) in groups.iter().zip(&rows).enumerate() {
+        if let Some(name) = &group.name {
+            if idx > 0 {
+                println!();
+            }
+            let heading = format!("## {name} ({})", group.tasks.len());
+            println!("{}", paint(&heading, HEADER, options.colors));
+        }
+        for row in cells {
+            print_row(row, &widths, options.colors);
+        }
+    }
+}
+
+/// Tasks paired with their nesting depth: tree order when `tree` is set,
+/// otherwise as given and flat.
+pub fn ordered(tasks: &[TaskEntry], tree: bool) -> Vec<(usize, &TaskEntry)> {
+    if tree {
+        tasks::tree(tasks)
+    } else {
+        tasks.iter().map(|task| (0, task)).collect()
+    }
+}
+
+fn row(task: &TaskEntry, depth: usize, options: &TableOptions) -> Vec<Cell> {
+    let muted = if task.done { DIM } else { Style::new() };
+    let status = if task.done { "[x]" } else { "[ ]" };
+    let priority = match task.priority {
+        Some(priority @ (Priority::Highest | Priority::High)) => {
+            Cell::new(priority.label(), URGENT)
+        }
+        Some(Priority::Medium) => Cell::new("medium", MEDIUM),
+        Some(priority) => Cell::new(priority.label(), DIM),
+        None => Cell::new("", Style::new()),
+    };
+    let due = match task.due {
+        Some(due) => {
+            let mut text = relative_date(due, options.today, task.done);
+            if options.wide && text != due.to_string() {
+                text = format!("{text} ({due})");
+            }
+            let style = match due.cmp(&options.today) {
+                _ if task.done => DIM,
+                Ordering::Less => OVERDUE,
+                Ordering::Equal => TODAY,
+                Ordering::Greater => Style::new(),
+            };
+            Cell::new(text, style)
+        }
+        None => Cell::new("", Style::new()),
+    };
+    let mut title = format!("{}{}", "  ".repeat(depth), task.title);
+    if let Some(progress) = task.progress {
+        title.push_str(&format!(" ({progress})"));
+    }
+    let source = if options.wide {
+        format!("{}:{}", task.source, task.line_number)
+    } else {
+        task.source.clone()
+    };
+    vec![
+        Cell::new(status, muted),
+        Cell::new(task.id.clone(), DIM),
+        priority,
+        Cell::new(title, muted),
+        due,
+        Cell::new(task.recurrence.clone().unwrap_or_default(), muted),
+        Cell::new(source, DIM),
+    ]
+}
+
+/// Describe `date` relative to `today`: `today`, `in 3 days`, `2 weeks
+/// ago`, or `3 days overdue` for open tasks; far-off dates stay absolute.
+fn relative_date(date: NaiveDate, today: NaiveDate, done: bool) -> String {
+    let days = (date - today).num_days();
+    let amount = |n: i64| {
+        let n = n.abs();
+        match n {
+            1 => "1 day".to_string(),
+            2..=13 => format!("{n} days"),
+            _ => format!("{} weeks", n / 7),
+        }
+    };
+    match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 if done => "yesterday".to_string(),
+        2..=55 => format!("in {}", amount(days)),
+        -55..=-1 if done => format!("{} ago", amount(days)),
+        -55..=-1 => format!("{} overdue", amount(days)),
+        _ => date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// Shrink the title, then the source column so rows fit `total` columns.
+fn fit(widths: &mut [usize], total: usize) {
+    let used = |widths: &[usize]| widths.iter().sum::<usize>() + GAP.len() * (widths.len() - 1);
+    for (column, min) in [(TITLE, MIN_TITLE_WIDTH), (SOURCE, width(COLUMNS[SOURCE]))] {
+        let excess = used(widths).saturating_sub(total);
+        let room = widths[column].saturating_sub(min);
+        widths[column] -= excess.min(room);
+    }
+}
+
+fn print_row(cells: &[Cell], widths: &[usize], colors: bool) {
+    let last = cells.len() - 1;
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(idx, (cell, &width))| {
+            let text = truncate(&cell.text, width);
+            let pad = if idx == last {
+                String::new()
+            } else {
+                " ".repeat(width - self::width(&text))
+            };
+            format!("{}{pad}", paint(&text, cell.style, colors))
+        })
+        .collect();
+    println!("{}", line.join(GAP).trim_end());
+}
+
+fn paint(text: &str, style: Style, colors: bool) -> String {
+    if colors && !text.is_empty() {
+        format!("{style}{text}{style:#}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Cut `text` to `max` columns, marking the cut with `…`.
+fn truncate(text: &str, max: usize) -> String {
+    if width(text) <= max {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let w = char_width(ch);
+        if used + w + 1 > max {
+            break;
+        }
+        out.push(ch);
+        used += w;
+    }
+    out.push('…');
+    out
+}
+
+fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+fn width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Terminal columns taken by `ch`: two for CJK and emoji, zero for
+/// combining marks and variation selectors, one otherwise.
+fn char_width(ch: char) -> usize {
+    match ch as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1FAFF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use anyhow::Context;
+use chrono::{Local, NaiveDate};
+use clap::{Args, Subcommand};
+use serde::Serialize;
+use serde_json::json;
+
+use super::Output;
+use crate::config::AppContext;
+use crate::error::Error;
+use crate::templates::{self, TemplateContext, TemplateKind};
+
+#[derive(Subcommand)]
+pub enum TemplateCommand {
+    /// List the templates in `templates/` and the named templates from config.
+    List,
+    /// Render a template to stdout without creating a note.
+    Render(TemplateRenderArgs),
+    /// Create a new template in `templates/`.
+    New(TemplateNewArgs),
+}
+
+#[derive(Args)]
+pub struct TemplateRenderArgs {
+    /// Template name, e.g. `daily` or `meeting`.
+    pub name: String,
+    /// Value for `{{title}}`; defaults to the template name.
+    #[arg(long)]
+    pub title: Option<String>,
+    /// Date the note is for (YYYY-MM-DD); defaults to today.
+    #[arg(long)]
+    pub date: Option<String>,
+    #[command(flatten)]
+    pub vars: TemplateVarArgs,
+}
+
+#[derive(Args)]
+pub struct TemplateNewArgs {
+    /// Template name, e.g. `meeting` for `templates/meeting.md`.
+    pub name: String,
+}
+
+/// Template variables given on the command line.
+#[derive(Args)]
+pub struct TemplateVarArgs {
+    /// Set a template variable or answer a prompt, e.g. `--var project=alpha`.
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+}
+
+impl TemplateVarArgs {
+    pub fn into_map(self) -> BTreeMap<String, String> {
+        self.vars.into_iter().collect()
+    }
+}
+
+/// A template in `template list` output; named templates from config carry
+/// their kind and path.
+#[derive(Serialize)]
+struct ListedTemplate<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a str>,
+}
+
+pub fn handle(cmd: TemplateCommand, ctx: &AppContext, out: &Output) -> anyhow::Result<()> {
+    match cmd {
+        TemplateCommand::List => {
+            let names = templates::list_templates(ctx.vault_root())?;
+            let mut listed: Vec<_> = names
+                .iter()
+                .map(|name| ListedTemplate {
+                    name,
+                    kind: None,
+                    path: None,
+                })
+                .collect();
+            for (kind, named) in &ctx.config().templates.named {
+                listed.extend(named.iter().map(|(name, path)| ListedTemplate {
+                    name,
+                    kind: Some(kind),
+                    path: Some(path),
+                }));
+            }
+            out.list(&listed, || {
+                for entry in &listed {
+                    match (entry.kind, entry.path) {
+                        (Some(kind), Some(path)) if !out.is_plain() => {
+                            println!("{kind}:{} -> {path}", entry.name)
+                        }
+                        (Some(kind), _) => println!("{kind}:{}", entry.name),
+                        (None, _) => println!("{}", entry.name),
+                    }
+                }
+            })?;
+        }
+        TemplateCommand::Render(args) => {
+            let date = match args.date.as_deref() {
+                Some(text) => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                    .with_context(|| format!("invalid date format: {text}"))?,
+                None => Local::now().date_naive(),
+            };
+            let title = args.title.as_deref().unwrap_or(&args.name);
+            let template = templates::load(
+                ctx.config(),
+                ctx.vault_root(),
+                TemplateKind::Note,
+                Some(&args.name),
+            )?;
+            let context = TemplateContext::new(ctx.config(), ctx.vault_root(), date, title)
+                .with_variables(args.vars.into_map())
+                .with_prompt(&prompt);
+            let content = templates::render(&template, &context)?;
+            out.record(&json!({ "name": args.name, "content": content }), || {
+                print!("{content}")
+            })?;
+        }
+        TemplateCommand::New(args) => {
+            let path = templates::create_template(ctx.vault_root(), &args.name)?;
+            out.record(&json!({ "path": path }), || {
+                if out.is_plain() {
+                    println!("{}", path.display());
+                } else {
+                    println!("Created {}", path.display());
+                }
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Ask for a `{{prompt:...}}` value on the terminal.
+pub fn prompt(question: &str) -> anyhow::Result<String> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!(Error::usage(format!(
+            "template asks \"{question}\"; pass --var \"{question}=VALUE\""
+        )));
+    }
+    eprint!("{question}: ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn parse_var(text: &str) -> Result<(String, String), String> {
+    match text.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got `{text}`")),
+    }
+}
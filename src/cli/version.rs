@@ -1,9 +1,11 @@
 use clap::Args;
 use serde::Serialize;
 
+use super::Output;
+
 #[derive(Args)]
 pub struct VersionCommand {
-    /// Output version information as JSON.
+    /// Output version information as JSON (same as `--format json`).
     #[arg(long)]
     pub json: bool,
     /// Show additional build metadata when available.
@@ -23,7 +25,7 @@ struct VersionInfo<'a> {
     build_timestamp: Option<&'a str>,
 }
 
-pub fn handle(cmd: VersionCommand) -> anyhow::Result<()> {
+pub fn handle(cmd: VersionCommand, out: &Output) -> anyhow::Result<()> {
     let info = VersionInfo {
         name: env!("CARGO_PKG_NAME"),
         version: env!("CARGO_PKG_VERSION"),
@@ -32,23 +34,27 @@ pub fn handle(cmd: VersionCommand) -> anyhow::Result<()> {
         build_timestamp: option_env!("OBSCTL_BUILD_TS"),
     };
 
-    if cmd.json {
+    if cmd.json && !out.is_structured() {
         println!("{}", serde_json::to_string_pretty(&info)?);
         return Ok(());
     }
 
-    println!("{} {}", info.name, info.version);
-    if cmd.verbose {
-        if let Some(desc) = info.description {
-            println!("{desc}");
-        }
-        if let Some(commit) = info.git_commit {
-            println!("commit: {commit}");
+    out.record(&info, || {
+        if out.is_plain() {
+            println!("{}", info.version);
+            return;
         }
-        if let Some(ts) = info.build_timestamp {
-            println!("built: {ts}");
+        println!("{} {}", info.name, info.version);
+        if cmd.verbose {
+            if let Some(desc) = info.description {
+                println!("{desc}");
+            }
+            if let Some(commit) = info.git_commit {
+                println!("commit: {commit}");
+            }
+            if let Some(ts) = info.build_timestamp {
+                println!("built: {ts}");
+            }
         }
-    }
-
-    Ok(())
+    })
 }
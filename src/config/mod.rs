@@ -1,14 +1,21 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use chrono::format::{Item, StrftimeItems};
 use serde::{Deserialize, Serialize};
 
+use crate::core::periodic::Period;
+use crate::core::storage::{self, FileLock};
+use crate::core::walk;
+use crate::error::Error;
 use crate::templates;
 
 const ROOT_DIR_NAME: &str = ".obsctl";
 const CONFIG_FILE_NAME: &str = "config.toml";
+const DEFAULT_PERIODIC_FOLDER: &str = "Journal";
 
 #[derive(Debug, Clone)]
 pub struct AppContext {
@@ -18,8 +25,21 @@ pub struct AppContext {
 }
 
 impl AppContext {
+    /// Load the configuration and check that the templates it names exist.
     pub fn load() -> Result<Self> {
-        let config_path = default_config_path()?;
+        let ctx = Self::load_unchecked()?;
+        templates::validate(&ctx.config, &ctx.vault_root)?;
+        Ok(ctx)
+    }
+
+    /// Load the configuration without validating it, for commands that
+    /// repair it.
+    pub fn load_unchecked() -> Result<Self> {
+        Self::load_from(default_config_path()?)
+    }
+
+    /// Load (initialising if needed) the configuration stored at `config_path`.
+    pub fn load_from(config_path: PathBuf) -> Result<Self> {
         let manager = ConfigManager::new(config_path.clone());
         if !config_path.exists() {
             manager.ensure_initialized(None)?;
@@ -54,6 +74,24 @@ pub struct AppConfig {
     pub vault: VaultConfig,
     pub templates: TemplateConfig,
     pub search: SearchConfig,
+    #[serde(default)]
+    pub daily: PeriodicConfig,
+    #[serde(default)]
+    pub weekly: PeriodicConfig,
+    #[serde(default)]
+    pub monthly: PeriodicConfig,
+    #[serde(default)]
+    pub quarterly: PeriodicConfig,
+    #[serde(default)]
+    pub yearly: PeriodicConfig,
+    #[serde(default)]
+    pub append: AppendConfig,
+    #[serde(default)]
+    pub tasks: TasksConfig,
+    #[serde(default)]
+    pub rollover: RolloverConfig,
+    #[serde(default)]
+    pub mcp: McpConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -63,8 +101,175 @@ pub struct VaultConfig {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TemplateConfig {
+    /// Daily note template, relative to the vault or absolute. A
+    /// `[daily] template` takes precedence.
     pub daily: String,
+    /// Initial content of `Tasks/tasks.md`, relative to the vault or absolute.
     pub task: String,
+    /// Values for `{{name}}` placeholders in templates.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    /// Alternative templates per kind (`daily`, `weekly`, ..., `note`,
+    /// `task`), chosen with `--template NAME`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub named: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Where one kind of periodic note lives and how it is named, mirroring
+/// Obsidian's Daily Notes and Periodic Notes settings. Unset fields fall back
+/// to the defaults for that period.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PeriodicConfig {
+    /// Folder relative to the vault. Defaults to `Journal`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// chrono format for the file name without `.md`, plus `%q` for the
+    /// quarter; may contain `/` to nest notes, e.g. `%Y/%m/%Y-%m-%d %A`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Template path, relative to the vault or absolute. Defaults to
+    /// `templates/<daily|weekly|monthly|quarterly|yearly>.md`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl PeriodicConfig {
+    fn defaults(period: Period) -> Self {
+        Self {
+            folder: Some(DEFAULT_PERIODIC_FOLDER.to_string()),
+            format: Some(period.default_format().to_string()),
+            template: None,
+        }
+    }
+
+    pub fn folder(&self) -> &str {
+        self.folder.as_deref().unwrap_or(DEFAULT_PERIODIC_FOLDER)
+    }
+
+    pub fn format(&self, period: Period) -> &str {
+        self.format
+            .as_deref()
+            .unwrap_or_else(|| period.default_format())
+    }
+
+    /// Check that the folder stays inside the vault and the format names
+    /// exactly one `period`.
+    pub fn validate(&self, period: Period) -> Result<()> {
+        if !walk::is_relative_inside(Path::new(self.folder())) {
+            anyhow::bail!(Error::config(format!(
+                "[{}] folder `{}` must be inside the vault",
+                period.name(),
+                self.folder()
+            )));
+        }
+        period.validate_format(self.format(period))
+    }
+}
+
+/// How `note add` and the `append_daily_note` MCP tool write entries.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AppendConfig {
+    /// Heading entries go under when no section is given; the end of the
+    /// note when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// chrono time format prefixed to each entry, e.g. `%H:%M`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    /// List bullet written before each entry, e.g. `-` or `- [ ]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bullet: Option<String>,
+}
+
+impl AppendConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(timestamp) = &self.timestamp {
+            if StrftimeItems::new(timestamp).any(|item| matches!(item, Item::Error)) {
+                anyhow::bail!(Error::config(format!(
+                    "invalid [append] timestamp format `{timestamp}`"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Task behaviour shared by the CLI and the MCP server.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TasksConfig {
+    /// Complete a parent task once its last open subtask is done.
+    pub auto_complete_parents: bool,
+}
+
+/// Carrying open tasks from the previous daily note into a newly created
+/// note for today.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RolloverConfig {
+    pub enabled: bool,
+    pub mode: RolloverMode,
+    /// Headings whose open tasks roll over; every heading when empty.
+    pub sections: Vec<String>,
+    /// Heading the tasks go under in today's note. Unset keeps each task
+    /// under the heading it came from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl Default for RolloverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: RolloverMode::Copy,
+            sections: vec!["Tasks".to_string()],
+            target: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RolloverMode {
+    /// Copy the tasks and mark the originals as migrated (`[>]`).
+    Copy,
+    /// Remove the tasks from the previous note.
+    Move,
+}
+
+/// The HTTP transport of `obsctl_mcp`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct McpConfig {
+    /// Address to listen on unless `--bind` is given.
+    pub bind: String,
+    /// Bearer token HTTP clients must send. Unset, anyone who can reach the
+    /// address may connect.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<String>,
+    /// `Host` header values, such as `obsctl.lan:8765`, accepted besides
+    /// loopback names and the bound address. Others are refused so web
+    /// pages cannot reach the server through DNS rebinding. On a wildcard
+    /// address such as `0.0.0.0`, list the names and IPs clients use.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    /// Browser origins, such as `https://app.example.com`, allowed besides
+    /// loopback ones.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1:8765".to_string(),
+            auth_token: None,
+            allowed_hosts: Vec::new(),
+            allowed_origins: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -76,19 +281,40 @@ pub struct SearchConfig {
 impl AppConfig {
     fn new(vault_root: &Path) -> Self {
         let vault_str = vault_root.to_string_lossy().to_string();
-        let templates_dir = vault_root.join("templates");
         AppConfig {
             vault: VaultConfig {
                 path: vault_str.clone(),
             },
             templates: TemplateConfig {
-                daily: templates_dir.join("daily.md").to_string_lossy().to_string(),
-                task: templates_dir.join("task.md").to_string_lossy().to_string(),
+                daily: "templates/daily.md".to_string(),
+                task: "templates/task.md".to_string(),
+                variables: BTreeMap::new(),
+                named: BTreeMap::new(),
             },
             search: SearchConfig {
-                tool: "ripgrep".to_string(),
+                tool: "native".to_string(),
                 fzf_preview: true,
             },
+            daily: PeriodicConfig::defaults(Period::Day),
+            weekly: PeriodicConfig::defaults(Period::Week),
+            monthly: PeriodicConfig::defaults(Period::Month),
+            quarterly: PeriodicConfig::defaults(Period::Quarter),
+            yearly: PeriodicConfig::defaults(Period::Year),
+            append: AppendConfig::default(),
+            tasks: TasksConfig::default(),
+            rollover: RolloverConfig::default(),
+            mcp: McpConfig::default(),
+        }
+    }
+
+    /// Settings for one kind of periodic note.
+    pub fn periodic(&self, period: Period) -> &PeriodicConfig {
+        match period {
+            Period::Day => &self.daily,
+            Period::Week => &self.weekly,
+            Period::Month => &self.monthly,
+            Period::Quarter => &self.quarterly,
+            Period::Year => &self.yearly,
         }
     }
 }
@@ -112,11 +338,8 @@ impl ConfigManager {
 
     pub fn save(&self, config: &AppConfig) -> Result<()> {
         let text = toml::to_string_pretty(config)?;
-        if let Some(parent) = self.path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(&self.path, text)?;
-        Ok(())
+        let _lock = FileLock::acquire(&self.lock_path())?;
+        storage::write_atomic(&self.path, text)
     }
 
     pub fn ensure_initialized(&self, explicit_vault: Option<&Path>) -> Result<()> {
@@ -156,6 +379,12 @@ impl ConfigManager {
         Ok(())
     }
 
+    fn lock_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        self.path.with_file_name(name)
+    }
+
     fn ensure_directories(&self, vault_root: &Path) -> Result<()> {
         fs::create_dir_all(vault_root)?;
         for dir in ["Journal", "Tasks", "Projects", "templates"] {
@@ -163,7 +392,10 @@ impl ConfigManager {
         }
         let tasks_file = vault_root.join("Tasks/tasks.md");
         if !tasks_file.exists() {
-            fs::write(&tasks_file, "# Tasks\n\n")?;
+            let _lock = FileLock::vault(vault_root)?;
+            if !tasks_file.exists() {
+                storage::write_atomic(&tasks_file, "# Tasks\n\n")?;
+            }
         }
         Ok(())
     }
@@ -176,7 +408,9 @@ fn default_root_dir() -> Result<PathBuf> {
     if let Ok(profile) = env::var("USERPROFILE") {
         return Ok(PathBuf::from(profile).join(ROOT_DIR_NAME));
     }
-    anyhow::bail!("unable to locate home directory for obsctl config");
+    anyhow::bail!(Error::config(
+        "unable to locate home directory for obsctl config"
+    ));
 }
 
 fn default_config_path() -> Result<PathBuf> {
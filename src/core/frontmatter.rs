@@ -0,0 +1,502 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Serializer};
+
+const DELIMITER: &str = "---";
+
+/// A note split into its YAML frontmatter and Markdown body. Properties are
+/// kept as their original lines so untouched keys, comments and ordering
+/// survive a round trip; only edited properties are re-rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    frontmatter: Option<Frontmatter>,
+    body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Frontmatter {
+    items: Vec<Item>,
+    closing: String,
+    closing_newline: bool,
+}
+
+/// A top-level property with its continuation lines, or a comment/blank line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    key: Option<String>,
+    lines: Vec<String>,
+}
+
+/// A property value as Obsidian understands it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyValue {
+    Null,
+    Bool(bool),
+    /// Numbers keep their original spelling.
+    Number(String),
+    Text(String),
+    List(Vec<String>),
+    /// Nested YAML that obsctl does not interpret, as written.
+    Raw(String),
+}
+
+impl Document {
+    pub fn parse(text: &str) -> Self {
+        let no_frontmatter = || Self {
+            frontmatter: None,
+            body: text.to_string(),
+        };
+        let mut lines = text.split_inclusive('\n');
+        match lines.next() {
+            Some(first) if first.trim_end() == DELIMITER => {}
+            _ => return no_frontmatter(),
+        }
+        let mut offset = text.find('\n').map(|i| i + 1).unwrap_or(text.len());
+        let mut raw = Vec::new();
+        for line in lines {
+            offset += line.len();
+            let content = line.trim_end_matches(['\n', '\r']);
+            if content == DELIMITER || content == "..." {
+                return Self {
+                    frontmatter: Some(Frontmatter {
+                        items: group_items(raw),
+                        closing: content.to_string(),
+                        closing_newline: line.ends_with('\n'),
+                    }),
+                    body: text[offset..].to_string(),
+                };
+            }
+            raw.push(content.to_string());
+        }
+        no_frontmatter()
+    }
+
+    pub fn render(&self) -> String {
+        let Some(frontmatter) = &self.frontmatter else {
+            return self.body.clone();
+        };
+        let mut out = format!("{DELIMITER}\n");
+        for line in frontmatter.items.iter().flat_map(|item| &item.lines) {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str(&frontmatter.closing);
+        if frontmatter.closing_newline {
+            out.push('\n');
+        }
+        out.push_str(&self.body);
+        out
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn set_body(&mut self, body: String) {
+        self.body = body;
+    }
+
+    pub fn has_frontmatter(&self) -> bool {
+        self.frontmatter.is_some()
+    }
+
+    /// Property keys and values in frontmatter order.
+    pub fn properties(&self) -> Vec<(String, PropertyValue)> {
+        self.items()
+            .filter_map(|item| Some((item.key.clone()?, item.value())))
+            .collect()
+    }
+
+    /// Look up a property; keys match case-insensitively like in Obsidian.
+    pub fn get(&self, key: &str) -> Option<PropertyValue> {
+        self.find(key).map(Item::value)
+    }
+
+    /// Insert or replace a property. New properties go after existing ones,
+    /// and a frontmatter block is created when the note has none.
+    pub fn set(&mut self, key: &str, value: &PropertyValue) {
+        let item = Item {
+            key: Some(key.to_string()),
+            lines: value.render(key),
+        };
+        let frontmatter = self.frontmatter.get_or_insert_with(|| Frontmatter {
+            items: Vec::new(),
+            closing: DELIMITER.to_string(),
+            closing_newline: true,
+        });
+        match frontmatter
+            .items
+            .iter_mut()
+            .find(|existing| existing.has_key(key))
+        {
+            Some(existing) => *existing = item,
+            None => frontmatter.items.push(item),
+        }
+    }
+
+    /// Remove a property, returning whether it existed. An emptied
+    /// frontmatter block is removed entirely.
+    pub fn unset(&mut self, key: &str) -> bool {
+        let Some(frontmatter) = &mut self.frontmatter else {
+            return false;
+        };
+        let before = frontmatter.items.len();
+        frontmatter.items.retain(|item| !item.has_key(key));
+        let removed = frontmatter.items.len() != before;
+        if frontmatter
+            .items
+            .iter()
+            .all(|item| item.lines.iter().all(|l| l.trim().is_empty()))
+        {
+            self.frontmatter = None;
+        }
+        removed
+    }
+
+    fn items(&self) -> impl Iterator<Item = &Item> {
+        self.frontmatter.iter().flat_map(|fm| fm.items.iter())
+    }
+
+    fn find(&self, key: &str) -> Option<&Item> {
+        self.items().find(|item| item.has_key(key))
+    }
+}
+
+impl Item {
+    fn has_key(&self, key: &str) -> bool {
+        self.key
+            .as_deref()
+            .map(|k| k.eq_ignore_ascii_case(key))
+            .unwrap_or(false)
+    }
+
+    fn value(&self) -> PropertyValue {
+        let Some((first, rest)) = self.lines.split_first() else {
+            return PropertyValue::Null;
+        };
+        let inline = first
+            .split_once(':')
+            .map(|(_, value)| strip_comment(value).trim())
+            .unwrap_or("");
+        let continuation: Vec<&str> = rest
+            .iter()
+            .map(String::as_str)
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+
+        if inline.is_empty() {
+            if continuation.is_empty() {
+                return PropertyValue::Null;
+            }
+            let items: Option<Vec<String>> = continuation
+                .iter()
+                .map(|line| {
+                    let line = line.trim();
+                    line.strip_prefix('-')
+                        .filter(|item| item.is_empty() || item.starts_with(' '))
+                        .map(|item| scalar_text(strip_comment(item).trim()))
+                })
+                .collect();
+            return match items {
+                Some(items) => PropertyValue::List(items),
+                None => PropertyValue::Raw(dedent(&continuation).join("\n")),
+            };
+        }
+        if matches!(inline.chars().next(), Some('|' | '>')) {
+            let lines = dedent(&continuation);
+            let separator = if inline.starts_with('>') { " " } else { "\n" };
+            return PropertyValue::Text(lines.join(separator));
+        }
+        if let Some(inner) = inline.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            return PropertyValue::List(
+                split_flow(inner)
+                    .iter()
+                    .map(|item| scalar_text(item.trim()))
+                    .filter(|item| !item.is_empty())
+                    .collect(),
+            );
+        }
+        if !continuation.is_empty() {
+            // Plain scalars may wrap onto indented lines.
+            let mut text = vec![inline.to_string()];
+            text.extend(continuation.iter().map(|line| line.trim().to_string()));
+            return PropertyValue::Text(text.join(" "));
+        }
+        PropertyValue::parse_scalar(inline)
+    }
+}
+
+impl PropertyValue {
+    /// Interpret a single YAML scalar (or a value typed on the command line).
+    pub fn parse_scalar(text: &str) -> Self {
+        let text = text.trim();
+        if text.starts_with('"') || text.starts_with('\'') {
+            return PropertyValue::Text(unquote(text));
+        }
+        match text.to_lowercase().as_str() {
+            "" | "~" | "null" => PropertyValue::Null,
+            "true" => PropertyValue::Bool(true),
+            "false" => PropertyValue::Bool(false),
+            _ if is_number(text) => PropertyValue::Number(text.to_string()),
+            _ => PropertyValue::Text(text.to_string()),
+        }
+    }
+
+    /// Whether the value equals `wanted` (case-insensitively), or contains it
+    /// for lists.
+    pub fn matches(&self, wanted: &str) -> bool {
+        let eq = |value: &str| value.eq_ignore_ascii_case(wanted.trim());
+        match self {
+            PropertyValue::List(items) => items.iter().any(|item| eq(item)),
+            PropertyValue::Null => wanted.trim().is_empty(),
+            other => eq(&other.to_string()),
+        }
+    }
+
+    fn render(&self, key: &str) -> Vec<String> {
+        let key = if needs_quotes(key) {
+            quote(key)
+        } else {
+            key.to_string()
+        };
+        match self {
+            PropertyValue::Null => vec![format!("{key}:")],
+            PropertyValue::Bool(value) => vec![format!("{key}: {value}")],
+            PropertyValue::Number(value) => vec![format!("{key}: {value}")],
+            PropertyValue::Text(value) => vec![format!("{key}: {}", render_text(value))],
+            PropertyValue::List(items) if items.is_empty() => vec![format!("{key}: []")],
+            PropertyValue::List(items) => std::iter::once(format!("{key}:"))
+                .chain(
+                    items
+                        .iter()
+                        .map(|item| format!("  - {}", render_text(item))),
+                )
+                .collect(),
+            PropertyValue::Raw(raw) => std::iter::once(format!("{key}:"))
+                .chain(raw.lines().map(|line| format!("  {line}")))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::Null => Ok(()),
+            PropertyValue::Bool(value) => write!(f, "{value}"),
+            PropertyValue::Number(value) | PropertyValue::Text(value) => f.write_str(value),
+            PropertyValue::List(items) => f.write_str(&items.join(", ")),
+            PropertyValue::Raw(raw) => f.write_str(raw),
+        }
+    }
+}
+
+/// Serializes as the matching JSON value; numbers JSON cannot represent and
+/// nested YAML are kept as strings.
+impl Serialize for PropertyValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PropertyValue::Null => serializer.serialize_none(),
+            PropertyValue::Bool(value) => serializer.serialize_bool(*value),
+            PropertyValue::Number(value) => match (value.parse::<i64>(), value.parse::<f64>()) {
+                (Ok(number), _) => serializer.serialize_i64(number),
+                (_, Ok(number)) if number.is_finite() => serializer.serialize_f64(number),
+                _ => serializer.serialize_str(value),
+            },
+            PropertyValue::Text(value) | PropertyValue::Raw(value) => {
+                serializer.serialize_str(value)
+            }
+            PropertyValue::List(items) => items.serialize(serializer),
+        }
+    }
+}
+
+/// A `key=value` (or bare `key`) condition on note properties, as accepted
+/// by `search grep --prop`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyFilter {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl PropertyFilter {
+    pub fn parse(text: &str) -> Result<Self> {
+        let (key, value) = match text.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (text.trim(), None),
+        };
+        if key.is_empty() {
+            return Err(anyhow!("property filter needs a key: {text:?}"));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value,
+        })
+    }
+
+    /// A bare key matches any non-empty value; `key=value` matches equal
+    /// values or list members.
+    pub fn matches(&self, document: &Document) -> bool {
+        match (document.get(&self.key), &self.value) {
+            (None, _) => false,
+            (Some(value), None) => value != PropertyValue::Null,
+            (Some(value), Some(wanted)) => value.matches(wanted),
+        }
+    }
+}
+
+impl FromStr for PropertyFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        PropertyFilter::parse(s)
+    }
+}
+
+/// Group raw frontmatter lines into top-level properties with their indented
+/// continuation lines; comments and blank lines stand alone.
+fn group_items(lines: Vec<String>) -> Vec<Item> {
+    let mut items: Vec<Item> = Vec::new();
+    for line in lines {
+        let top_level = !line.starts_with([' ', '\t', '-', '#']) && !line.trim().is_empty();
+        let continues = !top_level
+            && !line.trim().is_empty()
+            && !line.starts_with('#')
+            && items.last().map(|i| i.key.is_some()).unwrap_or(false);
+        if continues {
+            if let Some(last) = items.last_mut() {
+                last.lines.push(line);
+            }
+            continue;
+        }
+        let key = top_level
+            .then(|| line.split_once(':'))
+            .flatten()
+            .map(|(key, _)| unquote(key.trim()));
+        items.push(Item {
+            key,
+            lines: vec![line],
+        });
+    }
+    items
+}
+
+/// Drop a trailing ` # comment` outside quotes.
+fn strip_comment(value: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (idx, c) in value.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => return &value[..idx],
+            _ => {}
+        }
+        prev = c;
+    }
+    value
+}
+
+/// Split a flow sequence body (`a, "b, c", d`) on commas outside quotes.
+fn split_flow(inner: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for c in inner.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (Some(q), c) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            (None, ',') => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+fn scalar_text(text: &str) -> String {
+    PropertyValue::parse_scalar(text).to_string()
+}
+
+fn dedent(lines: &[&str]) -> Vec<String> {
+    let indent = lines
+        .iter()
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line[indent..].to_string())
+        .collect()
+}
+
+fn unquote(text: &str) -> String {
+    if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        let mut out = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some(other) => out.push(other),
+                    None => out.push('\\'),
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        return out;
+    }
+    if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return inner.replace("''", "'");
+    }
+    text.to_string()
+}
+
+fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+fn render_text(text: &str) -> String {
+    if needs_quotes(text) {
+        quote(text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Whether a string must be quoted to stay a YAML string.
+fn needs_quotes(text: &str) -> bool {
+    text.is_empty()
+        || text.trim() != text
+        || text.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`',
+        ])
+        || text.contains(": ")
+        || text.contains(" #")
+        || text.ends_with(':')
+        || text.contains('\n')
+        || !matches!(PropertyValue::parse_scalar(text), PropertyValue::Text(_))
+}
+
+fn is_number(text: &str) -> bool {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    !digits.is_empty()
+        && digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1
+}
@@ -0,0 +1,240 @@
+mod parse;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::core::walk;
+
+pub use parse::{extract_links, line_links, Link};
+
+/// Forward and backward `[[wikilink]]` graph of every note in the vault
+/// (excluding the templates folder).
+pub struct LinkGraph {
+    notes: Vec<Note>,
+    /// Vault files (notes and attachments) keyed by lowercased file name.
+    by_name: HashMap<String, Vec<String>>,
+    edges: Vec<ResolvedLink>,
+}
+
+struct Note {
+    path: String,
+    headings: HashSet<String>,
+    blocks: HashSet<String>,
+}
+
+/// A link together with the note it appears in and where it points.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedLink {
+    /// Note containing the link, relative to the vault root.
+    pub source: String,
+    pub link: Link,
+    /// Resolved file, relative to the vault root.
+    pub target: Option<String>,
+    pub problem: Option<LinkProblem>,
+    /// The trimmed line the link appears on.
+    pub context: String,
+}
+
+/// Renders as `source:line: [[link]] -> target`, or with the problem for
+/// broken links.
+impl fmt::Display for ResolvedLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.source, self.link.line, self.link)?;
+        match (&self.target, self.problem) {
+            (_, Some(problem)) => write!(f, " ({problem})"),
+            (Some(target), None) => write!(f, " -> {target}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkProblem {
+    MissingNote,
+    MissingHeading,
+    MissingBlock,
+}
+
+impl fmt::Display for LinkProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LinkProblem::MissingNote => "missing note",
+            LinkProblem::MissingHeading => "missing heading",
+            LinkProblem::MissingBlock => "missing block",
+        })
+    }
+}
+
+impl LinkGraph {
+    pub fn build(root: &Path) -> Result<Self> {
+        let templates_dir = root.join("templates");
+        let mut notes = Vec::new();
+        let mut sources = Vec::new();
+        let mut by_name: HashMap<String, Vec<String>> = HashMap::new();
+        for path in walk::vault_files(root)? {
+            if path.starts_with(&templates_dir) {
+                continue;
+            }
+            let relative = walk::relative_path(root, &path);
+            let name = relative
+                .rsplit('/')
+                .next()
+                .unwrap_or(&relative)
+                .to_lowercase();
+            by_name.entry(name).or_default().push(relative.clone());
+            if !walk::is_markdown(&path) {
+                continue;
+            }
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("read note {}", path.display()))?;
+            notes.push(Note {
+                path: relative.clone(),
+                headings: parse::headings(&text)
+                    .iter()
+                    .map(|h| parse::heading_key(h))
+                    .collect(),
+                blocks: parse::block_ids(&text).into_iter().collect(),
+            });
+            sources.push((relative, text));
+        }
+
+        let mut graph = Self {
+            notes,
+            by_name,
+            edges: Vec::new(),
+        };
+        for (source, text) in &sources {
+            let lines: Vec<&str> = text.lines().collect();
+            for link in extract_links(text) {
+                let target = graph.resolve(source, &link.target).map(str::to_string);
+                let problem = graph.problem(&link, target.as_deref());
+                graph.edges.push(ResolvedLink {
+                    source: source.clone(),
+                    context: lines[link.line - 1].trim().to_string(),
+                    link,
+                    target,
+                    problem,
+                });
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Find a note by vault-relative path (with or without `.md`) or by the
+    /// name used in links.
+    pub fn find_note(&self, name: &str) -> Option<&str> {
+        self.resolve("", name.trim())
+            .filter(|path| walk::is_markdown(Path::new(path)))
+    }
+
+    /// Resolve a link target as written in `source` to a vault file,
+    /// preferring exact paths, then files in the same folder, then the
+    /// shortest path.
+    pub fn resolve(&self, source: &str, target: &str) -> Option<&str> {
+        if target.is_empty() {
+            return self
+                .notes
+                .iter()
+                .find(|n| n.path == source)
+                .map(|n| n.path.as_str());
+        }
+        let wanted = target
+            .trim_start_matches('/')
+            .replace('\\', "/")
+            .to_lowercase();
+        let folder = source.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        for wanted in [format!("{wanted}.md"), wanted] {
+            let name = wanted.rsplit('/').next().unwrap_or(&wanted);
+            let Some(candidates) = self.by_name.get(name) else {
+                continue;
+            };
+            let suffix = format!("/{wanted}");
+            let best = candidates
+                .iter()
+                .filter(|path| {
+                    let lower = path.to_lowercase();
+                    lower == wanted || lower.ends_with(&suffix)
+                })
+                .min_by_key(|path| {
+                    let in_folder = path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+                    (
+                        path.to_lowercase() != wanted,
+                        in_folder != folder,
+                        path.len(),
+                    )
+                });
+            if let Some(best) = best {
+                return Some(best.as_str());
+            }
+        }
+        None
+    }
+
+    /// Links going out of `note`, in document order.
+    pub fn links(&self, note: &str) -> Vec<&ResolvedLink> {
+        self.edges.iter().filter(|e| e.source == note).collect()
+    }
+
+    /// Links in other notes pointing at `note`, one per linking line.
+    pub fn backlinks(&self, note: &str) -> Vec<&ResolvedLink> {
+        let mut edges: Vec<&ResolvedLink> = self
+            .edges
+            .iter()
+            .filter(|e| e.source != note && e.target.as_deref() == Some(note))
+            .collect();
+        edges.dedup_by(|a, b| a.source == b.source && a.link.line == b.link.line);
+        edges
+    }
+
+    /// Every link resolving to `note`, including links inside the note itself.
+    pub fn backlinks_all(&self, note: &str) -> Vec<&ResolvedLink> {
+        self.edges
+            .iter()
+            .filter(|e| e.target.as_deref() == Some(note))
+            .collect()
+    }
+
+    /// Notes that no other note links to.
+    pub fn orphans(&self) -> Vec<&str> {
+        let linked: HashSet<&str> = self
+            .edges
+            .iter()
+            .filter_map(|e| e.target.as_deref().filter(|target| *target != e.source))
+            .collect();
+        self.notes
+            .iter()
+            .map(|n| n.path.as_str())
+            .filter(|path| !linked.contains(path))
+            .collect()
+    }
+
+    /// Links whose note, heading or block does not exist.
+    pub fn broken_links(&self) -> Vec<&ResolvedLink> {
+        self.edges.iter().filter(|e| e.problem.is_some()).collect()
+    }
+
+    fn problem(&self, link: &Link, target: Option<&str>) -> Option<LinkProblem> {
+        let Some(target) = target else {
+            return Some(LinkProblem::MissingNote);
+        };
+        let note = self.notes.iter().find(|n| n.path == target)?;
+        if let Some(heading) = &link.heading {
+            let last = heading.rsplit('#').next().unwrap_or(heading);
+            if !note.headings.contains(&parse::heading_key(last)) {
+                return Some(LinkProblem::MissingHeading);
+            }
+        }
+        if let Some(block) = &link.block {
+            if !note.blocks.contains(block) {
+                return Some(LinkProblem::MissingBlock);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::core::markdown::{self, FenceTracker};
+
+/// A `[[wikilink]]` or `![[embed]]` found in a note, e.g.
+/// `[[Projects/alpha#Goals|the goals]]` or `![[diagram.png]]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Link {
+    /// Linked note or file as written; empty for references within the same
+    /// note such as `[[#Heading]]`.
+    pub target: String,
+    pub heading: Option<String>,
+    pub block: Option<String>,
+    pub alias: Option<String>,
+    pub embed: bool,
+    /// 1-based line number of the link.
+    pub line: usize,
+    /// Byte range of the whole link (including `!` and brackets) in its line.
+    #[serde(skip)]
+    pub span: Range<usize>,
+}
+
+impl Link {
+    fn parse_inner(inner: &str, embed: bool) -> Option<Self> {
+        let (target, alias) = match inner.split_once('|') {
+            // `\|` escapes the pipe inside Markdown tables.
+            Some((target, alias)) => (target.trim_end_matches('\\'), Some(alias.trim())),
+            None => (inner, None),
+        };
+        let (note, anchor) = match target.split_once('#') {
+            Some((note, anchor)) => (note, Some(anchor.trim())),
+            None => (target, None),
+        };
+        let (heading, block) = match anchor {
+            Some(anchor) => match anchor.strip_prefix('^') {
+                Some(block) => (None, Some(block.to_string())),
+                None => (Some(anchor.to_string()), None),
+            },
+            None => (None, None),
+        };
+        let target = note.trim().to_string();
+        if target.is_empty() && heading.is_none() && block.is_none() {
+            return None;
+        }
+        Some(Self {
+            target,
+            heading: heading.filter(|h| !h.is_empty()),
+            block: block.filter(|b| !b.is_empty()),
+            alias: alias.filter(|a| !a.is_empty()).map(str::to_string),
+            embed,
+            line: 0,
+            span: 0..0,
+        })
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.embed {
+            f.write_str("!")?;
+        }
+        write!(f, "[[{}", self.target)?;
+        if let Some(heading) = &self.heading {
+            write!(f, "#{heading}")?;
+        }
+        if let Some(block) = &self.block {
+            write!(f, "#^{block}")?;
+        }
+        if let Some(alias) = &self.alias {
+            write!(f, "|{alias}")?;
+        }
+        f.write_str("]]")
+    }
+}
+
+/// Every link in a note, skipping fenced code blocks and inline code spans.
+pub fn extract_links(text: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    for (idx, line) in prose_lines(text) {
+        links.extend(line_links(line, idx + 1));
+    }
+    links
+}
+
+/// Links within a single line.
+pub fn line_links(line: &str, line_number: usize) -> Vec<Link> {
+    let bytes = line.as_bytes();
+    let mut links = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let fence = &line[i..i + run];
+                i += run;
+                if let Some(end) = line[i..].find(fence) {
+                    i += end + run;
+                }
+            }
+            b'[' if bytes.get(i + 1) == Some(&b'[') => {
+                let embed = i > 0 && bytes[i - 1] == b'!';
+                let inner_start = i + 2;
+                let Some(len) = line[inner_start..].find("]]") else {
+                    break;
+                };
+                let end = inner_start + len + 2;
+                if let Some(mut link) = Link::parse_inner(&line[inner_start..end - 2], embed) {
+                    link.line = line_number;
+                    link.span = if embed { i - 1 } else { i }..end;
+                    links.push(link);
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    links
+}
+
+/// Headings (`# Title` through `###### Title`) outside code blocks.
+pub fn headings(text: &str) -> Vec<String> {
+    prose_lines(text)
+        .filter(|(_, line)| markdown::is_heading(line))
+        .map(|(_, line)| {
+            line.trim_start_matches('#')
+                .trim()
+                .trim_end_matches('#')
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Block ids declared with a trailing `^id` marker.
+pub fn block_ids(text: &str) -> Vec<String> {
+    prose_lines(text)
+        .filter_map(|(_, line)| {
+            let last = line.split_whitespace().last()?;
+            let id = last.strip_prefix('^')?;
+            (!id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+                .then(|| id.to_string())
+        })
+        .collect()
+}
+
+/// Normalize a heading the way Obsidian matches `[[note#Heading]]`:
+/// case-insensitive, with link syntax characters treated as spaces.
+pub fn heading_key(heading: &str) -> String {
+    heading
+        .chars()
+        .map(|c| match c {
+            '#' | '|' | '^' | ':' | '%' | '[' | ']' => ' ',
+            c => c,
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Lines outside fenced code blocks, with their 0-based index.
+fn prose_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut fences = FenceTracker::default();
+    text.lines()
+        .enumerate()
+        .filter(move |(_, line)| fences.is_prose(line))
+}
@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+/// Tracks fenced code blocks (```` ``` ```` or `~~~`) while scanning a note
+/// line by line.
+#[derive(Debug, Default)]
+pub struct FenceTracker {
+    in_fence: bool,
+}
+
+impl FenceTracker {
+    /// Feed the next line, returning whether it is prose: outside any code
+    /// block and not a fence marker itself.
+    pub fn is_prose(&mut self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            self.in_fence = !self.in_fence;
+            return false;
+        }
+        !self.in_fence
+    }
+}
+
+/// Whether `line` is an ATX heading such as `## Goals`.
+pub fn is_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with([' ', '\t'])
+}
+
+/// Level and text of an ATX heading, e.g. `(2, "Goals")` for `## Goals`.
+pub fn heading(line: &str) -> Option<(usize, &str)> {
+    if !is_heading(line) {
+        return None;
+    }
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].trim();
+    // A closing sequence of `#`s is not part of the heading text.
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim_end(),
+        _ => text,
+    };
+    Some((level, text))
+}
+
+/// Insert `entry` at the end of the section under the heading named
+/// `section` (case-insensitive, any level), before the blank lines that
+/// separate it from the next heading. An empty `- ` or `- [ ] ` placeholder
+/// left by a template is filled with the entry, which keeps the placeholder's
+/// bullet unless it has its own. When the heading is missing, a
+/// `## section` is appended to the end of the note.
+pub fn insert_in_section(text: &str, section: &str, entry: &str) -> String {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let Some((start, end)) = find_section(&lines, section) else {
+        let mut out = text.to_string();
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        if !out.trim().is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+        out.push_str(&format!("## {}\n\n{entry}\n", section.trim()));
+        return out;
+    };
+
+    let mut last = end;
+    while last > start + 1 && lines[last - 1].trim().is_empty() {
+        last -= 1;
+    }
+    let placeholder = last > start + 1
+        && list_marker(&lines[last - 1])
+            .map(|marker| marker.len() == lines[last - 1].len())
+            .unwrap_or(false);
+    if placeholder {
+        let bullet = lines[last - 1].trim_end().to_string();
+        lines[last - 1] = if list_marker(entry).is_some() {
+            entry.to_string()
+        } else {
+            format!("{bullet} {entry}")
+        };
+    } else {
+        lines.insert(last, entry.to_string());
+    }
+    let mut out = lines.join("\n");
+    if text.ends_with('\n') || text.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Line indices of the first heading named `section` (case-insensitive,
+/// any level) and of the next heading at the same or a higher level, or the
+/// line count when the section runs to the end. Headings in code blocks are
+/// ignored.
+pub fn find_section(lines: &[String], section: &str) -> Option<(usize, usize)> {
+    let mut fences = FenceTracker::default();
+    let mut found: Option<(usize, usize)> = None;
+    for (idx, line) in lines.iter().enumerate() {
+        let prose = fences.is_prose(line);
+        let Some((level, title)) = heading(line).filter(|_| prose) else {
+            continue;
+        };
+        match found {
+            Some((start, found_level)) if level <= found_level => return Some((start, idx)),
+            None if title.eq_ignore_ascii_case(section.trim()) => found = Some((idx, level)),
+            _ => {}
+        }
+    }
+    found.map(|(start, _)| (start, lines.len()))
+}
+
+/// The list marker a line starts with, including indentation, a checkbox
+/// and the following space: `- `, `  * `, `1. ` or `- [ ] `.
+pub fn list_marker(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start().len();
+    let trimmed = &line[indent..];
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let rest = if digits > 0 {
+        trimmed[digits..].strip_prefix(['.', ')'])?
+    } else {
+        trimmed.strip_prefix(['-', '*', '+'])?
+    };
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let mut end = line.len() - rest.len();
+    end += rest.len() - rest.trim_start().len();
+    let rest = &line[end..];
+    if rest.len() >= 3 && rest.starts_with('[') && rest.as_bytes()[2] == b']' {
+        let after = &rest[3..];
+        if after.is_empty() || after.starts_with(' ') {
+            end += 3 + after.len() - after.trim_start().len();
+        }
+    }
+    Some(&line[..end])
+}
+
+/// Byte ranges of inline code spans (`` `code` ``) within a line.
+pub fn code_spans(line: &str) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+        let fence = &line[i..i + run];
+        match line[i + run..].find(fence) {
+            Some(end) => {
+                spans.push(i..i + run + end + run);
+                i += run + end + run;
+            }
+            None => i += run,
+        }
+    }
+    spans
+}
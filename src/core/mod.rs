@@ -1,2 +1,10 @@
+pub mod frontmatter;
+pub mod links;
+pub mod markdown;
+pub mod patch;
+pub mod periodic;
+pub mod storage;
+pub mod tags;
 pub mod tasks;
 pub mod vault;
+pub mod walk;
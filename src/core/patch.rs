@@ -0,0 +1,351 @@
+use anyhow::Result;
+
+use crate::core::markdown;
+use crate::error::Error;
+
+/// A targeted edit of a note's text.
+#[derive(Debug, Clone)]
+pub enum NotePatch {
+    /// Replace the body under a heading, keeping the heading line; the
+    /// section is appended as `## heading` when missing.
+    ReplaceSection { heading: String, content: String },
+    /// Insert lines after the single line equal to `anchor` (ignoring
+    /// surrounding whitespace).
+    InsertAfter { anchor: String, content: String },
+    /// Apply a unified diff such as `diff -u` or `git diff` prints.
+    UnifiedDiff(String),
+}
+
+impl NotePatch {
+    pub fn apply(&self, text: &str) -> Result<String> {
+        let lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let (lines, newline) = match self {
+            NotePatch::ReplaceSection { heading, content } => {
+                (replace_section(lines, heading, content)?, None)
+            }
+            NotePatch::InsertAfter { anchor, content } => {
+                (insert_after(lines, anchor, content)?, None)
+            }
+            NotePatch::UnifiedDiff(diff) => apply_diff(lines, diff)?,
+        };
+        let mut out = lines.join("\n");
+        let newline = newline.unwrap_or(text.ends_with('\n') || text.is_empty());
+        if !out.is_empty() && newline {
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+fn replace_section(mut lines: Vec<String>, heading: &str, content: &str) -> Result<Vec<String>> {
+    let heading = heading.trim().trim_start_matches('#').trim();
+    if heading.is_empty() {
+        anyhow::bail!(Error::usage("heading must not be empty"));
+    }
+    let mut body: Vec<String> = content.lines().map(str::to_string).collect();
+    while body.last().is_some_and(|line| line.trim().is_empty()) {
+        body.pop();
+    }
+    let leading = body
+        .iter()
+        .take_while(|line| line.trim().is_empty())
+        .count();
+    body.drain(..leading);
+    match markdown::find_section(&lines, heading) {
+        Some((start, end)) => {
+            // Blank lines set the body apart from its heading and the next.
+            if !body.is_empty() {
+                body.insert(0, String::new());
+            }
+            if end < lines.len() {
+                body.push(String::new());
+            }
+            lines.splice(start + 1..end, body);
+        }
+        None => {
+            while lines.last().is_some_and(|line| line.trim().is_empty()) {
+                lines.pop();
+            }
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("## {heading}"));
+            lines.push(String::new());
+            lines.extend(body);
+        }
+    }
+    Ok(lines)
+}
+
+fn insert_after(mut lines: Vec<String>, anchor: &str, content: &str) -> Result<Vec<String>> {
+    let anchor = anchor.trim();
+    if anchor.is_empty() {
+        anyhow::bail!(Error::usage("anchor must not be empty"));
+    }
+    let matches: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == anchor)
+        .map(|(idx, _)| idx)
+        .collect();
+    let at = match matches.as_slice() {
+        [idx] => idx + 1,
+        [] => anyhow::bail!(Error::not_found(format!(
+            "no line matches anchor: {anchor}"
+        ))),
+        _ => anyhow::bail!(Error::conflict(format!(
+            "anchor matches {} lines: {anchor}",
+            matches.len()
+        ))),
+    };
+    lines.splice(at..at, content.lines().map(str::to_string));
+    Ok(lines)
+}
+
+/// One `@@ -old +new @@` block of a unified diff.
+#[derive(Debug, Default)]
+struct Hunk {
+    /// 1-based first line of the hunk in the original text; for a pure
+    /// insertion, the line it follows.
+    old_start: usize,
+    /// Line counts from the header, counted down while reading the body.
+    old_left: usize,
+    new_left: usize,
+    /// Context and removed lines, as expected in the original.
+    old: Vec<String>,
+    /// Context and added lines, as written in the result.
+    new: Vec<String>,
+    /// Whether the hunk reaches the end of the original or the result
+    /// without a final newline (`\ No newline at end of file`).
+    old_unterminated: bool,
+    new_unterminated: bool,
+}
+
+impl Hunk {
+    fn is_complete(&self) -> bool {
+        self.old_left == 0 && self.new_left == 0
+    }
+}
+
+/// Apply the hunks of `diff`, returning the lines and, when a hunk says so,
+/// whether the result ends with a newline.
+fn apply_diff(mut lines: Vec<String>, diff: &str) -> Result<(Vec<String>, Option<bool>)> {
+    let hunks = parse_hunks(diff)?;
+    if hunks.is_empty() {
+        anyhow::bail!(Error::usage("diff contains no @@ hunks"));
+    }
+    // Lines added or removed by earlier hunks shift the later ones.
+    let mut offset: isize = 0;
+    let mut from = 0;
+    let mut newline = None;
+    for (number, hunk) in hunks.iter().enumerate() {
+        let start = if hunk.old.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (start as isize + offset).max(0) as usize;
+        let at = locate(&lines, &hunk.old, expected, from).ok_or_else(|| {
+            Error::conflict(format!(
+                "hunk {} (line {}) does not match the note",
+                number + 1,
+                hunk.old_start
+            ))
+        })?;
+        lines.splice(at..at + hunk.old.len(), hunk.new.iter().cloned());
+        offset += hunk.new.len() as isize - hunk.old.len() as isize;
+        from = at + hunk.new.len();
+        if hunk.new_unterminated {
+            newline = Some(false);
+        } else if hunk.old_unterminated {
+            newline = Some(true);
+        }
+    }
+    Ok((lines, newline))
+}
+
+/// Where `old` occurs in `lines` at or after `from`: at `expected` when it
+/// matches there, else the closest match.
+fn locate(lines: &[String], old: &[String], expected: usize, from: usize) -> Option<usize> {
+    let fits = |at: usize| at + old.len() <= lines.len() && lines[at..at + old.len()] == *old;
+    if expected >= from && fits(expected) {
+        return Some(expected);
+    }
+    (from..=lines.len().saturating_sub(old.len()))
+        .filter(|at| fits(*at))
+        .min_by_key(|at| at.abs_diff(expected))
+}
+
+/// Read the hunks of a single-file diff. Each hunk ends once the line
+/// counts in its header are used up, so trailing blank lines and the
+/// headers of a following file are not mistaken for hunk lines.
+fn parse_hunks(diff: &str) -> Result<Vec<Hunk>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut current: Option<Hunk> = None;
+    // Side of the last hunk line, for a following `\ No newline` marker.
+    let mut last_kind = ' ';
+    let mut files = 0;
+    for line in diff.lines() {
+        if let Some(hunk) = current.as_mut() {
+            if let Some(marker) = line.strip_prefix('\\') {
+                if !marker.trim().is_empty() {
+                    match last_kind {
+                        '-' => hunk.old_unterminated = true,
+                        '+' => hunk.new_unterminated = true,
+                        _ => {
+                            hunk.old_unterminated = true;
+                            hunk.new_unterminated = true;
+                        }
+                    }
+                }
+                continue;
+            }
+            if !hunk.is_complete() {
+                let (kind, text) = match line.chars().next() {
+                    Some(kind @ (' ' | '-' | '+')) => (kind, &line[1..]),
+                    // Some tools strip the space from empty context lines.
+                    None => (' ', ""),
+                    Some(_) => anyhow::bail!(Error::usage(format!(
+                        "hunk {} has fewer lines than its header says",
+                        hunks.len() + 1
+                    ))),
+                };
+                let (old, new) = match kind {
+                    ' ' => (true, true),
+                    '-' => (true, false),
+                    _ => (false, true),
+                };
+                if (old && hunk.old_left == 0) || (new && hunk.new_left == 0) {
+                    anyhow::bail!(Error::usage(format!(
+                        "hunk {} has more lines than its header says",
+                        hunks.len() + 1
+                    )));
+                }
+                if old {
+                    hunk.old_left -= 1;
+                    hunk.old.push(text.to_string());
+                }
+                if new {
+                    hunk.new_left -= 1;
+                    hunk.new.push(text.to_string());
+                }
+                last_kind = kind;
+                continue;
+            }
+            hunks.extend(current.take());
+        }
+
+        if let Some(header) = line.strip_prefix("@@") {
+            let (old_start, old_left, new_left) = hunk_header(header)
+                .ok_or_else(|| Error::usage(format!("malformed hunk header: {line}")))?;
+            current = Some(Hunk {
+                old_start,
+                old_left,
+                new_left,
+                ..Hunk::default()
+            });
+        } else if line.starts_with("--- ") {
+            files += 1;
+            if files > 1 {
+                anyhow::bail!(Error::usage(
+                    "diff changes several files; send one diff per note"
+                ));
+            }
+        } else if !hunks.is_empty()
+            && !line.starts_with("+++ ")
+            && (line.starts_with(['+', '-']) || line.starts_with(' ') && !line.trim().is_empty())
+        {
+            anyhow::bail!(Error::usage(format!(
+                "hunk {} has more lines than its header says",
+                hunks.len()
+            )));
+        }
+        // Anything else between hunks is a file header (`diff`, `index`,
+        // `+++`), a commit message or a blank line.
+    }
+    if let Some(hunk) = current {
+        if !hunk.is_complete() {
+            anyhow::bail!(Error::usage(format!(
+                "hunk {} has fewer lines than its header says",
+                hunks.len() + 1
+            )));
+        }
+        hunks.push(hunk);
+    }
+    Ok(hunks)
+}
+
+/// The old start line and the old and new line counts from
+/// ` -12,5 +12,6 @@`; an omitted count is 1.
+fn hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = header.split_whitespace();
+    let range = |text: &str| -> Option<(usize, usize)> {
+        match text.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((text.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(ranges.next()?.strip_prefix('-')?)?;
+    let (_, new_count) = range(ranges.next()?.strip_prefix('+')?)?;
+    Some((old_start, old_count, new_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const NOTE: &str = "# Plan\n\n- one\n- two\n- three\n";
+
+    fn diff(diff: &str) -> Result<String> {
+        NotePatch::UnifiedDiff(diff.to_string()).apply(NOTE)
+    }
+
+    #[test]
+    fn ignores_blank_lines_after_a_hunk() {
+        let patched =
+            diff("--- a/Plan.md\n+++ b/Plan.md\n@@ -3,2 +3,2 @@\n - one\n-- two\n+- 2\n\n\n");
+        assert_eq!(patched.unwrap(), "# Plan\n\n- one\n- 2\n- three\n");
+    }
+
+    #[test]
+    fn keeps_removed_lines_that_look_like_file_headers() {
+        let note = "a\n-- b\nc\n";
+        let patch = NotePatch::UnifiedDiff("@@ -1,3 +1,2 @@\n a\n--- b\n c\n".to_string());
+        assert_eq!(patch.apply(note).unwrap(), "a\nc\n");
+    }
+
+    #[test]
+    fn inserts_after_the_line_of_an_empty_old_range() {
+        let patched = diff("@@ -3,0 +4,1 @@\n+- one and a half\n");
+        assert_eq!(
+            patched.unwrap(),
+            "# Plan\n\n- one\n- one and a half\n- two\n- three\n"
+        );
+    }
+
+    #[test]
+    fn honours_missing_final_newlines() {
+        let patched = diff("@@ -5 +5 @@\n-- three\n+- 3\n\\ No newline at end of file\n");
+        assert_eq!(patched.unwrap(), "# Plan\n\n- one\n- two\n- 3");
+
+        let patch = NotePatch::UnifiedDiff(
+            "@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+b\n".to_string(),
+        );
+        assert_eq!(patch.apply("a").unwrap(), "b\n");
+    }
+
+    #[test]
+    fn rejects_multi_file_and_miscounted_diffs() {
+        for bad in [
+            "--- a/x.md\n+++ b/x.md\n@@ -3 +3 @@\n-- one\n+- 1\n--- a/y.md\n+++ b/y.md\n@@ -1 +1 @@\n-a\n+b\n",
+            "@@ -3,2 +3,2 @@\n - one\n-- two\n",
+            "@@ -3 +3 @@\n-- one\n+- 1\n+- 1b\n",
+        ] {
+            let err = diff(bad).unwrap_err();
+            assert_eq!(ErrorKind::of(&err), ErrorKind::Usage, "{bad}: {err}");
+        }
+        let err = diff("@@ -3 +3 @@\n-- eleven\n+- 11\n").unwrap_err();
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Conflict);
+    }
+}
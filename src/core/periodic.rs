@@ -0,0 +1,155 @@
+use std::fmt;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+use crate::core::walk::is_relative_inside;
+use crate::error::Error;
+
+/// The kinds of periodic notes, following Obsidian's Periodic Notes plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Period {
+    pub const ALL: [Period; 5] = [
+        Period::Day,
+        Period::Week,
+        Period::Month,
+        Period::Quarter,
+        Period::Year,
+    ];
+
+    /// Config section and template name, e.g. `weekly`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Day => "daily",
+            Period::Week => "weekly",
+            Period::Month => "monthly",
+            Period::Quarter => "quarterly",
+            Period::Year => "yearly",
+        }
+    }
+
+    /// Default file name format. `%q` is the quarter (1-4); everything else
+    /// is a chrono format specifier.
+    pub fn default_format(self) -> &'static str {
+        match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Week => "%G-W%V",
+            Period::Month => "%Y-%m",
+            Period::Quarter => "%Y-Q%q",
+            Period::Year => "%Y",
+        }
+    }
+
+    /// First day of the period containing `date`; weeks start on Monday.
+    pub fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week => date - Days::new(u64::from(date.weekday().num_days_from_monday())),
+            Period::Month => date.with_day(1).expect("first of month"),
+            Period::Quarter => NaiveDate::from_ymd_opt(date.year(), (date.month0() / 3) * 3 + 1, 1)
+                .expect("first of quarter"),
+            Period::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).expect("first of year"),
+        }
+    }
+
+    /// Last day of the period containing `date`.
+    pub fn end(self, date: NaiveDate) -> NaiveDate {
+        self.shift(date, 1)
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(date)
+    }
+
+    /// Start of the period `offset` periods away from the one containing
+    /// `date`, e.g. `-1` for the previous week.
+    pub fn shift(self, date: NaiveDate, offset: i64) -> Option<NaiveDate> {
+        let start = self.start(date);
+        let (days, months) = match self {
+            Period::Day => (offset, 0),
+            Period::Week => (offset.checked_mul(7)?, 0),
+            Period::Month => (0, offset),
+            Period::Quarter => (0, offset.checked_mul(3)?),
+            Period::Year => (0, offset.checked_mul(12)?),
+        };
+        let days = Days::new(days.unsigned_abs());
+        let months = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+        if offset >= 0 {
+            start.checked_add_days(days)?.checked_add_months(months)
+        } else {
+            start.checked_sub_days(days)?.checked_sub_months(months)
+        }
+    }
+
+    /// Every day of the period containing `date`.
+    pub fn days(self, date: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+        let end = self.end(date);
+        self.start(date)
+            .iter_days()
+            .take_while(move |day| *day <= end)
+    }
+
+    /// Render `format` for `date`, expanding `%q` to the quarter.
+    pub fn format(format: &str, date: NaiveDate) -> String {
+        let quarter = (date.month0() / 3 + 1).to_string();
+        date.format(&format.replace("%q", &quarter)).to_string()
+    }
+
+    /// Check that `format` is valid, names exactly one period of this kind
+    /// and stays inside its folder.
+    pub fn validate_format(self, format: &str) -> Result<()> {
+        let plain = format.replace("%q", "1");
+        if StrftimeItems::new(&plain).any(|item| matches!(item, Item::Error)) {
+            return Err(
+                Error::config(format!("invalid [{}] format `{format}`", self.name())).into(),
+            );
+        }
+        // The whole period must share one name, and neighbours must differ.
+        let sample = NaiveDate::from_ymd_opt(2024, 12, 31).expect("valid date");
+        let rendered = Self::format(format, sample);
+        let same = Self::format(format, self.start(sample)) == rendered;
+        let distinct = [-1, 1].into_iter().all(|offset| {
+            self.shift(sample, offset)
+                .map(|other| Self::format(format, other) != rendered)
+                .unwrap_or(false)
+        });
+        // Daily notes are listed by parsing their names back into dates.
+        let parses = self != Period::Day
+            || NaiveDate::parse_from_str(&rendered, format).ok() == Some(sample);
+        if !same || !distinct || !parses {
+            return Err(Error::config(format!(
+                "[{}] format `{format}` must name exactly one {self}",
+                self.name()
+            ))
+            .into());
+        }
+        if rendered.is_empty() || !is_relative_inside(Path::new(&rendered)) {
+            return Err(Error::config(format!(
+                "[{}] format `{format}` must stay inside its folder",
+                self.name()
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Quarter => "quarter",
+            Period::Year => "year",
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+
+const VAULT_LOCK_FILE: &str = ".obsctl/lock";
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// An exclusive advisory lock held until dropped. Every read-modify-write of
+/// vault files happens under the vault lock so the CLI and `obsctl_mcp` can
+/// run side by side without losing updates.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Block until the lock file at `path` can be locked exclusively.
+    pub fn acquire(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("open lock file {}", path.display()))?;
+        file.lock()
+            .with_context(|| format!("lock {}", path.display()))?;
+        Ok(Self { _file: file })
+    }
+
+    /// Lock the whole vault rooted at `vault_root`.
+    pub fn vault(vault_root: &Path) -> Result<Self> {
+        Self::acquire(&vault_root.join(VAULT_LOCK_FILE))
+    }
+}
+
+/// Replace `path` with `contents` by writing a temporary sibling file and
+/// renaming it into place, so readers never observe a partially written file.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("cannot write to {}", path.display()))?
+        .to_string_lossy();
+    let tmp = dir.join(format!(
+        ".{name}.{}.{}.tmp",
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result.with_context(|| format!("write {}", path.display()))
+}
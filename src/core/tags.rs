@@ -0,0 +1,255 @@
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::core::frontmatter::{Document, PropertyValue};
+use crate::core::markdown::{self, code_spans, FenceTracker};
+use crate::core::storage::{self, FileLock};
+use crate::core::walk;
+use crate::error::Error;
+
+/// Frontmatter properties holding a note's tags.
+const TAG_PROPERTIES: [&str; 2] = ["tags", "tag"];
+
+/// A tag carried by a note, either inline (`#project/alpha`) or listed in
+/// the `tags` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagOccurrence {
+    /// Tag name without the leading `#`, as written.
+    pub tag: String,
+    /// 1-based line number in the note.
+    pub line: usize,
+    /// The trimmed line the tag appears on.
+    pub context: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct RenameStats {
+    pub notes: usize,
+    pub occurrences: usize,
+}
+
+/// Every tag in a note: frontmatter `tags` first, then inline tags outside
+/// code blocks, inline code and headings.
+pub fn note_tags(text: &str) -> Vec<TagOccurrence> {
+    let document = Document::parse(text);
+    let frontmatter_len = text.len() - document.body().len();
+    let body_offset = text[..frontmatter_len].lines().count();
+
+    let mut tags = Vec::new();
+    for (idx, line) in text[..frontmatter_len].lines().enumerate() {
+        let key = line.split_once(':').map(|(key, _)| key.trim());
+        let property = TAG_PROPERTIES
+            .iter()
+            .find(|name| key.map(|k| k.eq_ignore_ascii_case(name)).unwrap_or(false));
+        if let Some(property) = property {
+            let values = property_values(&document, property);
+            tags.extend(values.into_iter().map(|tag| TagOccurrence {
+                tag,
+                line: idx + 1,
+                context: line.trim().to_string(),
+            }));
+        }
+    }
+
+    let mut fences = FenceTracker::default();
+    for (idx, line) in document.body().lines().enumerate() {
+        if !fences.is_prose(line) || markdown::is_heading(line) {
+            continue;
+        }
+        tags.extend(inline_tags(line).into_iter().map(|(_, tag)| TagOccurrence {
+            tag,
+            line: body_offset + idx + 1,
+            context: line.trim().to_string(),
+        }));
+    }
+    tags
+}
+
+/// Inline `#tags` in a line with the byte range of each (including `#`).
+/// A tag follows whitespace or starts the line, may nest with `/`, and must
+/// contain a non-digit; tags inside inline code are ignored.
+pub fn inline_tags(line: &str) -> Vec<(Range<usize>, String)> {
+    let code = code_spans(line);
+    let mut tags = Vec::new();
+    let mut prev: Option<char> = None;
+    for (idx, ch) in line.char_indices() {
+        let boundary = prev.map(char::is_whitespace).unwrap_or(true);
+        prev = Some(ch);
+        if ch != '#' || !boundary || code.iter().any(|span| span.contains(&idx)) {
+            continue;
+        }
+        let name: String = line[idx + 1..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+            .collect();
+        let name = name.trim_end_matches('/');
+        if name.chars().any(|c| !c.is_ascii_digit()) {
+            tags.push((idx..idx + 1 + name.len(), name.to_string()));
+        }
+    }
+    tags
+}
+
+/// Strip a leading `#` and surrounding whitespace from user input.
+pub fn normalize(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_string()
+}
+
+/// Whether `tag` is `query` or nested below it, case-insensitively, so
+/// `project` matches `#Project` and `#project/alpha`.
+pub fn matches(tag: &str, query: &str) -> bool {
+    nested_suffix(&normalize(tag), &normalize(query)).is_some()
+}
+
+/// What follows `query` in `tag` when `tag` is `query` or nested below it:
+/// empty or `/rest`. Compares character by character, ignoring case, so the
+/// suffix is sliced from `tag` itself even where case folding changes the
+/// UTF-8 length (`ẞ`/`ß`, `K`/`k`).
+fn nested_suffix<'a>(tag: &'a str, query: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    for expected in query.chars() {
+        let mut chars = rest.chars();
+        let actual = chars.next()?;
+        if !actual.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+        rest = chars.as_str();
+    }
+    (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+}
+
+/// Rename a tag (and the tags nested below it) in every note of the vault.
+pub fn rename_tag(root: &Path, from: &str, to: &str) -> Result<RenameStats> {
+    let (from, to) = (normalize(from), normalize(to));
+    if from.is_empty() || to.is_empty() {
+        return Err(Error::usage("tag names must not be empty").into());
+    }
+    if inline_tags(&format!("#{to}")).first().map(|(_, t)| t) != Some(&to) {
+        return Err(Error::usage(format!("invalid tag name: #{to}")).into());
+    }
+    let _lock = FileLock::vault(root)?;
+    let mut stats = RenameStats::default();
+    for path in walk::markdown_files(root)? {
+        let text = std::fs::read_to_string(&path)?;
+        let (renamed, count) = rename_in_text(&text, &from, &to);
+        if count > 0 {
+            storage::write_atomic(&path, renamed)?;
+            stats.notes += 1;
+            stats.occurrences += count;
+        }
+    }
+    Ok(stats)
+}
+
+/// Rewrite `from` to `to` in a note's frontmatter tags and inline tags,
+/// returning the new text and the number of tags changed.
+fn rename_in_text(text: &str, from: &str, to: &str) -> (String, usize) {
+    let rename = |tag: &str| -> Option<String> {
+        nested_suffix(&normalize(tag), from).map(|suffix| format!("{to}{suffix}"))
+    };
+    let mut count = 0;
+    let mut document = Document::parse(text);
+
+    for key in TAG_PROPERTIES {
+        let before = count;
+        let renamed = match document.get(key) {
+            // Keep a plain-text property as text, with its separators.
+            Some(PropertyValue::Text(text)) => {
+                let renamed: String = text
+                    .split_inclusive([',', ' '])
+                    .map(|piece| {
+                        let word = piece.trim_end_matches([',', ' ']);
+                        let hash = if word.starts_with('#') { "#" } else { "" };
+                        match rename(word).filter(|_| !normalize(word).is_empty()) {
+                            Some(new) => {
+                                count += 1;
+                                format!("{hash}{new}{}", &piece[word.len()..])
+                            }
+                            None => piece.to_string(),
+                        }
+                    })
+                    .collect();
+                PropertyValue::Text(renamed)
+            }
+            Some(_) => PropertyValue::List(
+                property_values(&document, key)
+                    .into_iter()
+                    .map(|tag| match rename(&tag) {
+                        Some(new) => {
+                            count += 1;
+                            new
+                        }
+                        None => tag,
+                    })
+                    .collect(),
+            ),
+            None => continue,
+        };
+        if count > before {
+            document.set(key, &renamed);
+        }
+    }
+
+    let mut fences = FenceTracker::default();
+    let mut body = String::with_capacity(document.body().len());
+    for line in document.body().split_inclusive('\n') {
+        if !fences.is_prose(line) || markdown::is_heading(line) {
+            body.push_str(line);
+            continue;
+        }
+        let mut rewritten = line.to_string();
+        for (span, tag) in inline_tags(line).into_iter().rev() {
+            if let Some(new) = rename(&tag) {
+                rewritten.replace_range(span, &format!("#{new}"));
+                count += 1;
+            }
+        }
+        body.push_str(&rewritten);
+    }
+    document.set_body(body);
+    (document.render(), count)
+}
+
+/// Tags listed under a property, either as a list or a comma/space
+/// separated string.
+fn property_values(document: &Document, key: &str) -> Vec<String> {
+    let values = match document.get(key) {
+        Some(PropertyValue::List(items)) => items,
+        Some(PropertyValue::Text(text)) => text.split([',', ' ']).map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+    values
+        .iter()
+        .map(|tag| normalize(tag))
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_tags_whose_case_folding_changes_length() {
+        let text = "#ẞtraße/x and #\u{212A}elvin\n";
+        assert_eq!(
+            rename_in_text(text, "ßtraße", "street"),
+            ("#street/x and #\u{212A}elvin\n".to_string(), 1)
+        );
+        assert_eq!(
+            rename_in_text(text, "kelvin", "temp"),
+            ("#ẞtraße/x and #temp\n".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn keeps_text_tag_properties_as_text() {
+        let text = "---\ntags: work, home/garden\n---\nbody\n";
+        let (renamed, count) = rename_in_text(text, "home", "house");
+        assert_eq!(count, 1);
+        assert_eq!(renamed, "---\ntags: work, house/garden\n---\nbody\n");
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::core::storage;
+
+const REGISTRY_FILE: &str = ".obsctl/task-ids";
+const ID_LENGTH: usize = 6;
+const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Append-only record of every task id ever seen or issued in the vault, so
+/// an id is never handed out again after its task is deleted. Callers hold
+/// the vault lock while a registry is loaded.
+pub struct IdRegistry {
+    path: PathBuf,
+    known: HashSet<String>,
+    pending: Vec<String>,
+}
+
+impl IdRegistry {
+    pub fn load(vault_root: &Path) -> Result<Self> {
+        let path = vault_root.join(REGISTRY_FILE);
+        let known = match fs::read_to_string(&path) {
+            Ok(text) => text
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("read task id registry {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path,
+            known,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Remember an id that already exists in the vault.
+    pub fn record(&mut self, id: &str) {
+        if self.known.insert(id.to_string()) {
+            self.pending.push(id.to_string());
+        }
+    }
+
+    /// Generate a short base-36 id that has never been used in this vault.
+    pub fn issue(&mut self) -> String {
+        loop {
+            let id = random_id();
+            if !self.known.contains(&id) {
+                self.record(&id);
+                return id;
+            }
+        }
+    }
+
+    /// A stable id for a checkbox that has none yet, derived from `seed` so
+    /// that a read and the write that finally labels the task agree on it.
+    /// Skips ids ever issued in the vault or listed in `taken`.
+    pub fn derive(&self, seed: &str, taken: &HashSet<String>) -> String {
+        let mut salt: u64 = 0;
+        loop {
+            let id = encode(fnv1a(seed, salt));
+            if !self.known.contains(&id) && !taken.contains(&id) {
+                return id;
+            }
+            salt += 1;
+        }
+    }
+
+    /// Persist ids recorded or issued since the registry was loaded.
+    pub fn save(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("read task id registry {}", self.path.display()))
+            }
+        };
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        for id in self.pending.drain(..) {
+            text.push_str(&id);
+            text.push('\n');
+        }
+        storage::write_atomic(&self.path, text)
+    }
+}
+
+fn random_id() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    hasher.write_u128(nanos);
+    encode(hasher.finish())
+}
+
+/// FNV-1a, which unlike std's hashers is stable across releases.
+fn fnv1a(seed: &str, salt: u64) -> u64 {
+    seed.bytes()
+        .chain(salt.to_le_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+fn encode(mut value: u64) -> String {
+    let mut id = String::with_capacity(ID_LENGTH);
+    for _ in 0..ID_LENGTH {
+        id.push(ALPHABET[(value % 36) as usize] as char);
+        value /= 36;
+    }
+    id
+}
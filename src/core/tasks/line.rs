@@ -0,0 +1,627 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::core::tags;
+
+/// Priority levels understood by the Obsidian Tasks plugin, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Lowest,
+    Low,
+    Medium,
+    High,
+    Highest,
+}
+
+impl Priority {
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Priority::Lowest => "⏬",
+            Priority::Low => "🔽",
+            Priority::Medium => "🔼",
+            Priority::High => "⏫",
+            Priority::Highest => "🔺",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Lowest => "lowest",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Highest => "highest",
+        }
+    }
+
+    /// Parse a [`label`](Self::label), ignoring case.
+    pub fn from_label(label: &str) -> Option<Self> {
+        [
+            Priority::Lowest,
+            Priority::Low,
+            Priority::Medium,
+            Priority::High,
+            Priority::Highest,
+        ]
+        .into_iter()
+        .find(|priority| priority.label().eq_ignore_ascii_case(label.trim()))
+    }
+}
+
+/// Date-valued task fields and the emoji that introduces each of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateField {
+    Due,
+    Scheduled,
+    Start,
+    Created,
+    Done,
+    Cancelled,
+}
+
+impl DateField {
+    pub fn marker(&self) -> &'static str {
+        match self {
+            DateField::Due => "📅",
+            DateField::Scheduled => "⏳",
+            DateField::Start => "🛫",
+            DateField::Created => "➕",
+            DateField::Done => "✅",
+            DateField::Cancelled => "❌",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkerKind {
+    Date(DateField),
+    Recurrence,
+    Priority(Priority),
+    Id,
+}
+
+const RECURRENCE_MARKER: &str = "🔁";
+const ID_MARKER: &str = "🆔";
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+
+/// Every emoji the parser recognizes. Alternate spellings accepted by the
+/// Obsidian Tasks plugin are listed alongside the canonical ones, and `🔥`/`⬇️`
+/// are kept so task lists written by older obsctl releases still parse.
+const MARKERS: &[(&str, MarkerKind)] = &[
+    ("📅", MarkerKind::Date(DateField::Due)),
+    ("📆", MarkerKind::Date(DateField::Due)),
+    ("🗓", MarkerKind::Date(DateField::Due)),
+    ("⏳", MarkerKind::Date(DateField::Scheduled)),
+    ("⌛", MarkerKind::Date(DateField::Scheduled)),
+    ("🛫", MarkerKind::Date(DateField::Start)),
+    ("➕", MarkerKind::Date(DateField::Created)),
+    ("✅", MarkerKind::Date(DateField::Done)),
+    ("❌", MarkerKind::Date(DateField::Cancelled)),
+    (RECURRENCE_MARKER, MarkerKind::Recurrence),
+    (ID_MARKER, MarkerKind::Id),
+    ("🔺", MarkerKind::Priority(Priority::Highest)),
+    ("⏫", MarkerKind::Priority(Priority::High)),
+    ("🔼", MarkerKind::Priority(Priority::Medium)),
+    ("🔽", MarkerKind::Priority(Priority::Low)),
+    ("⏬", MarkerKind::Priority(Priority::Lowest)),
+    ("🔥", MarkerKind::Priority(Priority::High)),
+    ("⬇", MarkerKind::Priority(Priority::Low)),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Date {
+        field: DateField,
+        date: NaiveDate,
+        raw: String,
+    },
+    Recurrence {
+        rule: String,
+        raw: String,
+    },
+    Priority {
+        priority: Priority,
+        raw: String,
+    },
+    Id {
+        id: String,
+        raw: String,
+    },
+}
+
+impl Segment {
+    fn raw(&self) -> &str {
+        match self {
+            Segment::Text(text) => text,
+            Segment::Date { raw, .. }
+            | Segment::Recurrence { raw, .. }
+            | Segment::Priority { raw, .. }
+            | Segment::Id { raw, .. } => raw,
+        }
+    }
+
+    fn is_field(&self) -> bool {
+        !matches!(self, Segment::Text(_))
+    }
+}
+
+/// A Markdown checkbox line split into its list prefix, status character and
+/// body. The body is kept as a sequence of text runs and recognized fields so
+/// that rendering an unmodified line reproduces it byte for byte, and edits
+/// only touch the field being changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskLine {
+    head: String,
+    status: char,
+    segments: Vec<Segment>,
+}
+
+impl TaskLine {
+    pub fn parse(line: &str) -> Option<Self> {
+        let indent_len = line.len() - line.trim_start().len();
+        let rest = &line[indent_len..];
+        let bullet_len = list_marker_len(rest)?;
+        let after_bullet = &rest[bullet_len..];
+        let spaces = after_bullet.len() - after_bullet.trim_start_matches([' ', '\t']).len();
+        if spaces == 0 {
+            return None;
+        }
+        let head_len = indent_len + bullet_len + spaces;
+        let mut chars = line[head_len..].chars();
+        if chars.next()? != '[' {
+            return None;
+        }
+        let status = chars.next()?;
+        if chars.next()? != ']' {
+            return None;
+        }
+        let body = chars.as_str();
+        if !body.is_empty() && !body.starts_with([' ', '\t']) {
+            return None;
+        }
+        Some(Self {
+            head: line[..head_len].to_string(),
+            status,
+            segments: parse_segments(body),
+        })
+    }
+
+    pub fn render(&self) -> String {
+        let mut line = format!("{}[{}]", self.head, self.status);
+        for segment in &self.segments {
+            line.push_str(segment.raw());
+        }
+        line
+    }
+
+    /// Leading whitespace before the list marker.
+    pub fn indent(&self) -> &str {
+        let trimmed = self.head.trim_start();
+        &self.head[..self.head.len() - trimmed.len()]
+    }
+
+    pub fn status(&self) -> char {
+        self.status
+    }
+
+    pub fn set_status(&mut self, status: char) {
+        self.status = status;
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.status, 'x' | 'X')
+    }
+
+    /// Still to do: unchecked (`[ ]`) or in progress (`[/]`).
+    pub fn is_open(&self) -> bool {
+        matches!(self.status, ' ' | '/')
+    }
+
+    /// Carried over into a later note (`[>]`); no longer a task of its own.
+    pub fn is_migrated(&self) -> bool {
+        self.status == '>'
+    }
+
+    /// Free text preceding the first recognized field.
+    pub fn description(&self) -> String {
+        self.segments
+            .iter()
+            .take_while(|segment| !segment.is_field())
+            .map(Segment::raw)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    /// Replace the free text preceding the first field, keeping the
+    /// surrounding whitespace intact.
+    pub fn set_description(&mut self, description: &str) {
+        let end = self
+            .segments
+            .iter()
+            .position(Segment::is_field)
+            .unwrap_or(self.segments.len());
+        let current: String = self.segments[..end].iter().map(Segment::raw).collect();
+        let trimmed = current.trim_start();
+        let lead = match &current[..current.len() - trimmed.len()] {
+            "" => " ",
+            lead => lead,
+        };
+        let trail = if end < self.segments.len() {
+            match &trimmed[trimmed.trim_end().len()..] {
+                "" => " ",
+                trail => trail,
+            }
+        } else {
+            &trimmed[trimmed.trim_end().len()..]
+        };
+        let replacement = Segment::Text(format!("{lead}{description}{trail}"));
+        self.segments.splice(..end, [replacement]);
+    }
+
+    pub fn date(&self, field: DateField) -> Option<NaiveDate> {
+        self.segments.iter().find_map(|segment| match segment {
+            Segment::Date { field: f, date, .. } if *f == field => Some(*date),
+            _ => None,
+        })
+    }
+
+    pub fn set_date(&mut self, field: DateField, date: Option<NaiveDate>) {
+        let matches =
+            |segment: &Segment| matches!(segment, Segment::Date { field: f, .. } if *f == field);
+        match date {
+            Some(date) => self.upsert(
+                matches,
+                Segment::Date {
+                    field,
+                    date,
+                    raw: format!("{} {}", field.marker(), date.format("%Y-%m-%d")),
+                },
+            ),
+            None => self.remove(matches),
+        }
+    }
+
+    pub fn priority(&self) -> Option<Priority> {
+        self.segments.iter().find_map(|segment| match segment {
+            Segment::Priority { priority, .. } => Some(*priority),
+            _ => None,
+        })
+    }
+
+    pub fn set_priority(&mut self, priority: Option<Priority>) {
+        let matches = |segment: &Segment| matches!(segment, Segment::Priority { .. });
+        match priority {
+            Some(priority) => self.upsert(
+                matches,
+                Segment::Priority {
+                    priority,
+                    raw: priority.marker().to_string(),
+                },
+            ),
+            None => self.remove(matches),
+        }
+    }
+
+    pub fn recurrence(&self) -> Option<&str> {
+        self.segments.iter().find_map(|segment| match segment {
+            Segment::Recurrence { rule, .. } => Some(rule.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn set_recurrence(&mut self, rule: Option<&str>) {
+        let matches = |segment: &Segment| matches!(segment, Segment::Recurrence { .. });
+        match rule.map(str::trim).filter(|rule| !rule.is_empty()) {
+            Some(rule) => self.upsert(
+                matches,
+                Segment::Recurrence {
+                    rule: rule.to_string(),
+                    raw: format!("{RECURRENCE_MARKER} {rule}"),
+                },
+            ),
+            None => self.remove(matches),
+        }
+    }
+
+    /// The Obsidian Tasks `🆔` identifier, if present.
+    pub fn id(&self) -> Option<&str> {
+        self.segments.iter().find_map(|segment| match segment {
+            Segment::Id { id, .. } => Some(id.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn set_id(&mut self, id: Option<&str>) {
+        let matches = |segment: &Segment| matches!(segment, Segment::Id { .. });
+        match id {
+            Some(id) => self.upsert(
+                matches,
+                Segment::Id {
+                    id: id.to_string(),
+                    raw: format!("{ID_MARKER} {id}"),
+                },
+            ),
+            None => self.remove(matches),
+        }
+    }
+
+    /// Inline `#tags` found anywhere in the body, including the leading `#`.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+        for segment in &self.segments {
+            if let Segment::Text(text) = segment {
+                tags.extend(extract_tags(text));
+            }
+        }
+        tags
+    }
+
+    /// Append `#tag` to the description unless the task already carries it.
+    pub fn add_tag(&mut self, tag: &str) {
+        let tag = tags::normalize(tag);
+        if self.has_tag(&tag) {
+            return;
+        }
+        let description = self.description();
+        self.set_description(format!("{description} #{tag}").trim_start());
+    }
+
+    /// Remove every inline `#tag` (case-insensitive, nested tags kept) from
+    /// the body, returning whether any was found.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let tag = tags::normalize(tag);
+        let mut removed = false;
+        for segment in &mut self.segments {
+            let Segment::Text(text) = segment else {
+                continue;
+            };
+            for (span, name) in tags::inline_tags(text).into_iter().rev() {
+                if !name.eq_ignore_ascii_case(&tag) {
+                    continue;
+                }
+                // Drop one adjoining space so the words around the tag stay
+                // separated by a single space.
+                let before = text[..span.start].ends_with(' ');
+                let after = text[span.end..].starts_with(' ');
+                let range = match (before, after) {
+                    (true, _) => span.start - 1..span.end,
+                    (false, true) => span.start..span.end + 1,
+                    (false, false) => span,
+                };
+                text.replace_range(range, "");
+                removed = true;
+            }
+        }
+        removed
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags()
+            .iter()
+            .any(|t| t.trim_start_matches('#').eq_ignore_ascii_case(tag))
+    }
+
+    fn upsert(&mut self, matches: impl Fn(&Segment) -> bool, segment: Segment) {
+        if let Some(existing) = self.segments.iter_mut().find(|s| matches(s)) {
+            *existing = segment;
+            return;
+        }
+        // Keep a trailing `^block-id` last so Obsidian block references survive.
+        let trailing = match self.segments.last() {
+            Some(Segment::Text(text)) => split_block_id(text),
+            _ => None,
+        };
+        if let Some((before, _)) = &trailing {
+            self.segments.pop();
+            if !before.is_empty() {
+                self.segments.push(Segment::Text(before.clone()));
+            }
+        }
+        let needs_space = self
+            .segments
+            .last()
+            .map(|s| !s.raw().ends_with(char::is_whitespace))
+            .unwrap_or(true);
+        if needs_space {
+            self.segments.push(Segment::Text(" ".to_string()));
+        }
+        self.segments.push(segment);
+        if let Some((_, block)) = trailing {
+            self.segments.push(Segment::Text(format!(" {block}")));
+        }
+    }
+
+    fn remove(&mut self, matches: impl Fn(&Segment) -> bool) {
+        let Some(idx) = self.segments.iter().position(matches) else {
+            return;
+        };
+        self.segments.remove(idx);
+        if idx > 0 {
+            if let Segment::Text(text) = &mut self.segments[idx - 1] {
+                if let Some(last) = text.chars().last().filter(|c| c.is_whitespace()) {
+                    text.truncate(text.len() - last.len_utf8());
+                }
+                if text.is_empty() {
+                    self.segments.remove(idx - 1);
+                }
+            }
+        }
+    }
+}
+
+fn list_marker_len(text: &str) -> Option<usize> {
+    let first = text.chars().next()?;
+    if matches!(first, '-' | '*' | '+') {
+        return Some(1);
+    }
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && matches!(text[digits..].chars().next(), Some('.' | ')')) {
+        return Some(digits + 1);
+    }
+    None
+}
+
+fn match_marker(text: &str) -> Option<(MarkerKind, usize)> {
+    MARKERS.iter().find_map(|(marker, kind)| {
+        text.strip_prefix(marker).map(|rest| {
+            let selector = if rest.starts_with(VARIATION_SELECTOR) {
+                VARIATION_SELECTOR.len_utf8()
+            } else {
+                0
+            };
+            (*kind, marker.len() + selector)
+        })
+    })
+}
+
+fn parse_segments(body: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut idx = 0;
+    while let Some(ch) = body[idx..].chars().next() {
+        if let Some((kind, marker_len)) = match_marker(&body[idx..]) {
+            if let Some((segment, consumed)) = parse_field(kind, &body[idx..], marker_len) {
+                if text_start < idx {
+                    segments.push(Segment::Text(body[text_start..idx].to_string()));
+                }
+                segments.push(segment);
+                idx += consumed;
+                text_start = idx;
+                continue;
+            }
+        }
+        idx += ch.len_utf8();
+    }
+    if text_start < body.len() {
+        segments.push(Segment::Text(body[text_start..].to_string()));
+    }
+    segments
+}
+
+/// Parse the value following a marker. Returns the segment and the number of
+/// bytes it spans, or `None` when the marker is not followed by a valid value
+/// (in which case it is kept as plain text).
+fn parse_field(kind: MarkerKind, text: &str, marker_len: usize) -> Option<(Segment, usize)> {
+    let after = &text[marker_len..];
+    let gap = after.len() - after.trim_start_matches([' ', '\t']).len();
+    let value = &after[gap..];
+    match kind {
+        MarkerKind::Priority(priority) => Some((
+            Segment::Priority {
+                priority,
+                raw: text[..marker_len].to_string(),
+            },
+            marker_len,
+        )),
+        MarkerKind::Date(field) => {
+            let date = NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+            let consumed = marker_len + gap + 10;
+            Some((
+                Segment::Date {
+                    field,
+                    date,
+                    raw: text[..consumed].to_string(),
+                },
+                consumed,
+            ))
+        }
+        MarkerKind::Id => {
+            let len = value
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_')))
+                .unwrap_or(value.len());
+            if len == 0 {
+                return None;
+            }
+            let consumed = marker_len + gap + len;
+            Some((
+                Segment::Id {
+                    id: value[..len].to_string(),
+                    raw: text[..consumed].to_string(),
+                },
+                consumed,
+            ))
+        }
+        MarkerKind::Recurrence => {
+            let end = recurrence_end(value);
+            let rule = value[..end].trim_end();
+            if rule.is_empty() {
+                return None;
+            }
+            let consumed = marker_len + gap + rule.len();
+            Some((
+                Segment::Recurrence {
+                    rule: rule.to_string(),
+                    raw: text[..consumed].to_string(),
+                },
+                consumed,
+            ))
+        }
+    }
+}
+
+/// A recurrence rule runs until the next marker, inline tag or block id.
+fn recurrence_end(value: &str) -> usize {
+    let mut prev_space = false;
+    for (idx, ch) in value.char_indices() {
+        if match_marker(&value[idx..]).is_some() || (prev_space && matches!(ch, '#' | '^')) {
+            return idx;
+        }
+        prev_space = ch.is_whitespace();
+    }
+    value.len()
+}
+
+fn split_block_id(text: &str) -> Option<(String, String)> {
+    let trimmed = text.trim_end();
+    let (idx, ch) = trimmed
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| ch.is_whitespace())?;
+    let start = idx + ch.len_utf8();
+    let candidate = &trimmed[start..];
+    let id = candidate.strip_prefix('^')?;
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    let before = trimmed[..start].trim_end().to_string();
+    Some((before, text[start..].to_string()))
+}
+
+/// Extract `#tag` tokens from free text. A tag must start the text or follow
+/// whitespace, may contain `/` for nesting, and cannot be purely numeric.
+pub fn extract_tags(text: &str) -> Vec<String> {
+    tags::inline_tags(text)
+        .into_iter()
+        .map(|(_, name)| format!("#{name}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_lines_with_multibyte_whitespace() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let mut task = TaskLine::parse("- [ ] 买菜\u{3000}牛奶").unwrap();
+        task.set_date(DateField::Done, Some(date));
+        task.set_id(Some("abc123"));
+        task.set_priority(Some(Priority::High));
+        assert_eq!(task.description(), "买菜\u{3000}牛奶");
+        assert_eq!(task.id(), Some("abc123"));
+        assert_eq!(task.date(DateField::Done), Some(date));
+        assert_eq!(task.priority(), Some(Priority::High));
+    }
+
+    #[test]
+    fn keeps_block_id_after_ideographic_space() {
+        let mut task = TaskLine::parse("- [ ] 写报告\u{3000}^block-1").unwrap();
+        task.set_id(Some("abc123"));
+        let rendered = task.render();
+        assert!(rendered.ends_with("^block-1"), "{rendered}");
+        assert!(rendered.contains("🆔 abc123"), "{rendered}");
+    }
+}
@@ -0,0 +1,946 @@
+mod ids;
+mod line;
+mod query;
+mod recurrence;
+mod rollover;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+
+use crate::config::AppContext;
+use crate::core::markdown::{self, FenceTracker};
+use crate::core::storage::{self, FileLock};
+use crate::core::walk;
+use crate::error::Error;
+use crate::templates::{self, TemplateContext, TemplateKind};
+use ids::IdRegistry;
+
+pub use line::{extract_tags, DateField, Priority, TaskLine};
+pub use query::{TaskGroup, TaskQuery};
+pub use recurrence::Recurrence;
+pub use rollover::{rollover, Rollover};
+
+pub struct TaskService {
+    root: PathBuf,
+    tasks_file: PathBuf,
+    /// Template files and folders, whose checkboxes are not tasks.
+    templates: Vec<PathBuf>,
+    auto_complete_parents: bool,
+}
+
+impl TaskService {
+    pub fn new(ctx: &AppContext) -> Result<Self> {
+        let vault_root = ctx.vault_root().to_path_buf();
+        let tasks_file = vault_root.join("Tasks/tasks.md");
+        if !tasks_file.exists() {
+            let _lock = FileLock::vault(&vault_root)?;
+            if !tasks_file.exists() {
+                let template =
+                    templates::load(ctx.config(), &vault_root, TemplateKind::Task, None)?;
+                let context = TemplateContext::new(
+                    ctx.config(),
+                    &vault_root,
+                    Local::now().date_naive(),
+                    "tasks",
+                );
+                storage::write_atomic(&tasks_file, templates::render(&template, &context)?)?;
+            }
+        }
+        Ok(Self {
+            templates: templates::vault_paths(ctx.config(), &vault_root),
+            root: vault_root,
+            tasks_file,
+            auto_complete_parents: ctx.config().tasks.auto_complete_parents,
+        })
+    }
+
+    /// Append a task to `Tasks/tasks.md` with a freshly issued `🆔` id, or
+    /// nest it as the last subtask of `new_task.parent`.
+    pub fn add_task(&self, new_task: NewTask) -> Result<String> {
+        let _lock = self.lock()?;
+        let (records, mut registry) = self.scan(true)?;
+        let id = registry.issue();
+        let line = new_task.render(&id);
+        match &new_task.parent {
+            Some(parent_id) => {
+                let parent_id = normalize_id(parent_id);
+                // Match the indentation of existing subtasks, else nest one
+                // tab deeper as Obsidian does.
+                let indent = records
+                    .iter()
+                    .find(|r| r.parent.as_deref() == Some(parent_id.as_str()))
+                    .map(|child| child.line.indent().to_string());
+                let parent = find_record(&records, &parent_id)?;
+                let indent = indent.unwrap_or_else(|| format!("{}\t", parent.line.indent()));
+                let path = self.root.join(&parent.source);
+                let mut lines = read_lines(&path)?;
+                self.label(&path, &mut lines, &records, &mut registry);
+                let at = block_end(&lines, parent.line_number - 1);
+                lines.insert(at, format!("{indent}{line}"));
+                write_lines(&path, lines)?;
+            }
+            None => {
+                let mut lines = read_lines(&self.tasks_file)?;
+                self.label(&self.tasks_file, &mut lines, &records, &mut registry);
+                lines.push(line);
+                write_lines(&self.tasks_file, lines)?;
+            }
+        }
+        registry.save()?;
+        Ok(id)
+    }
+
+    pub fn mark_done(&self, id: &str) -> Result<StatusUpdate> {
+        self.set_status(id, true)
+    }
+
+    /// Update a task's checkbox in whichever note it lives in. Completing an
+    /// open recurring task inserts its next occurrence after it and the
+    /// subtasks nested under it, which stay with the completed task.
+    /// With `[tasks] auto_complete_parents`, completing the last open
+    /// subtask also completes its parents.
+    pub fn set_status(&self, id: &str, done: bool) -> Result<StatusUpdate> {
+        let id = normalize_id(id);
+        let _lock = self.lock()?;
+        let (records, mut registry) = self.scan(true)?;
+        let parents = if done && self.auto_complete_parents {
+            completed_ancestors(&records, &id)
+        } else {
+            Vec::new()
+        };
+        let record = find_record(&records, &id)?;
+        let path = self.root.join(&record.source);
+        let mut lines = read_lines(&path)?;
+        self.label(&path, &mut lines, &records, &mut registry);
+        let idx = record.line_number - 1;
+
+        let today = Local::now().date_naive();
+        let mut next = if done && !record.done() {
+            record
+                .next_occurrence(&mut registry, today)
+                .with_context(|| format!("regenerate recurring task {id}"))?
+        } else {
+            None
+        };
+        lines[idx] = record.into_status_line(done);
+        // A new occurrence is another open subtask, so parents stay open.
+        let parents = if next.is_some() { Vec::new() } else { parents };
+        for parent in &parents {
+            lines[parent.line_number - 1] = parent.clone().into_status_line(true);
+        }
+        if let Some(next) = &mut next {
+            let at = block_end(&lines, idx);
+            next.line_number = at + 1;
+            lines.insert(at, next.line.render());
+        }
+        write_lines(&path, lines)?;
+        registry.save()?;
+        Ok(StatusUpdate {
+            next: next.map(TaskRecord::into_entry),
+            completed_parents: parents.into_iter().map(|parent| parent.id).collect(),
+        })
+    }
+
+    /// Change the fields named in `edit`, leaving the rest of the line as
+    /// written, and return the updated task.
+    pub fn edit_task(&self, id: &str, edit: &TaskEdit) -> Result<TaskEntry> {
+        let id = normalize_id(id);
+        if edit.is_empty() {
+            return Err(Error::usage("no task fields to change").into());
+        }
+        let _lock = self.lock()?;
+        let (records, mut registry) = self.scan(true)?;
+        let mut record = find_record(&records, &id)?;
+        let recurrence = match &edit.recurrence {
+            Some(Some(rule)) => Some(Some(Recurrence::parse(rule)?.to_string())),
+            Some(None) => Some(None),
+            None => None,
+        };
+
+        let line = &mut record.line;
+        // A legacy `(N)` prefix is part of the description; keep the id when
+        // the title is rewritten.
+        if line.id().is_none() {
+            line.set_description(&record.title);
+            line.set_id(Some(&id));
+        }
+        if let Some(title) = &edit.title {
+            let title = title.trim();
+            if title.is_empty() {
+                return Err(Error::usage("task title must not be empty").into());
+            }
+            line.set_description(title);
+        }
+        for (field, value) in [
+            (DateField::Due, edit.due),
+            (DateField::Scheduled, edit.scheduled),
+            (DateField::Start, edit.start),
+        ] {
+            if let Some(date) = value {
+                line.set_date(field, date);
+            }
+        }
+        if let Some(priority) = edit.priority {
+            line.set_priority(priority);
+        }
+        if let Some(rule) = &recurrence {
+            line.set_recurrence(rule.as_deref());
+        }
+        for tag in &edit.remove_tags {
+            line.remove_tag(tag);
+        }
+        for tag in &edit.add_tags {
+            line.add_tag(tag);
+        }
+
+        let path = self.root.join(&record.source);
+        let mut lines = read_lines(&path)?;
+        self.label(&path, &mut lines, &records, &mut registry);
+        lines[record.line_number - 1] = record.line.render();
+        write_lines(&path, lines)?;
+        registry.save()?;
+        record.title = split_legacy_id(&record.line.description()).1;
+        Ok(record.into_entry())
+    }
+
+    /// Remove a task's line, and the subtasks and notes nested under it, from
+    /// its note and return the removed task.
+    pub fn delete_task(&self, id: &str) -> Result<TaskEntry> {
+        let id = normalize_id(id);
+        let _lock = self.lock()?;
+        let (records, mut registry) = self.scan(true)?;
+        let record = find_record(&records, &id)?;
+        let path = self.root.join(&record.source);
+        let mut lines = read_lines(&path)?;
+        self.label(&path, &mut lines, &records, &mut registry);
+        let start = record.line_number - 1;
+        lines.drain(start..block_end(&lines, start));
+        write_lines(&path, lines)?;
+        registry.save()?;
+        Ok(record.into_entry())
+    }
+
+    /// Move a task with everything nested under it to the end of `note` (a
+    /// vault-relative path, `.md` optional), or to the end of its `section`
+    /// heading, which is created if missing. The task lands unindented, its
+    /// subtasks keeping their depth below it. The note itself is created
+    /// when it does not exist.
+    pub fn move_task(&self, id: &str, note: &str, section: Option<&str>) -> Result<TaskEntry> {
+        let id = normalize_id(id);
+        let note = note.trim().trim_start_matches('/');
+        let relative = if walk::is_markdown(Path::new(note)) {
+            note.to_string()
+        } else {
+            format!("{note}.md")
+        };
+        let destination = self.root.join(&relative);
+        if note.is_empty()
+            || !walk::is_relative_inside(Path::new(&relative))
+            || self.is_template(&destination)
+        {
+            return Err(Error::usage(format!("invalid destination note: {note}")).into());
+        }
+
+        let _lock = self.lock()?;
+        let (records, mut registry) = self.scan(true)?;
+        let record = find_record(&records, &id)?;
+        let source = self.root.join(&record.source);
+        let mut source_lines = read_lines(&source)?;
+        self.label(&source, &mut source_lines, &records, &mut registry);
+        let start = record.line_number - 1;
+        let end = block_end(&source_lines, start);
+        let block: Vec<String> = source_lines.drain(start..end).collect();
+        let indent = record.line.indent().to_string();
+        let entry = block
+            .iter()
+            .map(|line| {
+                line.strip_prefix(indent.as_str())
+                    .unwrap_or(line.trim_start())
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let entry = entry.as_str();
+
+        let text = if destination == source {
+            source_lines.join("\n") + "\n"
+        } else if destination.exists() {
+            let mut lines = read_lines(&destination)?;
+            self.label(&destination, &mut lines, &records, &mut registry);
+            if lines.is_empty() {
+                String::new()
+            } else {
+                lines.join("\n") + "\n"
+            }
+        } else {
+            String::new()
+        };
+        let text = match section {
+            Some(section) => markdown::insert_in_section(&text, section, entry),
+            None => {
+                let mut lines: Vec<&str> = text.lines().collect();
+                lines.push(entry);
+                lines.join("\n") + "\n"
+            }
+        };
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write the destination first so a failure never loses the task.
+        storage::write_atomic(&destination, text)?;
+        if destination != source {
+            write_lines(&source, source_lines)?;
+        }
+        registry.save()?;
+
+        let (records, _) = self.scan(false)?;
+        Ok(find_record(&records, &id)?.into_entry())
+    }
+
+    pub fn list_tasks(&self, filter: TaskFilter) -> Result<Vec<String>> {
+        let records = self.read_records()?;
+        let items = records
+            .into_iter()
+            .filter(|r| match filter {
+                TaskFilter::All => true,
+                TaskFilter::Open => !r.done(),
+                TaskFilter::Done => r.done(),
+            })
+            .map(|r| r.line.render().trim().to_string())
+            .collect();
+        Ok(items)
+    }
+
+    /// Evaluate a task query against every task, evaluating relative dates
+    /// such as `today` against the local date.
+    pub fn query(&self, query: &TaskQuery) -> Result<Vec<TaskGroup>> {
+        Ok(query.apply(self.tasks()?, Local::now().date_naive()))
+    }
+
+    /// Remove completed tasks from `Tasks/tasks.md`. Tasks in other notes are
+    /// left alone so journals keep their history.
+    /// Returns the number of tasks removed.
+    pub fn clean_completed(&self) -> Result<usize> {
+        let _lock = self.lock()?;
+        let lines = read_lines(&self.tasks_file)?;
+        let total = lines.len();
+        let kept: Vec<String> = lines
+            .into_iter()
+            .filter(|line| TaskRecord::parse(line).map(|r| !r.done()).unwrap_or(true))
+            .collect();
+        let removed = total - kept.len();
+        write_lines(&self.tasks_file, kept)?;
+        Ok(removed)
+    }
+
+    pub fn find_task_by_title(&self, title: &str) -> Result<Option<TaskEntry>> {
+        let title_lower = title.to_lowercase();
+        for record in self.read_records()? {
+            if record.title.to_lowercase() == title_lower {
+                return Ok(Some(record.into_entry()));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn tasks(&self) -> Result<Vec<TaskEntry>> {
+        self.read_records()
+            .map(|records| records.into_iter().map(TaskRecord::into_entry).collect())
+    }
+
+    /// Write ids to checkboxes without one across the vault, and rewrite
+    /// legacy `(N)` prefixes as `🆔 N` fields. A number already used by
+    /// another task gets a fresh id instead. Returns the number of legacy tasks migrated.
+    pub fn migrate_ids(&self) -> Result<usize> {
+        let _lock = self.lock()?;
+        let (records, mut registry) = self.scan(true)?;
+        let mut claimed: HashSet<String> = records
+            .iter()
+            .filter(|r| r.line.id().is_some())
+            .map(|r| r.id.clone())
+            .collect();
+        let mut migrated = 0;
+        for path in self.task_files()? {
+            let mut lines = read_lines(&path)?;
+            let mut changed = self.label(&path, &mut lines, &records, &mut registry);
+            for idx in task_line_indices(&lines) {
+                let Some(mut line) = TaskLine::parse(&lines[idx]) else {
+                    continue;
+                };
+                let (legacy, title) = split_legacy_id(&line.description());
+                let Some(legacy) = legacy else {
+                    continue;
+                };
+                line.set_description(&title);
+                if line.id().is_none() {
+                    let id = if claimed.insert(legacy.clone()) {
+                        legacy
+                    } else {
+                        let fresh = registry.issue();
+                        claimed.insert(fresh.clone());
+                        fresh
+                    };
+                    line.set_id(Some(&id));
+                }
+                lines[idx] = line.render();
+                changed = true;
+                migrated += 1;
+            }
+            if changed {
+                write_lines(&path, lines)?;
+            }
+        }
+        registry.save()?;
+        Ok(migrated)
+    }
+
+    /// Markdown files that may contain tasks: `Tasks/tasks.md` first, then
+    /// every other note in the vault except templates.
+    fn task_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = vec![self.tasks_file.clone()];
+        files.extend(
+            walk::markdown_files(&self.root)?
+                .into_iter()
+                .filter(|path| *path != self.tasks_file && !self.is_template(path)),
+        );
+        Ok(files)
+    }
+
+    fn is_template(&self, path: &Path) -> bool {
+        self.templates
+            .iter()
+            .any(|template| path.starts_with(template))
+    }
+
+    /// Hold the vault lock for a whole read-modify-write so concurrent CLI
+    /// and MCP processes never interleave edits or issue the same id.
+    fn lock(&self) -> Result<FileLock> {
+        FileLock::vault(&self.root)
+    }
+
+    fn read_records(&self) -> Result<Vec<TaskRecord>> {
+        let _lock = self.lock()?;
+        self.scan(false).map(|(records, _)| records)
+    }
+
+    /// Parse every task in the vault without writing to any note. A
+    /// checkbox without an id gets one derived from its note and text, so
+    /// reads show the id that [`Self::label`] later writes. With `record`,
+    /// the ids already written in notes are added to the registry.
+    fn scan(&self, record: bool) -> Result<(Vec<TaskRecord>, IdRegistry)> {
+        let mut registry = IdRegistry::load(&self.root)?;
+        let mut notes = Vec::new();
+        for path in self.task_files()? {
+            let lines = read_lines(&path)?;
+            notes.push((path, lines));
+        }
+        // Derived ids must not clash with one written further down the vault.
+        let mut taken: HashSet<String> = notes
+            .iter()
+            .flat_map(|(_, lines)| {
+                task_line_indices(lines)
+                    .into_iter()
+                    .filter_map(|idx| TaskRecord::parse(&lines[idx]).map(|record| record.id))
+            })
+            .collect();
+
+        let mut records = Vec::new();
+        for (path, lines) in notes {
+            let source = walk::relative_path(&self.root, &path);
+            let mut file_records = Vec::new();
+            // Occurrences of each unlabelled line, to tell identical ones apart.
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for idx in task_line_indices(&lines) {
+                let mut task = match TaskRecord::parse(&lines[idx]) {
+                    Some(task) => {
+                        if record {
+                            registry.record(&task.id);
+                        }
+                        task
+                    }
+                    None => match TaskLine::parse(&lines[idx]) {
+                        Some(mut line) if !line.description().is_empty() => {
+                            let occurrence = seen.entry(lines[idx].trim().to_string()).or_default();
+                            let seed = format!("{source}\n{}\n{occurrence}", lines[idx].trim());
+                            *occurrence += 1;
+                            let id = registry.derive(&seed, &taken);
+                            taken.insert(id.clone());
+                            line.set_id(Some(&id));
+                            match TaskRecord::parse(&line.render()) {
+                                Some(task) => TaskRecord {
+                                    labelled: false,
+                                    ..task
+                                },
+                                None => continue,
+                            }
+                        }
+                        _ => continue,
+                    },
+                };
+                task.source = source.clone();
+                task.line_number = idx + 1;
+                file_records.push(task);
+            }
+            link_subtasks(&lines, &mut file_records);
+            records.extend(file_records);
+        }
+        Ok((records, registry))
+    }
+
+    /// Write the derived ids of the unlabelled tasks in `path` into its
+    /// `lines`, read under the same lock as `records`, and record them in
+    /// `registry`. Notes that are not being rewritten are left to
+    /// [`Self::migrate_ids`]. Returns whether any line changed.
+    fn label(
+        &self,
+        path: &Path,
+        lines: &mut [String],
+        records: &[TaskRecord],
+        registry: &mut IdRegistry,
+    ) -> bool {
+        let source = walk::relative_path(&self.root, path);
+        let mut changed = false;
+        for task in records
+            .iter()
+            .filter(|task| !task.labelled && task.source == source)
+        {
+            lines[task.line_number - 1] = task.line.render();
+            registry.record(&task.id);
+            changed = true;
+        }
+        changed
+    }
+}
+
+fn read_lines(path: &Path) -> Result<Vec<String>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("read tasks from {}", path.display()))?;
+    Ok(content.lines().map(|line| line.to_string()).collect())
+}
+
+fn write_lines(path: &Path, lines: Vec<String>) -> Result<()> {
+    let text = if lines.is_empty() {
+        String::new()
+    } else {
+        lines.join("\n") + "\n"
+    };
+    storage::write_atomic(path, text)
+}
+
+fn find_record(records: &[TaskRecord], id: &str) -> Result<TaskRecord> {
+    records
+        .iter()
+        .find(|r| r.id == id)
+        .cloned()
+        .ok_or_else(|| Error::not_found(format!("task {id} was not found")).into())
+}
+
+/// Indices of checkbox lines outside fenced code blocks, skipping tasks
+/// migrated to a later note.
+fn task_line_indices(lines: &[String]) -> Vec<usize> {
+    let mut fences = FenceTracker::default();
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| {
+            fences.is_prose(line) && TaskLine::parse(line).is_some_and(|t| !t.is_migrated())
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Set each task's parent to the nearest task above it with a smaller list
+/// indent, and count the subtasks of every parent. Other list items
+/// between them keep the nesting; unindented text ends it.
+fn link_subtasks(lines: &[String], records: &mut [TaskRecord]) {
+    let by_line: HashMap<usize, usize> = records
+        .iter()
+        .enumerate()
+        .map(|(pos, record)| (record.line_number - 1, pos))
+        .collect();
+    let mut fences = FenceTracker::default();
+    // (indent width, record position) of the enclosing tasks.
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if !fences.is_prose(line) || line.trim().is_empty() {
+            continue;
+        }
+        let width = indent_width(line);
+        let is_task = by_line.get(&idx).copied();
+        if is_task.is_some() || markdown::list_marker(line).is_some() {
+            while stack.last().is_some_and(|(w, _)| *w >= width) {
+                stack.pop();
+            }
+        } else if width == 0 {
+            stack.clear();
+        }
+        let Some(pos) = is_task else {
+            continue;
+        };
+        if let Some(&(_, parent)) = stack.last() {
+            records[pos].parent = Some(records[parent].id.clone());
+            let done = records[pos].done();
+            let progress = records[parent]
+                .progress
+                .get_or_insert_with(Progress::default);
+            progress.total += 1;
+            progress.done += usize::from(done);
+        }
+        stack.push((width, pos));
+    }
+}
+
+/// Parents of task `id` that are finished once it is done, innermost first.
+/// Stops at a parent with other open subtasks, or that recurs.
+fn completed_ancestors(records: &[TaskRecord], id: &str) -> Vec<TaskRecord> {
+    let mut finished: HashSet<&str> = HashSet::from([id]);
+    let mut parents = Vec::new();
+    let mut current = records.iter().find(|r| r.id == id);
+    while let Some(parent_id) = current.and_then(|r| r.parent.as_deref()) {
+        let Some(parent) = records.iter().find(|r| r.id == parent_id) else {
+            break;
+        };
+        let all_done = records
+            .iter()
+            .filter(|r| r.parent.as_deref() == Some(parent_id))
+            .all(|r| r.done() || finished.contains(r.id.as_str()));
+        if !all_done || parent.done() || parent.line.recurrence().is_some() {
+            break;
+        }
+        finished.insert(&parent.id);
+        parents.push(parent.clone());
+        current = Some(parent);
+    }
+    parents
+}
+
+/// Index just past the list item at `start` and everything nested under it,
+/// ignoring trailing blank lines.
+fn block_end(lines: &[String], start: usize) -> usize {
+    let width = indent_width(&lines[start]);
+    let mut end = start + 1;
+    for (idx, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indent_width(line) <= width || markdown::is_heading(line) {
+            break;
+        }
+        end = idx + 1;
+    }
+    end
+}
+
+/// Columns of leading whitespace, counting a tab as four.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Split a `(N) title` description written by older releases into its
+/// numeric id and the remaining title.
+fn split_legacy_id(description: &str) -> (Option<String>, String) {
+    let legacy = description.strip_prefix('(').and_then(|rest| {
+        let end = rest.find(')')?;
+        let digits = &rest[..end];
+        (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+            .then(|| (digits.to_string(), rest[end + 1..].trim().to_string()))
+    });
+    match legacy {
+        Some((id, title)) => (Some(id), title),
+        None => (None, description.to_string()),
+    }
+}
+
+/// Accept ids as printed by the CLI (`#abc123`) or pasted with the marker.
+pub fn normalize_id(id: &str) -> String {
+    id.trim()
+        .trim_start_matches("🆔")
+        .trim()
+        .trim_start_matches('#')
+        .to_string()
+}
+
+/// Field changes for [`TaskService::edit_task`]. `None` leaves a field as
+/// it is; `Some(None)` removes it.
+#[derive(Debug, Clone, Default)]
+pub struct TaskEdit {
+    pub title: Option<String>,
+    pub due: Option<Option<NaiveDate>>,
+    pub scheduled: Option<Option<NaiveDate>>,
+    pub start: Option<Option<NaiveDate>>,
+    pub priority: Option<Option<Priority>>,
+    /// A recurrence rule such as `every week`, normalized before writing.
+    pub recurrence: Option<Option<String>>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+impl TaskEdit {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.due.is_none()
+            && self.scheduled.is_none()
+            && self.start.is_none()
+            && self.priority.is_none()
+            && self.recurrence.is_none()
+            && self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+    }
+}
+
+pub struct NewTask {
+    pub title: String,
+    pub due_date: Option<NaiveDate>,
+    pub recurrence: Option<String>,
+    pub priority: Option<Priority>,
+    /// Id of the task to nest this one under.
+    pub parent: Option<String>,
+}
+
+impl NewTask {
+    fn render(&self, id: &str) -> String {
+        let mut line = format!("- [ ] {}", self.title);
+        if let Some(due) = &self.due_date {
+            line.push_str(&format!(
+                " {} {}",
+                DateField::Due.marker(),
+                due.format("%Y-%m-%d")
+            ));
+        }
+        if let Some(repeat) = &self.recurrence {
+            line.push_str(&format!(" 🔁 {repeat}"));
+        }
+        if let Some(priority) = &self.priority {
+            line.push_str(&format!(" {}", priority.marker()));
+        }
+        line.push_str(&format!(" 🆔 {id}"));
+        line
+    }
+}
+
+pub enum TaskFilter {
+    All,
+    Open,
+    Done,
+}
+
+/// A parsed task with its Obsidian Tasks fields exposed as typed values.
+/// Serializes with dates as `YYYY-MM-DD` and the priority as its label.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskEntry {
+    pub id: String,
+    pub done: bool,
+    pub title: String,
+    pub raw: String,
+    pub due: Option<NaiveDate>,
+    pub scheduled: Option<NaiveDate>,
+    pub start: Option<NaiveDate>,
+    pub created: Option<NaiveDate>,
+    pub done_date: Option<NaiveDate>,
+    pub cancelled_date: Option<NaiveDate>,
+    pub priority: Option<Priority>,
+    pub recurrence: Option<String>,
+    pub tags: Vec<String>,
+    /// Note containing the task, relative to the vault root.
+    pub source: String,
+    /// 1-based line number of the task within `source`.
+    pub line_number: usize,
+    /// Id of the task this one is nested under.
+    pub parent: Option<String>,
+    /// Done and total counts of direct subtasks, if there are any.
+    pub progress: Option<Progress>,
+}
+
+impl TaskEntry {
+    /// The note under `Projects/` holding the task, or its
+    /// `#project/<name>` tag.
+    pub fn project(&self) -> Option<String> {
+        query::project(self)
+    }
+}
+
+/// Subtask completion of a parent task, shown as `done/total`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.done, self.total)
+    }
+}
+
+/// Outcome of [`TaskService::set_status`].
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusUpdate {
+    /// Next occurrence created by completing a recurring task.
+    pub next: Option<TaskEntry>,
+    /// Ids of parents completed along with their last open subtask.
+    pub completed_parents: Vec<String>,
+}
+
+/// Order tasks so that subtasks follow their parent, paired with their
+/// depth below the nearest ancestor in `tasks`. Siblings keep their order.
+pub fn tree(tasks: &[TaskEntry]) -> Vec<(usize, &TaskEntry)> {
+    let ids: HashSet<&str> = tasks.iter().map(|task| task.id.as_str()).collect();
+    let mut children: HashMap<&str, Vec<&TaskEntry>> = HashMap::new();
+    let mut roots = Vec::new();
+    for task in tasks {
+        match task.parent.as_deref().filter(|parent| ids.contains(parent)) {
+            Some(parent) => children.entry(parent).or_default().push(task),
+            None => roots.push(task),
+        }
+    }
+    let mut ordered = Vec::with_capacity(tasks.len());
+    let mut stack: Vec<(usize, &TaskEntry)> = roots.into_iter().rev().map(|t| (0, t)).collect();
+    while let Some((depth, task)) = stack.pop() {
+        ordered.push((depth, task));
+        if let Some(kids) = children.get(task.id.as_str()) {
+            stack.extend(kids.iter().rev().map(|kid| (depth + 1, *kid)));
+        }
+    }
+    ordered
+}
+
+#[derive(Clone)]
+struct TaskRecord {
+    id: String,
+    title: String,
+    line: TaskLine,
+    source: String,
+    line_number: usize,
+    parent: Option<String>,
+    progress: Option<Progress>,
+    /// Whether the id is written in the note, rather than derived by `scan`.
+    labelled: bool,
+}
+
+impl TaskRecord {
+    /// Parse a checkbox line carrying an id: a `🆔` field, or the `(N)`
+    /// prefix written by older releases.
+    fn parse(line: &str) -> Option<Self> {
+        let parsed = TaskLine::parse(line)?;
+        let (legacy, title) = split_legacy_id(&parsed.description());
+        let id = parsed.id().map(str::to_string).or(legacy)?;
+        Some(TaskRecord {
+            id,
+            title,
+            line: parsed,
+            source: String::new(),
+            line_number: 0,
+            parent: None,
+            progress: None,
+            labelled: true,
+        })
+    }
+
+    fn done(&self) -> bool {
+        self.line.is_done()
+    }
+
+    /// Build the follow-up of a recurring task: same text with an id issued
+    /// from `registry` and its due/scheduled/start dates shifted to the next
+    /// occurrence. Tasks that do not recur take no id. The caller sets the
+    /// line number once it has placed the line.
+    fn next_occurrence(
+        &self,
+        registry: &mut IdRegistry,
+        today: NaiveDate,
+    ) -> Result<Option<TaskRecord>> {
+        let Some(rule) = self.line.recurrence() else {
+            return Ok(None);
+        };
+        let recurrence = Recurrence::parse(rule)?;
+        let id = registry.issue();
+        let shifted = [DateField::Due, DateField::Scheduled, DateField::Start];
+        let base = shifted.iter().find_map(|field| self.line.date(*field));
+        let reference = match base {
+            Some(date) if !recurrence.when_done() => date,
+            _ => today,
+        };
+        let next = recurrence.next_after(reference)?;
+
+        let mut line = self.line.clone();
+        line.set_status(' ');
+        line.set_date(DateField::Done, None);
+        line.set_date(DateField::Cancelled, None);
+        match base {
+            Some(base) => {
+                let delta = next - base;
+                for field in shifted {
+                    if let Some(date) = line.date(field) {
+                        let shifted = date
+                            .checked_add_signed(delta)
+                            .ok_or_else(recurrence::out_of_range)?;
+                        line.set_date(field, Some(shifted));
+                    }
+                }
+            }
+            None => line.set_date(DateField::Due, Some(next)),
+        }
+        if line.date(DateField::Created).is_some() {
+            line.set_date(DateField::Created, Some(today));
+        }
+        line.set_description(&self.title);
+        line.set_id(Some(&id));
+        Ok(Some(TaskRecord {
+            id,
+            title: self.title.clone(),
+            line,
+            source: self.source.clone(),
+            line_number: 0,
+            parent: self.parent.clone(),
+            progress: None,
+            labelled: true,
+        }))
+    }
+
+    /// Flip the checkbox, stamping (or clearing) the `✅` completion date the
+    /// way the Obsidian Tasks plugin does.
+    fn into_status_line(mut self, done: bool) -> String {
+        if done {
+            self.line.set_status('x');
+            if self.line.date(DateField::Done).is_none() {
+                self.line
+                    .set_date(DateField::Done, Some(Local::now().date_naive()));
+            }
+        } else {
+            self.line.set_status(' ');
+            self.line.set_date(DateField::Done, None);
+        }
+        self.line.render()
+    }
+
+    fn into_entry(self) -> TaskEntry {
+        let line = &self.line;
+        TaskEntry {
+            id: self.id,
+            done: line.is_done(),
+            raw: line.render().trim().to_string(),
+            due: line.date(DateField::Due),
+            scheduled: line.date(DateField::Scheduled),
+            start: line.date(DateField::Start),
+            created: line.date(DateField::Created),
+            done_date: line.date(DateField::Done),
+            cancelled_date: line.date(DateField::Cancelled),
+            priority: line.priority(),
+            recurrence: line.recurrence().map(str::to_string),
+            tags: line.tags(),
+            title: self.title,
+            source: self.source,
+            line_number: self.line_number,
+            parent: self.parent,
+            progress: self.progress,
+        }
+    }
+}
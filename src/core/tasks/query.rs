@@ -0,0 +1,928 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use chrono::{Duration, NaiveDate};
+
+use super::{DateField, Priority, TaskEntry};
+use crate::core::periodic::Period;
+use crate::error::Error;
+
+/// A parsed task query such as
+/// `due before 2025-03-01 and priority >= medium and tag:#work and not done sort by due`.
+///
+/// Filters combine with `and`, `or`, `not` and parentheses (adjacent filters
+/// are implicitly and-ed), followed by optional `sort by`, `group by` and
+/// `limit` clauses.
+#[derive(Debug, Clone, Default)]
+pub struct TaskQuery {
+    filter: Option<Expr>,
+    sorts: Vec<SortKey>,
+    group: Option<GroupField>,
+    limit: Option<usize>,
+}
+
+/// Tasks sharing a `group by` value. Ungrouped queries yield a single group
+/// without a name.
+#[derive(Debug, Clone)]
+pub struct TaskGroup {
+    pub name: Option<String>,
+    pub tasks: Vec<TaskEntry>,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Pred(Predicate),
+}
+
+#[derive(Debug, Clone)]
+enum Predicate {
+    Done,
+    Overdue,
+    Recurring,
+    HasDate(DateField),
+    Date(DateField, Cmp, DateValue),
+    Priority(Cmp, u8),
+    Tag(String),
+    TitleIncludes(String),
+    PathIncludes(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DateValue {
+    Today,
+    Offset(i64),
+    Fixed(NaiveDate),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SortField {
+    Id,
+    Title,
+    Status,
+    Priority,
+    Date(DateField),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SortKey {
+    field: SortField,
+    reverse: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GroupField {
+    Status,
+    Priority,
+    Recurrence,
+    Tag,
+    Path,
+    Folder,
+    Date(DateField),
+    /// Overdue, today, this week, later or no due date.
+    Bucket,
+    /// The note under `Projects/`, or a `#project/<name>` tag.
+    Project,
+}
+
+impl TaskQuery {
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.query()?;
+        if let Some(token) = parser.peek() {
+            bail!(Error::usage(format!("unexpected `{token}` in task query")));
+        }
+        Ok(query)
+    }
+
+    /// Additionally require tasks to be done (or open).
+    pub fn with_status(self, done: bool) -> Self {
+        let pred = Expr::Pred(Predicate::Done);
+        if done {
+            self.and(pred)
+        } else {
+            self.and(Expr::Not(Box::new(pred)))
+        }
+    }
+
+    /// Additionally require the tag `tag` or a tag nested below it.
+    pub fn with_tag(self, tag: &str) -> Self {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        self.and(Expr::Pred(Predicate::Tag(tag)))
+    }
+
+    /// Additionally require the note path to contain `text`.
+    pub fn with_path(self, text: &str) -> Self {
+        self.and(Expr::Pred(Predicate::PathIncludes(text.to_lowercase())))
+    }
+
+    /// Additionally require a due date within `from..=to`; either bound may
+    /// be open.
+    pub fn with_due_range(mut self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        for (cmp, date) in [(Cmp::Ge, from), (Cmp::Le, to)] {
+            if let Some(date) = date {
+                let pred = Predicate::Date(DateField::Due, cmp, DateValue::Fixed(date));
+                self = self.and(Expr::Pred(pred));
+            }
+        }
+        self
+    }
+
+    /// Additionally require at least `priority`; tasks without one rank
+    /// between low and medium.
+    pub fn with_min_priority(self, priority: Priority) -> Self {
+        let rank = priority_rank(Some(priority));
+        self.and(Expr::Pred(Predicate::Priority(Cmp::Ge, rank)))
+    }
+
+    fn and(mut self, pred: Expr) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => Expr::And(Box::new(existing), Box::new(pred)),
+            None => pred,
+        });
+        self
+    }
+
+    /// Group by `field`, any field accepted by `group by`, replacing the
+    /// query's own grouping.
+    pub fn with_group(mut self, field: &str) -> Result<Self> {
+        self.group = TaskQuery::parse(&format!("group by {field}"))?.group;
+        Ok(self)
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.group.is_some()
+    }
+
+    pub fn matches(&self, task: &TaskEntry, today: NaiveDate) -> bool {
+        self.filter
+            .as_ref()
+            .map(|expr| expr.eval(task, today))
+            .unwrap_or(true)
+    }
+
+    /// Filter, sort, truncate and group `tasks`.
+    pub fn apply(&self, tasks: Vec<TaskEntry>, today: NaiveDate) -> Vec<TaskGroup> {
+        let mut selected: Vec<TaskEntry> = tasks
+            .into_iter()
+            .filter(|task| self.matches(task, today))
+            .collect();
+        // Stable sorts applied last-to-first leave the first key as primary.
+        for key in self.sorts.iter().rev() {
+            selected.sort_by(|a, b| key.compare(a, b));
+        }
+        if let Some(limit) = self.limit {
+            selected.truncate(limit);
+        }
+
+        let Some(group) = self.group else {
+            return vec![TaskGroup {
+                name: None,
+                tasks: selected,
+            }];
+        };
+        let mut groups: BTreeMap<(String, String), Vec<TaskEntry>> = BTreeMap::new();
+        for task in selected {
+            for key in group.keys(&task, today) {
+                groups.entry(key).or_default().push(task.clone());
+            }
+        }
+        groups
+            .into_iter()
+            .map(|((_, name), tasks)| TaskGroup {
+                name: Some(name),
+                tasks,
+            })
+            .collect()
+    }
+}
+
+impl FromStr for TaskQuery {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        TaskQuery::parse(s)
+    }
+}
+
+impl Expr {
+    fn eval(&self, task: &TaskEntry, today: NaiveDate) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(task, today) && b.eval(task, today),
+            Expr::Or(a, b) => a.eval(task, today) || b.eval(task, today),
+            Expr::Not(inner) => !inner.eval(task, today),
+            Expr::Pred(pred) => pred.eval(task, today),
+        }
+    }
+}
+
+impl Predicate {
+    fn eval(&self, task: &TaskEntry, today: NaiveDate) -> bool {
+        match self {
+            Predicate::Done => task.done,
+            Predicate::Overdue => !task.done && task.due.map(|due| due < today).unwrap_or(false),
+            Predicate::Recurring => task.recurrence.is_some(),
+            Predicate::HasDate(field) => task_date(task, *field).is_some(),
+            Predicate::Date(field, cmp, value) => task_date(task, *field)
+                .map(|date| cmp.holds(date.cmp(&value.resolve(today))))
+                .unwrap_or(false),
+            Predicate::Priority(cmp, rank) => cmp.holds(priority_rank(task.priority).cmp(rank)),
+            Predicate::Tag(wanted) => task.tags.iter().any(|tag| tag_matches(tag, wanted)),
+            Predicate::TitleIncludes(text) => task.title.to_lowercase().contains(text),
+            Predicate::PathIncludes(text) => task.source.to_lowercase().contains(text),
+        }
+    }
+}
+
+impl Cmp {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Cmp::Lt => ordering == Ordering::Less,
+            Cmp::Le => ordering != Ordering::Greater,
+            Cmp::Eq => ordering == Ordering::Equal,
+            Cmp::Ne => ordering != Ordering::Equal,
+            Cmp::Ge => ordering != Ordering::Less,
+            Cmp::Gt => ordering == Ordering::Greater,
+        }
+    }
+}
+
+impl DateValue {
+    fn resolve(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            DateValue::Today => today,
+            DateValue::Offset(days) => today + Duration::days(days),
+            DateValue::Fixed(date) => date,
+        }
+    }
+}
+
+impl SortKey {
+    fn compare(&self, a: &TaskEntry, b: &TaskEntry) -> Ordering {
+        let ordering = match self.field {
+            SortField::Id => a.id.cmp(&b.id),
+            SortField::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            SortField::Status => a.done.cmp(&b.done),
+            // Most urgent first, like the Obsidian Tasks plugin.
+            SortField::Priority => priority_rank(b.priority).cmp(&priority_rank(a.priority)),
+            // Tasks without the date sort after every dated task.
+            SortField::Date(field) => match (task_date(a, field), task_date(b, field)) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        };
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+impl GroupField {
+    /// `(sort key, display name)` pairs; tags may place a task in several groups.
+    fn keys(&self, task: &TaskEntry, today: NaiveDate) -> Vec<(String, String)> {
+        match self {
+            GroupField::Status => {
+                let (key, name) = if task.done {
+                    ("1", "Done")
+                } else {
+                    ("0", "Open")
+                };
+                vec![(key.to_string(), name.to_string())]
+            }
+            GroupField::Priority => {
+                let rank = priority_rank(task.priority);
+                let name = task.priority.map(|p| p.label()).unwrap_or("none");
+                vec![((9 - rank).to_string(), format!("Priority: {name}"))]
+            }
+            GroupField::Recurrence => match &task.recurrence {
+                Some(rule) => vec![(format!("0{rule}"), rule.clone())],
+                None => vec![("1".to_string(), "Not recurring".to_string())],
+            },
+            GroupField::Tag if task.tags.is_empty() => {
+                vec![("~".to_string(), "No tags".to_string())]
+            }
+            GroupField::Tag => task
+                .tags
+                .iter()
+                .map(|tag| (tag.to_lowercase(), tag.clone()))
+                .collect(),
+            GroupField::Path => vec![(task.source.to_lowercase(), task.source.clone())],
+            GroupField::Folder => {
+                let folder = match task.source.rsplit_once('/') {
+                    Some((folder, _)) => folder.to_string(),
+                    None => "/".to_string(),
+                };
+                vec![(folder.to_lowercase(), folder)]
+            }
+            GroupField::Date(field) => match task_date(task, *field) {
+                Some(date) => {
+                    let text = date.format("%Y-%m-%d").to_string();
+                    vec![(text.clone(), text)]
+                }
+                None => vec![("~".to_string(), format!("No {} date", field_name(*field)))],
+            },
+            GroupField::Bucket => {
+                let week_end = Period::Week.end(today);
+                let (key, name) = match task.due {
+                    _ if task.done => ("5", "Done"),
+                    None => ("4", "No due date"),
+                    Some(due) if due < today => ("0", "Overdue"),
+                    Some(due) if due == today => ("1", "Today"),
+                    Some(due) if due <= week_end => ("2", "This week"),
+                    Some(_) => ("3", "Later"),
+                };
+                vec![(key.to_string(), name.to_string())]
+            }
+            GroupField::Project => match project(task) {
+                Some(name) => vec![(name.to_lowercase(), name)],
+                None => vec![("~".to_string(), "No project".to_string())],
+            },
+        }
+    }
+}
+
+/// A task's project: the first folder or note below `Projects/`, otherwise
+/// its first `#project/<name>` tag.
+pub(super) fn project(task: &TaskEntry) -> Option<String> {
+    if let Some(rest) = task.source.strip_prefix("Projects/") {
+        let name = rest.split('/').next().unwrap_or(rest);
+        return Some(name.trim_end_matches(".md").to_string());
+    }
+    task.tags.iter().find_map(|tag| {
+        let name = tag.trim_start_matches('#').strip_prefix("project/")?;
+        Some(name.split('/').next().unwrap_or(name).to_string())
+    })
+}
+
+fn task_date(task: &TaskEntry, field: DateField) -> Option<NaiveDate> {
+    match field {
+        DateField::Due => task.due,
+        DateField::Scheduled => task.scheduled,
+        DateField::Start => task.start,
+        DateField::Created => task.created,
+        DateField::Done => task.done_date,
+        DateField::Cancelled => task.cancelled_date,
+    }
+}
+
+/// Rank priorities with "no priority" between low and medium, as Obsidian does.
+fn priority_rank(priority: Option<Priority>) -> u8 {
+    match priority {
+        Some(Priority::Lowest) => 0,
+        Some(Priority::Low) => 1,
+        None => 2,
+        Some(Priority::Medium) => 3,
+        Some(Priority::High) => 4,
+        Some(Priority::Highest) => 5,
+    }
+}
+
+/// `#work` matches `#work` and nested tags such as `#work/meetings`.
+fn tag_matches(tag: &str, wanted: &str) -> bool {
+    let tag = tag.trim_start_matches('#').to_lowercase();
+    tag == wanted || tag.starts_with(&format!("{wanted}/"))
+}
+
+fn field_name(field: DateField) -> &'static str {
+    match field {
+        DateField::Due => "due",
+        DateField::Scheduled => "scheduled",
+        DateField::Start => "start",
+        DateField::Created => "created",
+        DateField::Done => "done",
+        DateField::Cancelled => "cancelled",
+    }
+}
+
+fn parse_date_field(word: &str) -> Option<DateField> {
+    match word {
+        "due" => Some(DateField::Due),
+        "scheduled" => Some(DateField::Scheduled),
+        "start" | "starts" => Some(DateField::Start),
+        "created" => Some(DateField::Created),
+        "done" => Some(DateField::Done),
+        "cancelled" | "canceled" => Some(DateField::Cancelled),
+        _ => None,
+    }
+}
+
+fn parse_priority_level(word: &str) -> Option<u8> {
+    match word {
+        "lowest" => Some(0),
+        "low" => Some(1),
+        "none" | "normal" => Some(2),
+        "medium" => Some(3),
+        "high" => Some(4),
+        "highest" => Some(5),
+        _ => None,
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch == '(' || ch == ')' {
+            tokens.push(ch.to_string());
+            chars.next();
+        } else if ch == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => text.push(c),
+                    None => bail!(Error::usage("unterminated quote in task query")),
+                }
+            }
+            // Keep the quote so the parser can tell literals from keywords.
+            tokens.push(format!("\"{text}"));
+        } else if matches!(ch, '<' | '>' | '=' | '!') {
+            let mut op = String::new();
+            while let Some(&c) = chars.peek().filter(|c| matches!(c, '<' | '>' | '=' | '!')) {
+                op.push(c);
+                chars.next();
+            }
+            tokens.push(op);
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| {
+                !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | '<' | '>' | '=' | '!')
+            }) {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(word);
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn peek_lower(&self, offset: usize) -> Option<String> {
+        self.tokens
+            .get(self.pos + offset)
+            .map(|token| token.to_lowercase())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect_next(&mut self, what: &str) -> Result<String> {
+        let token = self
+            .next()
+            .ok_or_else(|| Error::usage(format!("task query ended early; expected {what}")))?;
+        Ok(token)
+    }
+
+    fn eat(&mut self, word: &str) -> bool {
+        if self.peek_lower(0).as_deref() == Some(word) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn at_clause(&self) -> bool {
+        match self.peek_lower(0).as_deref() {
+            Some("sort") | Some("group") => self.peek_lower(1).as_deref() == Some("by"),
+            Some("limit") => true,
+            _ => false,
+        }
+    }
+
+    fn query(&mut self) -> Result<TaskQuery> {
+        let mut query = TaskQuery::default();
+        if self.peek().is_some() && !self.at_clause() {
+            query.filter = Some(self.or_expr()?);
+        }
+        while self.peek().is_some() {
+            if self.eat("sort") {
+                self.eat("by");
+                let field = self.sort_field()?;
+                let reverse = self.eat("reverse") || self.eat("desc");
+                if !reverse {
+                    self.eat("asc");
+                }
+                query.sorts.push(SortKey { field, reverse });
+            } else if self.eat("group") {
+                self.eat("by");
+                query.group = Some(self.group_field()?);
+            } else if self.eat("limit") {
+                self.eat("to");
+                let count = self.expect_next("a number after `limit`")?;
+                query.limit =
+                    Some(count.parse().map_err(|_| {
+                        Error::usage(format!("invalid limit `{count}` in task query"))
+                    })?);
+                self.eat("tasks");
+            } else {
+                break;
+            }
+        }
+        Ok(query)
+    }
+
+    fn sort_field(&mut self) -> Result<SortField> {
+        let word = self.expect_next("a field after `sort by`")?.to_lowercase();
+        if let Some(field) = parse_date_field(&word) {
+            return Ok(SortField::Date(field));
+        }
+        match word.as_str() {
+            "id" => Ok(SortField::Id),
+            "title" | "description" => Ok(SortField::Title),
+            "status" => Ok(SortField::Status),
+            "priority" => Ok(SortField::Priority),
+            other => bail!(Error::usage(format!("cannot sort tasks by `{other}`"))),
+        }
+    }
+
+    fn group_field(&mut self) -> Result<GroupField> {
+        let word = self.expect_next("a field after `group by`")?.to_lowercase();
+        if let Some(field) = parse_date_field(&word) {
+            return Ok(GroupField::Date(field));
+        }
+        match word.as_str() {
+            "status" => Ok(GroupField::Status),
+            "priority" => Ok(GroupField::Priority),
+            "recurrence" | "recurring" => Ok(GroupField::Recurrence),
+            "tag" | "tags" => Ok(GroupField::Tag),
+            "path" | "file" => Ok(GroupField::Path),
+            "folder" => Ok(GroupField::Folder),
+            "bucket" | "when" => Ok(GroupField::Bucket),
+            "project" => Ok(GroupField::Project),
+            other => bail!(Error::usage(format!("cannot group tasks by `{other}`"))),
+        }
+    }
+
+    fn or_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.and_expr()?;
+        while self.eat("or") {
+            let rhs = self.and_expr()?;
+            expr = Expr::Or(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            let explicit = self.eat("and");
+            let implicit = !explicit
+                && self.peek().is_some()
+                && self.peek() != Some(")")
+                && self.peek_lower(0).as_deref() != Some("or")
+                && !self.at_clause();
+            if !explicit && !implicit {
+                break;
+            }
+            let rhs = self.unary()?;
+            expr = Expr::And(Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some("(") {
+            self.next();
+            let expr = self.or_expr()?;
+            if self.next().as_deref() != Some(")") {
+                bail!(Error::usage("missing `)` in task query"));
+            }
+            return Ok(expr);
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Expr> {
+        let pred = |p: Predicate| Ok(Expr::Pred(p));
+        let negated = |p: Predicate| Ok(Expr::Not(Box::new(Expr::Pred(p))));
+        let token = self.expect_next("a filter")?;
+        if let Some(text) = token.strip_prefix('"') {
+            return pred(Predicate::TitleIncludes(text.to_lowercase()));
+        }
+        if let Some(tag) = token.strip_prefix('#') {
+            return pred(Predicate::Tag(tag.to_lowercase()));
+        }
+        let word = token.to_lowercase();
+        if let Some(tag) = word
+            .strip_prefix("tag:")
+            .or_else(|| word.strip_prefix("tags:"))
+        {
+            return pred(Predicate::Tag(tag.trim_start_matches('#').to_string()));
+        }
+
+        // `done` is both a status and a date field: `done before today`.
+        if let Some(field) = parse_date_field(&word) {
+            if let Some(cmp) = self.date_cmp() {
+                let value = self.date_value()?;
+                return pred(Predicate::Date(field, cmp, value));
+            }
+        }
+
+        match word.as_str() {
+            "done" | "completed" => pred(Predicate::Done),
+            "open" | "todo" | "undone" => negated(Predicate::Done),
+            "overdue" => pred(Predicate::Overdue),
+            "recurring" => pred(Predicate::Recurring),
+            "is" if self.eat("recurring") => pred(Predicate::Recurring),
+            "is" if self.eat("not") && self.eat("recurring") => negated(Predicate::Recurring),
+            "has" | "no" => {
+                let field = self.expect_next("a date field")?.to_lowercase();
+                let field = parse_date_field(&field).ok_or_else(|| {
+                    Error::usage(format!("unknown date field `{field}` in task query"))
+                })?;
+                self.eat("date");
+                if word == "has" {
+                    pred(Predicate::HasDate(field))
+                } else {
+                    negated(Predicate::HasDate(field))
+                }
+            }
+            "priority" => {
+                let cmp = self.priority_cmp();
+                let level = self.expect_next("a priority level")?.to_lowercase();
+                let rank = parse_priority_level(&level).ok_or_else(|| {
+                    Error::usage(format!("unknown priority `{level}` in task query"))
+                })?;
+                pred(Predicate::Priority(cmp, rank))
+            }
+            "tag" | "tags" => {
+                if !(self.eat("includes") || self.eat("include") || self.eat("is")) {
+                    bail!(Error::usage(format!("expected `includes` after `{word}`")));
+                }
+                let tag = self.expect_next("a tag")?.to_lowercase();
+                pred(Predicate::Tag(tag.trim_start_matches('#').to_string()))
+            }
+            "title" | "description" | "path" => {
+                if !(self.eat("includes") || self.eat("contains")) {
+                    bail!(Error::usage(format!("expected `includes` after `{word}`")));
+                }
+                let text = self.expect_next("text to search for")?;
+                let text = text.trim_start_matches('"').to_lowercase();
+                if word == "path" {
+                    pred(Predicate::PathIncludes(text))
+                } else {
+                    pred(Predicate::TitleIncludes(text))
+                }
+            }
+            _ => bail!(Error::usage(format!(
+                "unknown filter `{token}` in task query"
+            ))),
+        }
+    }
+
+    fn date_cmp(&mut self) -> Option<Cmp> {
+        let word = self.peek_lower(0)?;
+        let cmp = match word.as_str() {
+            "before" | "<" => Cmp::Lt,
+            "after" | ">" => Cmp::Gt,
+            "<=" => Cmp::Le,
+            ">=" => Cmp::Ge,
+            "=" | "==" => Cmp::Eq,
+            "!=" => Cmp::Ne,
+            "on" => {
+                self.pos += 1;
+                if self.peek_lower(0).as_deref() == Some("or") {
+                    match self.peek_lower(1).as_deref() {
+                        Some("before") => {
+                            self.pos += 2;
+                            return Some(Cmp::Le);
+                        }
+                        Some("after") => {
+                            self.pos += 2;
+                            return Some(Cmp::Ge);
+                        }
+                        _ => {}
+                    }
+                }
+                return Some(Cmp::Eq);
+            }
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(cmp)
+    }
+
+    fn date_value(&mut self) -> Result<DateValue> {
+        let word = self.expect_next("a date")?.to_lowercase();
+        match word.as_str() {
+            "today" => Ok(DateValue::Today),
+            "tomorrow" => Ok(DateValue::Offset(1)),
+            "yesterday" => Ok(DateValue::Offset(-1)),
+            _ => match NaiveDate::parse_from_str(&word, "%Y-%m-%d") {
+                Ok(date) => Ok(DateValue::Fixed(date)),
+                Err(_) => bail!(Error::usage(format!(
+                    "invalid date `{word}` in task query (use YYYY-MM-DD, today, tomorrow or yesterday)"
+                ))),
+            },
+        }
+    }
+
+    fn priority_cmp(&mut self) -> Cmp {
+        let mut cmp = Cmp::Eq;
+        if self.eat("is") {
+            if self.eat("above") {
+                cmp = Cmp::Gt;
+            } else if self.eat("below") {
+                cmp = Cmp::Lt;
+            } else if self.eat("not") {
+                cmp = Cmp::Ne;
+            }
+            return cmp;
+        }
+        let Some(word) = self.peek_lower(0) else {
+            return cmp;
+        };
+        cmp = match word.as_str() {
+            "above" | ">" => Cmp::Gt,
+            "below" | "<" => Cmp::Lt,
+            ">=" => Cmp::Ge,
+            "<=" => Cmp::Le,
+            "=" | "==" => Cmp::Eq,
+            "!=" => Cmp::Ne,
+            _ => return Cmp::Eq,
+        };
+        self.pos += 1;
+        cmp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn task(title: &str) -> TaskEntry {
+        TaskEntry {
+            id: title.to_string(),
+            done: false,
+            title: title.to_string(),
+            raw: format!("- [ ] {title}"),
+            due: None,
+            scheduled: None,
+            start: None,
+            created: None,
+            done_date: None,
+            cancelled_date: None,
+            priority: None,
+            recurrence: None,
+            tags: Vec::new(),
+            source: "Tasks/tasks.md".to_string(),
+            line_number: 1,
+            parent: None,
+            progress: None,
+        }
+    }
+
+    /// Titles of `tasks` matching `query` on 2025-03-10.
+    fn matching(query: &str, tasks: &[TaskEntry]) -> Vec<String> {
+        let query = TaskQuery::parse(query).unwrap();
+        tasks
+            .iter()
+            .filter(|task| query.matches(task, date("2025-03-10")))
+            .map(|task| task.title.clone())
+            .collect()
+    }
+
+    fn sample() -> Vec<TaskEntry> {
+        let mut done = task("done");
+        done.done = true;
+        let mut work = task("work");
+        work.tags = vec!["#work/meetings".to_string()];
+        let mut urgent = task("urgent");
+        urgent.priority = Some(Priority::High);
+        let mut urgent_work = task("urgent work");
+        urgent_work.priority = Some(Priority::Highest);
+        urgent_work.tags = vec!["#work".to_string()];
+        vec![done, work, urgent, urgent_work]
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let tasks = sample();
+        assert_eq!(
+            matching("done or priority high and #work", &tasks),
+            ["done"]
+        );
+        assert_eq!(
+            matching("done or priority >= high and #work", &tasks),
+            ["done", "urgent work"]
+        );
+    }
+
+    #[test]
+    fn parentheses_group_filters() {
+        let tasks = sample();
+        assert_eq!(
+            matching("(done or #work) and not priority highest", &tasks),
+            ["done", "work"]
+        );
+        assert_eq!(matching("not (done or #work)", &tasks), ["urgent"]);
+    }
+
+    #[test]
+    fn adjacent_filters_are_and_ed() {
+        let tasks = sample();
+        assert_eq!(matching("open #work", &tasks), ["work", "urgent work"]);
+        assert_eq!(
+            matching("open #work priority above none", &tasks),
+            ["urgent work"]
+        );
+    }
+
+    #[test]
+    fn resolves_date_keywords_against_today() {
+        let tasks: Vec<TaskEntry> = ["2025-03-09", "2025-03-10", "2025-03-11", "2025-03-12"]
+            .iter()
+            .map(|due| {
+                let mut task = task(due);
+                task.due = Some(date(due));
+                task
+            })
+            .collect();
+        assert_eq!(matching("due before today", &tasks), ["2025-03-09"]);
+        assert_eq!(matching("due on today", &tasks), ["2025-03-10"]);
+        assert_eq!(matching("due after tomorrow", &tasks), ["2025-03-12"]);
+        assert_eq!(
+            matching("due on or before yesterday or due = 2025-03-12", &tasks),
+            ["2025-03-09", "2025-03-12"]
+        );
+        assert_eq!(
+            matching("due on or after tomorrow", &tasks),
+            ["2025-03-11", "2025-03-12"]
+        );
+        assert_eq!(matching("overdue", &tasks), ["2025-03-09"]);
+    }
+
+    #[test]
+    fn sorts_and_limits() {
+        let query = TaskQuery::parse("not done sort by priority limit 2").unwrap();
+        let groups = query.apply(sample(), date("2025-03-10"));
+        let titles: Vec<&str> = groups[0].tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["urgent work", "urgent"]);
+    }
+
+    #[test]
+    fn rejects_bad_input_as_usage_errors() {
+        for input in [
+            "due before someday",
+            "(done or #work",
+            "done)",
+            "priority urgent",
+            "bogus",
+            "\"unterminated",
+            "sort by colour",
+            "group by mood",
+            "limit many",
+            "tag #work",
+            "has nothing",
+        ] {
+            let err = TaskQuery::parse(input).unwrap_err();
+            assert_eq!(ErrorKind::of(&err), ErrorKind::Usage, "{input}: {err}");
+        }
+    }
+}
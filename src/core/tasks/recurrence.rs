@@ -0,0 +1,390 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+use crate::error::Error;
+
+/// A parsed `🔁` rule such as `every 2 weeks on Monday` or
+/// `every month on the 15th when done`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    when_done: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekdays,
+    Weekly(Vec<Weekday>),
+    Monthly(Option<MonthDay>),
+    Yearly,
+}
+
+/// The largest `every N ...` count a rule may use.
+const MAX_INTERVAL: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MonthDay {
+    Day(u32),
+    Last,
+}
+
+impl Recurrence {
+    pub fn parse(rule: &str) -> Result<Self> {
+        let normalized = rule.trim().to_lowercase().replace(',', " , ");
+        let mut words: Vec<&str> = normalized.split_whitespace().collect();
+        let when_done = words.ends_with(&["when", "done"]);
+        if when_done {
+            words.truncate(words.len() - 2);
+        }
+        let unsupported = || Error::usage(format!("unsupported recurrence rule: {rule:?}"));
+
+        let (frequency, interval) = match words.as_slice() {
+            ["daily"] => (Frequency::Daily, 1),
+            ["weekly"] => (Frequency::Weekly(Vec::new()), 1),
+            ["monthly"] => (Frequency::Monthly(None), 1),
+            ["yearly"] | ["annually"] => (Frequency::Yearly, 1),
+            ["every", "weekday"] | ["every", "weekdays"] => (Frequency::Weekdays, 1),
+            ["every", rest @ ..] => parse_every(rest).ok_or_else(unsupported)?,
+            _ => return Err(unsupported().into()),
+        };
+        if interval == 0 {
+            return Err(unsupported().into());
+        }
+        if interval > MAX_INTERVAL {
+            return Err(Error::usage(format!(
+                "recurrence interval out of range (at most {MAX_INTERVAL}): {rule:?}"
+            ))
+            .into());
+        }
+        Ok(Self {
+            frequency,
+            interval,
+            when_done,
+        })
+    }
+
+    /// Whether the next occurrence is computed from the completion date rather
+    /// than from the task's own dates.
+    pub fn when_done(&self) -> bool {
+        self.when_done
+    }
+
+    /// The first occurrence strictly after `date`. Fails when that falls
+    /// outside the calendar.
+    pub fn next_after(&self, date: NaiveDate) -> Result<NaiveDate> {
+        self.checked_next_after(date)
+            .ok_or_else(|| out_of_range().into())
+    }
+
+    fn checked_next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let n = self.interval;
+        let after_days =
+            |from: NaiveDate, days: i64| from.checked_add_signed(Duration::try_days(days)?);
+        match &self.frequency {
+            Frequency::Daily => after_days(date, n.into()),
+            Frequency::Weekdays => {
+                let mut next = after_days(date, 1)?;
+                while matches!(next.weekday(), Weekday::Sat | Weekday::Sun) {
+                    next = after_days(next, 1)?;
+                }
+                Some(next)
+            }
+            Frequency::Weekly(days) if days.is_empty() => after_days(date, i64::from(n) * 7),
+            Frequency::Weekly(days) => {
+                let from_monday = |day: &Weekday| day.num_days_from_monday();
+                let today = date.weekday().num_days_from_monday();
+                if let Some(later) = days.iter().map(from_monday).filter(|d| *d > today).min() {
+                    return after_days(date, (later - today).into());
+                }
+                let first = days.iter().map(from_monday).min().unwrap_or(0);
+                after_days(date, i64::from(n) * 7 + i64::from(first) - i64::from(today))
+            }
+            Frequency::Monthly(None) => add_months(date, n),
+            Frequency::Monthly(Some(day)) => {
+                let this_month = day_in_month(date, *day);
+                if this_month > date {
+                    Some(this_month)
+                } else {
+                    Some(day_in_month(add_months(first_of_month(date), n)?, *day))
+                }
+            }
+            Frequency::Yearly => add_months(date, n.checked_mul(12)?),
+        }
+    }
+}
+
+/// The error for a rule whose next occurrence cannot be represented.
+pub(crate) fn out_of_range() -> Error {
+    Error::usage("recurrence interval out of range")
+}
+
+impl FromStr for Recurrence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Recurrence::parse(s)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.interval;
+        let unit = |name: &str| {
+            if n == 1 {
+                format!("every {name}")
+            } else {
+                format!("every {n} {name}s")
+            }
+        };
+        let text = match &self.frequency {
+            Frequency::Daily => unit("day"),
+            Frequency::Weekdays => "every weekday".to_string(),
+            Frequency::Weekly(days) if days.is_empty() => unit("week"),
+            Frequency::Weekly(days) => {
+                let names: Vec<&str> = days.iter().map(|d| weekday_name(*d)).collect();
+                format!("{} on {}", unit("week"), names.join(", "))
+            }
+            Frequency::Monthly(None) => unit("month"),
+            Frequency::Monthly(Some(MonthDay::Last)) => format!("{} on the last", unit("month")),
+            Frequency::Monthly(Some(MonthDay::Day(day))) => {
+                format!("{} on the {}", unit("month"), ordinal(*day))
+            }
+            Frequency::Yearly => unit("year"),
+        };
+        if self.when_done {
+            write!(f, "{text} when done")
+        } else {
+            f.write_str(&text)
+        }
+    }
+}
+
+/// Parse the words following `every`.
+fn parse_every(words: &[&str]) -> Option<(Frequency, u32)> {
+    let (interval, rest) = match words {
+        ["other", rest @ ..] => (2, rest),
+        [count, rest @ ..] if count.parse::<u32>().is_ok() => (count.parse().ok()?, rest),
+        _ => (1, words),
+    };
+    let (unit, clause) = rest.split_first()?;
+    let clause = match clause {
+        ["on", tail @ ..] => tail,
+        _ => clause,
+    };
+    match unit.trim_end_matches('s') {
+        "day" if clause.is_empty() => Some((Frequency::Daily, interval)),
+        "week" => Some((Frequency::Weekly(parse_weekdays(clause)?), interval)),
+        "month" if clause.is_empty() => Some((Frequency::Monthly(None), interval)),
+        "month" => Some((Frequency::Monthly(Some(parse_month_day(clause)?)), interval)),
+        "year" if clause.is_empty() => Some((Frequency::Yearly, interval)),
+        _ if interval == 1 => {
+            // `every monday, thursday` is shorthand for a weekly rule.
+            Some((Frequency::Weekly(parse_weekdays(rest)?), 1))
+        }
+        _ => None,
+    }
+}
+
+fn parse_weekdays(words: &[&str]) -> Option<Vec<Weekday>> {
+    let mut days = Vec::new();
+    for word in words {
+        if matches!(*word, "," | "and") {
+            continue;
+        }
+        let day = parse_weekday(word)?;
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+    days.sort_by_key(|d| d.num_days_from_monday());
+    Some(days)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    let word = word.trim_end_matches('s');
+    let day = match word {
+        "monday" | "mon" => Weekday::Mon,
+        "tuesday" | "tue" | "tues" => Weekday::Tue,
+        "wednesday" | "wed" => Weekday::Wed,
+        "thursday" | "thu" | "thur" | "thurs" => Weekday::Thu,
+        "friday" | "fri" => Weekday::Fri,
+        "saturday" | "sat" => Weekday::Sat,
+        "sunday" | "sun" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(day)
+}
+
+fn parse_month_day(words: &[&str]) -> Option<MonthDay> {
+    let words = match words {
+        ["the", rest @ ..] => rest,
+        _ => words,
+    };
+    match words {
+        ["last"] | ["last", "day"] => Some(MonthDay::Last),
+        [day] | [day, "day"] => {
+            let digits = day.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            let value: u32 = digits.parse().ok()?;
+            (1..=31).contains(&value).then_some(MonthDay::Day(value))
+        }
+        _ => None,
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// Add calendar months, clamping to the end of shorter months.
+fn add_months(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    date.checked_add_months(Months::new(months))
+}
+
+fn day_in_month(date: NaiveDate, day: MonthDay) -> NaiveDate {
+    let last = last_day_of_month(date);
+    match day {
+        MonthDay::Last => date.with_day(last).unwrap_or(date),
+        MonthDay::Day(day) => date.with_day(day.min(last)).unwrap_or(date),
+    }
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    add_months(first_of_month(date), 1)
+        .and_then(|next| next.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{day}{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn next(rule: &str, from: &str) -> NaiveDate {
+        Recurrence::parse(rule)
+            .unwrap()
+            .next_after(date(from))
+            .unwrap()
+    }
+
+    #[test]
+    fn monthly_clamps_to_the_end_of_shorter_months() {
+        assert_eq!(next("every month", "2025-01-31"), date("2025-02-28"));
+        assert_eq!(next("every month", "2024-01-31"), date("2024-02-29"));
+        assert_eq!(
+            next("every month on the 31st", "2025-01-31"),
+            date("2025-02-28")
+        );
+        assert_eq!(
+            next("every month on the 31st", "2025-02-28"),
+            date("2025-03-31")
+        );
+        assert_eq!(
+            next("every month on the last", "2024-02-10"),
+            date("2024-02-29")
+        );
+        assert_eq!(
+            next("every 2 months on the 30th", "2023-12-30"),
+            date("2024-02-29")
+        );
+    }
+
+    #[test]
+    fn yearly_from_a_leap_day() {
+        assert_eq!(next("every year", "2024-02-29"), date("2025-02-28"));
+        assert_eq!(next("every 4 years", "2024-02-29"), date("2028-02-29"));
+    }
+
+    #[test]
+    fn weekly_rules() {
+        // 2025-03-10 is a Monday.
+        assert_eq!(
+            next("every week on Monday, Thursday", "2025-03-10"),
+            date("2025-03-13")
+        );
+        assert_eq!(
+            next("every 2 weeks on Monday", "2025-03-10"),
+            date("2025-03-24")
+        );
+        assert_eq!(next("every weekday", "2025-03-14"), date("2025-03-17"));
+        assert_eq!(next("every other day", "2025-02-28"), date("2025-03-02"));
+    }
+
+    #[test]
+    fn normalizes_rules() {
+        for (input, expected) in [
+            ("weekly", "every week"),
+            ("every Mon and fri", "every week on Monday, Friday"),
+            (
+                "Every 3 Months on the 1st when done",
+                "every 3 months on the 1st when done",
+            ),
+            ("every month on the last day", "every month on the last"),
+        ] {
+            assert_eq!(Recurrence::parse(input).unwrap().to_string(), expected);
+        }
+        assert!(Recurrence::parse("every day when done")
+            .unwrap()
+            .when_done());
+    }
+
+    #[test]
+    fn rejects_unsupported_rules_as_usage_errors() {
+        for input in [
+            "",
+            "every",
+            "every 0 days",
+            "every blue moon",
+            "every month on the 32nd",
+            "every 100000000 days",
+        ] {
+            let err = Recurrence::parse(input).unwrap_err();
+            assert_eq!(ErrorKind::of(&err), ErrorKind::Usage, "{input}");
+        }
+    }
+
+    #[test]
+    fn reports_occurrences_past_the_calendar_as_usage_errors() {
+        let rule = Recurrence::parse("every 1000 years").unwrap();
+        let err = rule.next_after(NaiveDate::MAX).unwrap_err();
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Usage);
+        assert_eq!(err.to_string(), "recurrence interval out of range");
+        let err = Recurrence::parse("every day")
+            .unwrap()
+            .next_after(NaiveDate::MAX)
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Usage);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::config::{RolloverConfig, RolloverMode};
+use crate::core::markdown::{self, FenceTracker};
+
+use super::{block_end, indent_width, TaskLine};
+
+/// Open tasks taken out of a daily note for the next one.
+#[derive(Debug)]
+pub struct Rollover {
+    /// The previous note with the tasks marked `[>]` or removed.
+    pub previous: String,
+    /// Blocks of lines to insert into the new note, by target heading, in
+    /// the order they appeared.
+    pub sections: Vec<(String, Vec<String>)>,
+}
+
+/// Collect the open tasks under the configured headings of `text`, each
+/// with the lines nested under it. In copy mode finished subtasks stay
+/// behind and the originals are marked `[>]`; in move mode whole blocks are
+/// cut. Returns `None` when nothing rolls over.
+pub fn rollover(text: &str, config: &RolloverConfig) -> Option<Rollover> {
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let mut fences = FenceTracker::default();
+    let mut heading: Option<String> = None;
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    let mut removed = vec![false; lines.len()];
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = &lines[idx];
+        if !fences.is_prose(line) {
+            idx += 1;
+            continue;
+        }
+        if let Some((_, title)) = markdown::heading(line) {
+            heading = Some(title.to_string());
+            idx += 1;
+            continue;
+        }
+        let selected = config.sections.is_empty()
+            || heading.as_deref().is_some_and(|heading| {
+                config
+                    .sections
+                    .iter()
+                    .any(|section| section.trim().eq_ignore_ascii_case(heading))
+            });
+        let open = TaskLine::parse(line).is_some_and(|task| rolls_over(&task));
+        if !selected || !open {
+            idx += 1;
+            continue;
+        }
+
+        let end = block_end(&lines, idx);
+        let indent = TaskLine::parse(line)
+            .map(|task| task.indent().to_string())
+            .unwrap_or_default();
+        let mut block = Vec::new();
+        // Width of a finished subtask whose nested lines stay behind with it.
+        let mut skip_under: Option<usize> = None;
+        for pos in idx..end {
+            let width = indent_width(&lines[pos]);
+            if pos > idx {
+                fences.is_prose(&lines[pos]);
+            }
+            if skip_under.is_some_and(|skip| lines[pos].trim().is_empty() || width > skip) {
+                continue;
+            }
+            skip_under = None;
+            let copied = lines[pos]
+                .strip_prefix(indent.as_str())
+                .unwrap_or(lines[pos].trim_start())
+                .to_string();
+            match (config.mode, TaskLine::parse(&lines[pos])) {
+                (RolloverMode::Move, _) => removed[pos] = true,
+                (RolloverMode::Copy, Some(mut task)) if rolls_over(&task) => {
+                    task.set_status('>');
+                    lines[pos] = task.render();
+                }
+                (RolloverMode::Copy, Some(_)) => {
+                    skip_under = Some(width);
+                    continue;
+                }
+                (RolloverMode::Copy, None) => {}
+            }
+            block.push(copied);
+        }
+
+        let target = config
+            .target
+            .clone()
+            .or_else(|| heading.clone())
+            .unwrap_or_else(|| "Tasks".to_string());
+        match sections
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&target))
+        {
+            Some((_, existing)) => existing.extend(block),
+            None => sections.push((target, block)),
+        }
+        idx = end;
+    }
+
+    if sections.is_empty() {
+        return None;
+    }
+    let kept: Vec<String> = lines
+        .into_iter()
+        .zip(removed)
+        .filter(|(_, removed)| !removed)
+        .map(|(line, _)| line)
+        .collect();
+    let mut previous = kept.join("\n");
+    if text.ends_with('\n') {
+        previous.push('\n');
+    }
+    Some(Rollover { previous, sections })
+}
+
+/// Open tasks with some text. An empty `- [ ] ` left by the daily template
+/// is a placeholder, as in [`markdown::insert_in_section`], and stays put.
+fn rolls_over(task: &TaskLine) -> bool {
+    task.is_open() && !task.description().trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_template_placeholders_behind() {
+        let note = "## Tasks\n\n- [ ] \n- [ ] call the bank\n\t- [ ] \n\n## Notes\n\n-\n";
+        let carried = rollover(note, &RolloverConfig::default()).unwrap();
+        assert_eq!(
+            carried.previous,
+            "## Tasks\n\n- [ ] \n- [>] call the bank\n\t- [ ] \n\n## Notes\n\n-\n"
+        );
+        assert_eq!(
+            carried.sections,
+            vec![("Tasks".to_string(), vec!["- [ ] call the bank".to_string()])]
+        );
+        assert!(rollover("## Tasks\n\n- [ ] \n", &RolloverConfig::default()).is_none());
+    }
+}
@@ -1,34 +1,110 @@
-use std::fs::{self, OpenOptions};
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{Local, NaiveDate};
+use serde::Serialize;
 
-use crate::config::AppContext;
-use crate::templates;
+use crate::config::{AppConfig, AppContext};
+use crate::core::frontmatter::{Document, PropertyValue};
+use crate::core::links::{Link, LinkGraph};
+use crate::core::markdown;
+use crate::core::patch::NotePatch;
+use crate::core::periodic::Period;
+use crate::core::storage::{self, FileLock};
+use crate::core::tasks;
+use crate::core::walk;
+use crate::error::Error;
+use crate::templates::{self, Prompt, TemplateContext, TemplateKind};
 
 pub struct VaultService {
     root: PathBuf,
-    journal_dir: PathBuf,
+    config: AppConfig,
+    daily_dir: PathBuf,
+    /// Extra template variables, e.g. from `--var`.
+    variables: BTreeMap<String, String>,
+    /// Answers `{{prompt:...}}` placeholders when creating notes.
+    prompt: Option<Box<Prompt>>,
 }
 
+/// Outcome of [`VaultService::move_note`].
+#[derive(Debug, Clone, Serialize)]
+pub struct MovedNote {
+    pub path: PathBuf,
+    pub links_updated: usize,
+    pub notes_updated: usize,
+}
+
+/// A daily note found by [`VaultService::list_recent`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyNote {
+    pub date: NaiveDate,
+    pub path: PathBuf,
+}
+
+/// Characters Obsidian does not allow in note names because they break links.
+const FORBIDDEN_NAME_CHARS: &[char] =
+    &['\\', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']'];
+
 impl VaultService {
     pub fn new(ctx: &AppContext) -> Result<Self> {
         let root = ctx.vault_root().to_path_buf();
-        let journal_dir = root.join("Journal");
-        fs::create_dir_all(&journal_dir)?;
-        Ok(Self { root, journal_dir })
+        let config = ctx.config().clone();
+        for period in Period::ALL {
+            config.periodic(period).validate(period)?;
+        }
+        config.append.validate()?;
+        let daily_dir = root.join(config.daily.folder());
+        fs::create_dir_all(&daily_dir)
+            .with_context(|| format!("create daily folder {}", daily_dir.display()))?;
+        Ok(Self {
+            root,
+            config,
+            daily_dir,
+            variables: BTreeMap::new(),
+            prompt: None,
+        })
     }
 
-    pub fn append_today(&self, text: &str) -> Result<()> {
-        let date = Local::now().date_naive();
-        self.append_for_date(date, text)
+    pub fn with_variables(mut self, variables: BTreeMap<String, String>) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Fn(&str) -> Result<String> + 'static) -> Self {
+        self.prompt = Some(Box::new(prompt));
+        self
+    }
+
+    /// Append an entry to today's daily note, under `section` (or the
+    /// configured `[append] section`) when given, formatted with the
+    /// configured bullet and timestamp.
+    pub fn append_today(&self, text: &str, section: Option<&str>) -> Result<()> {
+        let now = Local::now();
+        let append = &self.config.append;
+        // A timestamp goes after the list marker the entry already has.
+        let (mut entry, body) = match markdown::list_marker(text) {
+            Some(marker) => (marker.to_string(), &text[marker.len()..]),
+            None => (String::new(), text),
+        };
+        if let Some(bullet) = append.bullet.as_deref().map(str::trim) {
+            if !bullet.is_empty() && entry.is_empty() {
+                entry.push_str(bullet);
+                entry.push(' ');
+            }
+        }
+        if let Some(timestamp) = &append.timestamp {
+            entry.push_str(&now.format(timestamp).to_string());
+            entry.push(' ');
+        }
+        entry.push_str(body);
+        let section = section.or(append.section.as_deref());
+        self.append_for_date(now.date_naive(), &entry, section)
     }
 
     pub fn today_path(&self) -> PathBuf {
-        self.journal_dir
-            .join(Self::filename_for(Local::now().date_naive()))
+        self.daily_path(Local::now().date_naive())
     }
 
     pub fn path_for(&self, date: Option<&str>) -> Result<PathBuf> {
@@ -37,51 +113,420 @@ impl VaultService {
                 .with_context(|| format!("invalid date format: {text}"))?,
             None => Local::now().date_naive(),
         };
-        let path = self.journal_dir.join(Self::filename_for(target));
-        self.ensure_daily_file(&path, target)?;
+        self.periodic_note(Period::Day, target, None)
+    }
+
+    /// Path of the periodic note covering `date`, created from its template
+    /// (or the named alternative `template`) if missing.
+    pub fn periodic_note(
+        &self,
+        period: Period,
+        date: NaiveDate,
+        template: Option<&str>,
+    ) -> Result<PathBuf> {
+        let path = self.periodic_path(period, date);
+        let _lock = FileLock::vault(&self.root)?;
+        self.ensure_periodic_file(period, &path, date, template)?;
         Ok(path)
     }
 
-    pub fn list_recent(&self, limit: usize) -> Result<Vec<PathBuf>> {
-        let mut entries: Vec<_> = fs::read_dir(&self.journal_dir)?
-            .filter_map(|res| res.ok())
-            .map(|entry| entry.path())
-            .filter(|path| {
-                path.is_file() && path.extension().map(|ext| ext == "md").unwrap_or(false)
+    /// The most recent daily notes, newest first. Files in the daily folder
+    /// whose path does not match the configured format are ignored.
+    pub fn list_recent(&self, limit: usize) -> Result<Vec<DailyNote>> {
+        let mut entries: Vec<_> = walk::markdown_files(&self.daily_dir)?
+            .into_iter()
+            .filter_map(|path| {
+                Some(DailyNote {
+                    date: self.daily_date(&path)?,
+                    path,
+                })
             })
             .collect();
-        entries.sort_by(|a, b| b.cmp(a));
+        entries.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| b.path.cmp(&a.path)));
         entries.truncate(limit);
         Ok(entries)
     }
 
-    fn append_for_date(&self, date: NaiveDate, text: &str) -> Result<()> {
-        let path = self.journal_dir.join(Self::filename_for(date));
-        self.ensure_daily_file(&path, date)?;
-        let mut file = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(&path)
-            .with_context(|| format!("open daily note {}", path.display()))?;
-        writeln!(file, "{}", text)?;
-        Ok(())
+    /// The daily note path for `date`, following the `[daily]` config.
+    pub fn daily_path(&self, date: NaiveDate) -> PathBuf {
+        self.periodic_path(Period::Day, date)
+    }
+
+    /// The note path for the period containing `date`, following the
+    /// `[daily]`, `[weekly]`, ... config sections.
+    pub fn periodic_path(&self, period: Period, date: NaiveDate) -> PathBuf {
+        let config = self.config.periodic(period);
+        let name = Period::format(config.format(period), period.start(date));
+        self.root.join(config.folder()).join(format!("{name}.md"))
+    }
+
+    /// The date a daily note path stands for, if it matches the format.
+    pub fn daily_date(&self, path: &Path) -> Option<NaiveDate> {
+        path.strip_prefix(&self.daily_dir).ok()?;
+        let relative = walk::relative_path(&self.daily_dir, path);
+        let stem = relative.strip_suffix(".md")?;
+        NaiveDate::parse_from_str(stem, self.config.daily.format(Period::Day)).ok()
+    }
+
+    /// Locate a note by vault-relative path (with or without `.md`) or by
+    /// file name anywhere in the vault, preferring the shallowest match.
+    pub fn resolve_note(&self, name: &str) -> Result<PathBuf> {
+        let trimmed = name.trim().trim_start_matches('/');
+        for candidate in [trimmed.to_string(), format!("{trimmed}.md")] {
+            let path = self.vault_path(&candidate)?;
+            if path.is_file() && walk::is_markdown(&path) {
+                return Ok(path);
+            }
+        }
+        let file_name = trimmed.rsplit('/').next().unwrap_or(trimmed).to_lowercase();
+        let wanted = if file_name.ends_with(".md") {
+            file_name
+        } else {
+            format!("{file_name}.md")
+        };
+        walk::markdown_files(&self.root)?
+            .into_iter()
+            .filter(|path| {
+                path.file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase() == wanted)
+                    .unwrap_or(false)
+            })
+            .min_by_key(|path| path.components().count())
+            .ok_or_else(|| Error::not_found(format!("note not found: {name}")).into())
+    }
+
+    /// Create a note at a vault-relative path or title (`Projects/alpha`,
+    /// `Meeting notes`), rendered from `template` when given.
+    pub fn create_note(&self, name: &str, template: Option<&str>) -> Result<PathBuf> {
+        let path = self.note_path(name)?;
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let template = templates::load(&self.config, &self.root, TemplateKind::Note, template)?;
+        let content = templates::render(
+            &template,
+            &self.template_context(Local::now().date_naive(), &title),
+        )?;
+        self.write_new_note(path, content)
+    }
+
+    /// Create a note like [`create_note`](Self::create_note) with the given
+    /// content instead of a template.
+    pub fn create_note_with_content(&self, name: &str, content: &str) -> Result<PathBuf> {
+        let path = self.note_path(name)?;
+        self.write_new_note(path, content.to_string())
+    }
+
+    fn write_new_note(&self, path: PathBuf, content: String) -> Result<PathBuf> {
+        let _lock = FileLock::vault(&self.root)?;
+        if path.exists() {
+            return Err(Error::conflict(format!("note already exists: {}", path.display())).into());
+        }
+        storage::write_atomic(&path, content)?;
+        Ok(path)
+    }
+
+    /// Apply `patch` to a note under the vault lock, returning whether its
+    /// text changed.
+    pub fn patch_note(&self, path: &Path, patch: &NotePatch) -> Result<bool> {
+        let _lock = FileLock::vault(&self.root)?;
+        let text =
+            fs::read_to_string(path).with_context(|| format!("read note {}", path.display()))?;
+        let patched = patch.apply(&text)?;
+        if patched == text {
+            return Ok(false);
+        }
+        storage::write_atomic(path, patched)?;
+        Ok(true)
+    }
+
+    /// Rename or move a note, rewriting every wikilink that resolved to it.
+    /// `destination` is a vault-relative path or title; a trailing `/` or an
+    /// existing folder keeps the file name.
+    pub fn move_note(&self, from: &Path, destination: &str) -> Result<MovedNote> {
+        let trimmed = destination.trim();
+        let folder = self.root.join(trimmed.trim_matches('/'));
+        let to = if trimmed.ends_with('/') || (!trimmed.is_empty() && folder.is_dir()) {
+            let file_name = from
+                .file_name()
+                .ok_or_else(|| anyhow!("invalid note path {}", from.display()))?;
+            self.vault_path(&walk::relative_path(&self.root, &folder.join(file_name)))?
+        } else {
+            self.note_path(trimmed)?
+        };
+
+        let _lock = FileLock::vault(&self.root)?;
+        if to.exists() {
+            return Err(Error::conflict(format!("note already exists: {}", to.display())).into());
+        }
+        let old = walk::relative_path(&self.root, from);
+        let new = walk::relative_path(&self.root, &to);
+        let graph = LinkGraph::build(&self.root)?;
+
+        let mut edits: BTreeMap<String, Vec<(usize, Link, String)>> = BTreeMap::new();
+        for edge in graph.backlinks_all(&old) {
+            if edge.link.target.is_empty() {
+                continue;
+            }
+            let target = self.link_target(&edge.link.target, &old, &new)?;
+            edits.entry(edge.source.clone()).or_default().push((
+                edge.link.line,
+                edge.link.clone(),
+                target,
+            ));
+        }
+
+        let mut stats = MovedNote {
+            path: to.clone(),
+            links_updated: 0,
+            notes_updated: edits.len(),
+        };
+        for (source, links) in edits {
+            let path = self.root.join(&source);
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("read note {}", path.display()))?;
+            let mut lines: Vec<String> = text.split_inclusive('\n').map(String::from).collect();
+            for (line, link, target) in links.into_iter().rev() {
+                let Some(current) = lines.get_mut(line - 1) else {
+                    continue;
+                };
+                let original = &current[link.span.clone()];
+                let mut rewritten = Link {
+                    target,
+                    ..link.clone()
+                }
+                .to_string();
+                if original.contains("\\|") {
+                    rewritten = rewritten.replacen('|', "\\|", 1);
+                }
+                current.replace_range(link.span.clone(), &rewritten);
+                stats.links_updated += 1;
+            }
+            storage::write_atomic(&path, lines.concat())?;
+        }
+
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(from, &to)
+            .with_context(|| format!("move {} to {}", from.display(), to.display()))?;
+        Ok(stats)
+    }
+
+    /// Resolve a new note's name to a path inside the vault, appending `.md`.
+    fn note_path(&self, name: &str) -> Result<PathBuf> {
+        let name = name.trim().trim_start_matches('/');
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        if file_name.trim().is_empty() {
+            return Err(Error::usage("note name must not be empty").into());
+        }
+        if let Some(c) = file_name.chars().find(|c| FORBIDDEN_NAME_CHARS.contains(c)) {
+            return Err(Error::usage(format!("note names cannot contain `{c}`: {name}")).into());
+        }
+        let relative = if walk::is_markdown(Path::new(name)) {
+            name.to_string()
+        } else {
+            format!("{name}.md")
+        };
+        self.vault_path(&relative)
+    }
+
+    /// Join a vault-relative path, refusing anything that escapes the vault,
+    /// including through symlinks. The path need not exist.
+    pub fn vault_path(&self, relative: &str) -> Result<PathBuf> {
+        let relative = Path::new(relative);
+        let escapes = || {
+            Error::usage(format!(
+                "path must stay inside the vault: {}",
+                relative.display()
+            ))
+        };
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(escapes().into());
+        }
+        let path = self.root.join(relative);
+        let existing = path
+            .ancestors()
+            .find(|ancestor| ancestor.symlink_metadata().is_ok())
+            .unwrap_or(&self.root);
+        let root = fs::canonicalize(&self.root)
+            .with_context(|| format!("resolve vault {}", self.root.display()))?;
+        match fs::canonicalize(existing) {
+            Ok(resolved) if resolved.starts_with(&root) => Ok(path),
+            _ => Err(escapes().into()),
+        }
     }
 
-    fn ensure_daily_file(&self, path: &Path, date: NaiveDate) -> Result<()> {
+    /// The link target to write for a moved note: a bare name when the
+    /// original link used one and the new name is unambiguous, otherwise the
+    /// vault-relative path without `.md`.
+    fn link_target(&self, written: &str, old: &str, new: &str) -> Result<String> {
+        let new_stem = new.trim_end_matches(".md");
+        let name = new_stem.rsplit('/').next().unwrap_or(new_stem);
+        let keep_extension = written.to_lowercase().ends_with(".md");
+        let suffix = if keep_extension { ".md" } else { "" };
+        if !written.contains('/') {
+            let wanted = format!("{}.md", name.to_lowercase());
+            let clashes = walk::markdown_files(&self.root)?
+                .iter()
+                .map(|path| walk::relative_path(&self.root, path))
+                .filter(|path| path != old)
+                .filter(|path| {
+                    path.rsplit('/').next().map(str::to_lowercase).as_deref()
+                        == Some(wanted.as_str())
+                })
+                .count();
+            if clashes == 0 {
+                return Ok(format!("{name}{suffix}"));
+            }
+        }
+        Ok(format!("{new_stem}{suffix}"))
+    }
+
+    pub fn read_document(&self, path: &Path) -> Result<Document> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("read note {}", path.display()))?;
+        Ok(Document::parse(&text))
+    }
+
+    pub fn set_property(&self, path: &Path, key: &str, value: &PropertyValue) -> Result<()> {
+        self.update_document(path, |doc| {
+            doc.set(key, value);
+            true
+        })
+        .map(|_| ())
+    }
+
+    /// Remove a frontmatter property, returning whether it was present.
+    pub fn unset_property(&self, path: &Path, key: &str) -> Result<bool> {
+        self.update_document(path, |doc| doc.unset(key))
+    }
+
+    /// Apply `edit` to a note's parsed frontmatter under the vault lock,
+    /// writing the note back only when `edit` reports a change.
+    fn update_document(
+        &self,
+        path: &Path,
+        edit: impl FnOnce(&mut Document) -> bool,
+    ) -> Result<bool> {
+        let _lock = FileLock::vault(&self.root)?;
+        let mut document = self.read_document(path)?;
+        let changed = edit(&mut document);
+        if changed {
+            storage::write_atomic(path, document.render())?;
+        }
+        Ok(changed)
+    }
+
+    fn append_for_date(&self, date: NaiveDate, text: &str, section: Option<&str>) -> Result<()> {
+        let path = self.daily_path(date);
+        let _lock = FileLock::vault(&self.root)?;
+        self.ensure_periodic_file(Period::Day, &path, date, None)?;
+        let mut content = fs::read_to_string(&path)
+            .with_context(|| format!("read daily note {}", path.display()))?;
+        if let Some(section) = section.filter(|s| !s.trim().is_empty()) {
+            let updated = markdown::insert_in_section(&content, section, text);
+            return storage::write_atomic(&path, updated);
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(text);
+        content.push('\n');
+        storage::write_atomic(&path, content)
+    }
+
+    /// Create a periodic note from its template. Besides the usual template
+    /// variables, `{{start}}` and `{{end}}` are the period's dates and
+    /// `{{days}}` a list of links to its daily notes; `{{date}}` is the
+    /// period's first day. `template` picks a named alternative. Callers hold
+    /// the vault lock.
+    fn ensure_periodic_file(
+        &self,
+        period: Period,
+        path: &Path,
+        date: NaiveDate,
+        template: Option<&str>,
+    ) -> Result<()> {
         if path.exists() {
             return Ok(());
         }
 
-        let template = templates::load_daily_template(&self.root)?;
-        let filled = template.replace("{{date}}", &date.format("%Y-%m-%d").to_string());
+        let template = templates::load(
+            &self.config,
+            &self.root,
+            TemplateKind::Periodic(period),
+            template,
+        )?;
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let days: Vec<String> = period
+            .days(date)
+            .map(|day| {
+                let daily = self.daily_path(day);
+                let name = daily.file_stem().unwrap_or_default().to_string_lossy();
+                format!("- [[{name}]]")
+            })
+            .collect();
+        let context = self
+            .template_context(period.start(date), &title)
+            .with_variables([
+                ("start".to_string(), period.start(date).to_string()),
+                ("end".to_string(), period.end(date).to_string()),
+                ("days".to_string(), days.join("\n")),
+            ]);
+        let filled = templates::render(&template, &context)?;
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(path, filled)?;
-        Ok(())
+        let rollover = period == Period::Day
+            && self.config.rollover.enabled
+            && date == Local::now().date_naive();
+        if rollover {
+            return self.roll_over(path, date, filled);
+        }
+        storage::write_atomic(path, filled)
+    }
+
+    /// Write today's new daily note with the open tasks of the latest
+    /// earlier daily note added under their headings, then mark or remove
+    /// them in that note. Callers hold the vault lock.
+    fn roll_over(&self, path: &Path, date: NaiveDate, mut content: String) -> Result<()> {
+        let previous = self
+            .list_recent(usize::MAX)?
+            .into_iter()
+            .find(|note| note.date < date);
+        let rolled = match &previous {
+            Some(note) => {
+                let text = fs::read_to_string(&note.path)
+                    .with_context(|| format!("read daily note {}", note.path.display()))?;
+                tasks::rollover(&text, &self.config.rollover)
+            }
+            None => None,
+        };
+        let (Some(note), Some(rolled)) = (previous, rolled) else {
+            return storage::write_atomic(path, content);
+        };
+        for (heading, block) in &rolled.sections {
+            content = markdown::insert_in_section(&content, heading, &block.join("\n"));
+        }
+        // Write the new note first so a failure never loses the tasks.
+        storage::write_atomic(path, content)?;
+        storage::write_atomic(&note.path, rolled.previous)
     }
 
-    fn filename_for(date: NaiveDate) -> String {
-        format!("{}.md", date.format("%Y-%m-%d"))
+    fn template_context(&self, date: NaiveDate, title: &str) -> TemplateContext<'_> {
+        let context = TemplateContext::new(&self.config, &self.root, date, title)
+            .with_variables(self.variables.clone());
+        match &self.prompt {
+            Some(prompt) => context.with_prompt(prompt.as_ref()),
+            None => context,
+        }
     }
 }
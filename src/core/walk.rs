@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Recursively collect Markdown files under `root`, skipping hidden entries
+/// such as `.git`, `.obsidian` and the `.obsctl` index directory.
+pub fn markdown_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vault_files(root)?;
+    files.retain(|path| is_markdown(path));
+    Ok(files)
+}
+
+/// Recursively collect every file under `root` (notes and attachments),
+/// skipping hidden entries.
+pub fn vault_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            fs::read_dir(&dir).with_context(|| format!("read directory {}", dir.display()))?;
+        for entry in entries.filter_map(|res| res.ok()) {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension().map(|ext| ext == "md").unwrap_or(false)
+}
+
+/// Render `path` relative to `root` using forward slashes, falling back to the
+/// full path when it lives outside the vault.
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether `path` is relative and made only of plain components, so joining
+/// it to a folder cannot escape that folder.
+pub fn is_relative_inside(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Match a vault-relative path against a glob: `*` and `?` stay within one
+/// folder, `**` spans folders. A pattern without `/` matches the file name.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let target = if pattern.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    let pattern: Vec<char> = pattern.trim_start_matches('/').chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    glob_chars(&pattern, &target)
+}
+
+fn glob_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` may also stand for no folder at all.
+            rest.strip_prefix(&['/'])
+                .is_some_and(|after| glob_chars(after, text))
+                || (0..=text.len()).any(|skip| glob_chars(rest, &text[skip..]))
+        }
+        ['*', rest @ ..] => {
+            for skip in 0..=text.len() {
+                if glob_chars(rest, &text[skip..]) {
+                    return true;
+                }
+                if text.get(skip) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        ['?', rest @ ..] => text.first().is_some_and(|c| *c != '/') && glob_chars(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_chars(rest, &text[1..]),
+    }
+}
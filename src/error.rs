@@ -0,0 +1,103 @@
+use std::io;
+
+use serde::Serialize;
+
+/// Broad category of a failure, used to pick the process exit code and
+/// reported as `kind` in JSON error output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Anything not covered below.
+    Other,
+    /// Bad arguments or input values.
+    Usage,
+    /// A note, task, template or property does not exist.
+    NotFound,
+    /// The target already exists.
+    Conflict,
+    /// The configuration file or a configured setting is invalid.
+    Config,
+    /// Reading or writing the vault failed.
+    Io,
+    /// An external program (ripgrep, fzf, the editor) failed.
+    External,
+}
+
+impl ErrorKind {
+    /// Process exit code; 2 matches clap's code for usage errors.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::NotFound => 3,
+            ErrorKind::Conflict => 4,
+            ErrorKind::Config => 5,
+            ErrorKind::Io => 6,
+            ErrorKind::External => 7,
+        }
+    }
+
+    /// Classify an error by its tagged [`Error`], or by the underlying
+    /// I/O, config or date parsing error.
+    pub fn of(err: &anyhow::Error) -> Self {
+        if let Some(err) = err.downcast_ref::<Error>() {
+            return err.kind;
+        }
+        if let Some(err) = err.downcast_ref::<io::Error>() {
+            return match err.kind() {
+                io::ErrorKind::NotFound => ErrorKind::NotFound,
+                io::ErrorKind::AlreadyExists => ErrorKind::Conflict,
+                _ => ErrorKind::Io,
+            };
+        }
+        if err.is::<toml::de::Error>() {
+            return ErrorKind::Config;
+        }
+        if err.is::<chrono::ParseError>() {
+            return ErrorKind::Usage;
+        }
+        ErrorKind::Other
+    }
+}
+
+/// An error message tagged with its [`ErrorKind`]; converts into
+/// `anyhow::Error` and survives added context.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Usage, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Conflict, message)
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Config, message)
+    }
+
+    pub fn external(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::External, message)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
@@ -1,6 +1,7 @@
 pub mod cli;
 pub mod config;
 pub mod core;
+pub mod error;
 pub mod mcp;
 pub mod search;
 pub mod templates;
@@ -0,0 +1,202 @@
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use rmcp::transport::streamable_http_server::{
+    session::{local::LocalSessionManager, SessionManager},
+    StreamableHttpServerConfig, StreamableHttpService,
+};
+use tokio::net::TcpListener;
+
+use crate::config::AppContext;
+use crate::error::Error;
+
+use super::ObsctlMcpServer;
+
+/// Path the MCP endpoint is served under.
+pub const MCP_PATH: &str = "/mcp";
+
+/// Serve the MCP server over streamable HTTP on `listener` until `shutdown`
+/// completes. Each client session gets its own server; with `[mcp]
+/// auth_token` set, requests must carry it as a bearer token. Requests from
+/// other hosts or browser origins than loopback, the bound address and
+/// `[mcp] allowed_hosts`/`allowed_origins` are refused.
+pub async fn serve(
+    ctx: AppContext,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let local = listener.local_addr().context("read MCP listen address")?;
+    check_address(&ctx, local)?;
+    let config = ctx.config().mcp.clone();
+    let token = config.auth_token.filter(|token| !token.is_empty());
+    let mut hosts = config.allowed_hosts;
+    if !local.ip().is_unspecified() {
+        hosts.push(local.ip().to_string());
+    }
+    let guard = Arc::new(Guard {
+        hosts,
+        origins: config.allowed_origins,
+    });
+
+    let sessions = Arc::new(LocalSessionManager::default());
+    let service = StreamableHttpService::new(
+        move || Ok(ObsctlMcpServer::new(ctx.clone())),
+        sessions.clone(),
+        StreamableHttpServerConfig::default(),
+    );
+    let mut router = Router::new().nest_service(MCP_PATH, service);
+    if let Some(token) = token {
+        let expected = Arc::new(token);
+        router = router.layer(middleware::from_fn_with_state(expected, require_token));
+    }
+    // Added last, so it runs first.
+    router = router.layer(middleware::from_fn_with_state(guard, check_origin));
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move {
+            shutdown.await;
+            // Open SSE streams would keep their connections, and so the
+            // server, alive; ending the sessions closes them.
+            let ids: Vec<_> = sessions.sessions.read().await.keys().cloned().collect();
+            for id in ids {
+                let _ = sessions.close_session(&id).await;
+            }
+        })
+        .await
+        .context("serve MCP over HTTP")
+}
+
+/// Refuse to listen beyond loopback without `[mcp] auth_token`.
+pub fn check_address(ctx: &AppContext, addr: SocketAddr) -> Result<()> {
+    let token = ctx.config().mcp.auth_token.as_deref().unwrap_or_default();
+    if token.is_empty() && !addr.ip().is_loopback() {
+        anyhow::bail!(Error::config(format!(
+            "refusing to serve MCP on {addr} without [mcp] auth_token; set a token or bind to a loopback address"
+        )));
+    }
+    Ok(())
+}
+
+/// A warning for a wildcard address such as `0.0.0.0` without `[mcp]
+/// allowed_hosts`: only loopback names pass the `Host` check there, so
+/// clients reaching the machine by its IP or hostname are refused.
+pub fn host_warning(ctx: &AppContext, addr: SocketAddr) -> Option<String> {
+    (addr.ip().is_unspecified() && ctx.config().mcp.allowed_hosts.is_empty()).then(|| {
+        format!(
+            "listening on {addr}, but only loopback hosts are accepted; \
+             list the names clients use to reach this machine in [mcp] allowed_hosts"
+        )
+    })
+}
+
+/// Hosts and origins allowed besides loopback ones.
+struct Guard {
+    hosts: Vec<String>,
+    origins: Vec<String>,
+}
+
+impl Guard {
+    /// A `Host` header; allowed entries without a port match any port.
+    fn allows_host(&self, host: &str) -> bool {
+        let name = host_name(host);
+        is_loopback(name)
+            || self.hosts.iter().any(|allowed| {
+                allowed.eq_ignore_ascii_case(host) || allowed.eq_ignore_ascii_case(name)
+            })
+    }
+
+    /// An `Origin` header such as `http://localhost:5173`.
+    fn allows_origin(&self, origin: &str) -> bool {
+        let loopback = ["http://", "https://"].iter().any(|scheme| {
+            origin
+                .strip_prefix(scheme)
+                .is_some_and(|authority| is_loopback(host_name(authority)))
+        });
+        loopback
+            || self.origins.iter().any(|allowed| {
+                allowed
+                    .trim_end_matches('/')
+                    .eq_ignore_ascii_case(origin.trim_end_matches('/'))
+            })
+    }
+}
+
+async fn check_origin(State(guard): State<Arc<Guard>>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let host = headers
+        .get(header::HOST)
+        .map(|value| value.to_str().unwrap_or_default());
+    let origin = headers
+        .get(header::ORIGIN)
+        .map(|value| value.to_str().unwrap_or_default());
+    if host.is_some_and(|host| !guard.allows_host(host)) {
+        return (StatusCode::FORBIDDEN, "host not allowed").into_response();
+    }
+    if origin.is_some_and(|origin| !guard.allows_origin(origin)) {
+        return (StatusCode::FORBIDDEN, "origin not allowed").into_response();
+    }
+    next.run(request).await
+}
+
+/// The host of `host[:port]` or `[v6]:port`, without brackets.
+fn host_name(authority: &str) -> &str {
+    if let Some(rest) = authority.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => authority,
+    }
+}
+
+fn is_loopback(host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+async fn require_token(
+    State(expected): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| bearer_credential(value.as_bytes()))
+        .is_some_and(|credential| constant_time_eq(credential, expected.as_bytes()));
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "missing or invalid bearer token",
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// The credential of a `Bearer <token>` header. The scheme is
+/// case-insensitive (RFC 7235), so `bearer <token>` is accepted too.
+fn bearer_credential(value: &[u8]) -> Option<&[u8]> {
+    let space = value.iter().position(|byte| *byte == b' ')?;
+    let (scheme, credential) = value.split_at(space);
+    scheme
+        .eq_ignore_ascii_case(b"Bearer")
+        .then(|| credential.trim_ascii())
+}
+
+/// Compare without exiting early, so timing does not reveal the token.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
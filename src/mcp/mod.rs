@@ -1,3 +1,6 @@
+pub mod http;
+mod prompts;
+mod resources;
 pub mod server;
 
 pub use server::ObsctlMcpServer;
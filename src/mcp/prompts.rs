@@ -0,0 +1,306 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{Days, Local, NaiveDate};
+use rmcp::model::{
+    GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageRole,
+};
+
+use crate::config::AppContext;
+use crate::core::periodic::Period;
+use crate::core::tasks::{Priority, TaskEntry, TaskService};
+use crate::core::vault::VaultService;
+use crate::core::walk;
+use crate::error::Error;
+
+use super::resources;
+
+/// Daily notes embedded by a review at most, newest kept.
+const MAX_NOTES: usize = 31;
+const DATE_HELP: &str = "YYYY-MM-DD, today, yesterday or tomorrow (default: today)";
+const PROJECT_HELP: &str = "Only tasks of this project (a note under Projects/ or #project/<name>)";
+
+pub fn list() -> Vec<Prompt> {
+    vec![
+        Prompt::new(
+            "daily_review",
+            Some("Review a day: its daily note, completed tasks and what is still due"),
+            Some(vec![argument("date", DATE_HELP)]),
+        ),
+        Prompt::new(
+            "weekly_review",
+            Some("Review a week: its daily notes, completed tasks, overdue and upcoming tasks"),
+            Some(vec![
+                argument(
+                    "start",
+                    "First day, YYYY-MM-DD (default: this week's Monday)",
+                ),
+                argument(
+                    "end",
+                    "Last day, YYYY-MM-DD (default: the end of that week)",
+                ),
+                argument("project", PROJECT_HELP),
+            ]),
+        ),
+        Prompt::new(
+            "plan_tomorrow",
+            Some("Plan a day from today's note and the tasks overdue, due or scheduled then"),
+            Some(vec![
+                argument("date", "Day to plan, YYYY-MM-DD (default: tomorrow)"),
+                argument("project", PROJECT_HELP),
+            ]),
+        ),
+        Prompt::new(
+            "triage_inbox",
+            Some("Triage open tasks that have no date or priority yet, and the Inbox note"),
+            Some(vec![argument("project", PROJECT_HELP)]),
+        ),
+    ]
+}
+
+fn argument(name: &str, description: &str) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        required: Some(false),
+    }
+}
+
+pub fn get(
+    ctx: &AppContext,
+    name: &str,
+    arguments: Option<&JsonObject>,
+) -> Result<GetPromptResult> {
+    let args = Arguments(arguments);
+    let today = Local::now().date_naive();
+    let builder = PromptBuilder::new(ctx, args.text("project"))?;
+    match name {
+        "daily_review" => {
+            let date = args.date("date")?.unwrap_or(today);
+            builder.daily_review(date)
+        }
+        "weekly_review" => {
+            let start = args
+                .date("start")?
+                .unwrap_or_else(|| Period::Week.start(today));
+            let end = args.date("end")?.unwrap_or_else(|| Period::Week.end(start));
+            if end < start {
+                anyhow::bail!(Error::usage("end must not be before start"));
+            }
+            builder.weekly_review(start, end, today)
+        }
+        "plan_tomorrow" => {
+            let date = args.date("date")?.unwrap_or(today + Days::new(1));
+            builder.plan_day(date)
+        }
+        "triage_inbox" => builder.triage_inbox(),
+        other => anyhow::bail!(Error::not_found(format!("unknown prompt: {other}"))),
+    }
+}
+
+/// Prompt arguments, which MCP passes as strings.
+struct Arguments<'a>(Option<&'a JsonObject>);
+
+impl Arguments<'_> {
+    fn text(&self, name: &str) -> Option<String> {
+        let value = self.0?.get(name)?.as_str()?.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    fn date(&self, name: &str) -> Result<Option<NaiveDate>> {
+        match self.text(name) {
+            Some(text) => resources::parse_date(&text)
+                .map(Some)
+                .ok_or_else(|| Error::usage(format!("invalid {name}: {text}")).into()),
+            None => Ok(None),
+        }
+    }
+}
+
+struct PromptBuilder<'a> {
+    ctx: &'a AppContext,
+    vault: VaultService,
+    /// Open and done tasks, limited to the requested project.
+    tasks: Vec<TaskEntry>,
+    project: Option<String>,
+    messages: Vec<PromptMessage>,
+}
+
+impl<'a> PromptBuilder<'a> {
+    fn new(ctx: &'a AppContext, project: Option<String>) -> Result<Self> {
+        let mut tasks = TaskService::new(ctx)?.tasks()?;
+        if let Some(project) = &project {
+            tasks.retain(|task| {
+                task.project()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(project))
+            });
+        }
+        Ok(Self {
+            ctx,
+            vault: VaultService::new(ctx)?,
+            tasks,
+            project,
+            messages: Vec::new(),
+        })
+    }
+
+    fn daily_review(mut self, date: NaiveDate) -> Result<GetPromptResult> {
+        self.ask(format!(
+            "Review my day on {date}. Summarize what I did and finished, point out what is \
+             still open or overdue, and suggest what to carry over to tomorrow."
+        ));
+        self.daily_note(date)?;
+        self.task_list(format!("Tasks completed on {date}"), |task| {
+            task.done_date == Some(date)
+        });
+        self.task_list(format!("Open tasks due on or before {date}"), |task| {
+            !task.done && task.due.is_some_and(|due| due <= date)
+        });
+        Ok(self.finish(format!("Review of {date}")))
+    }
+
+    fn weekly_review(
+        mut self,
+        start: NaiveDate,
+        end: NaiveDate,
+        today: NaiveDate,
+    ) -> Result<GetPromptResult> {
+        self.ask(format!(
+            "Review my week from {start} to {end}{}. Summarize progress and themes from my \
+             notes, list what I completed, flag overdue work, and suggest priorities for the \
+             week ahead.",
+            self.project_suffix()
+        ));
+        let weekly = self.vault.periodic_path(Period::Week, start);
+        self.embed(&weekly)?;
+        let days: Vec<NaiveDate> = start.iter_days().take_while(|day| *day <= end).collect();
+        let skip = days.len().saturating_sub(MAX_NOTES);
+        for day in &days[skip..] {
+            let path = self.vault.daily_path(*day);
+            self.embed(&path)?;
+        }
+        self.task_list(format!("Tasks completed from {start} to {end}"), |task| {
+            task.done_date
+                .is_some_and(|done| start <= done && done <= end)
+        });
+        self.task_list("Overdue open tasks".to_string(), |task| {
+            !task.done && task.due.is_some_and(|due| due < today)
+        });
+        let horizon = end + Days::new(7);
+        self.task_list(
+            format!("Open tasks due after {end} until {horizon}"),
+            |task| !task.done && task.due.is_some_and(|due| end < due && due <= horizon),
+        );
+        Ok(self.finish(format!("Review of {start} to {end}")))
+    }
+
+    fn plan_day(mut self, date: NaiveDate) -> Result<GetPromptResult> {
+        self.ask(format!(
+            "Help me plan {date}{}. Propose a realistic, prioritized plan from the tasks below: \
+             what to do first, what to reschedule, and anything missing.",
+            self.project_suffix()
+        ));
+        self.daily_note(date - Days::new(1))?;
+        self.task_list(format!("Overdue open tasks (due before {date})"), |task| {
+            !task.done && task.due.is_some_and(|due| due < date)
+        });
+        self.task_list(
+            format!("Open tasks due, scheduled or starting on {date}"),
+            |task| {
+                !task.done
+                    && [task.due, task.scheduled, task.start].contains(&Some(date))
+                    && task.due.is_none_or(|due| due >= date)
+            },
+        );
+        self.task_list("Undated high-priority open tasks".to_string(), |task| {
+            !task.done
+                && task.due.is_none()
+                && task.scheduled.is_none()
+                && task.priority >= Some(Priority::High)
+        });
+        Ok(self.finish(format!("Plan for {date}")))
+    }
+
+    fn triage_inbox(mut self) -> Result<GetPromptResult> {
+        self.ask(format!(
+            "Triage my inbox{}. For each item suggest a due or scheduled date, a priority and \
+             a project, or say that it can be dropped. Reference tasks by their 🆔 id.",
+            self.project_suffix()
+        ));
+        if let Ok(inbox) = self.vault.resolve_note("Inbox") {
+            self.embed(&inbox)?;
+        }
+        self.task_list("Open tasks without dates or priority".to_string(), |task| {
+            !task.done
+                && task.due.is_none()
+                && task.scheduled.is_none()
+                && task.start.is_none()
+                && task.priority.is_none()
+        });
+        Ok(self.finish("Inbox triage".to_string()))
+    }
+
+    fn project_suffix(&self) -> String {
+        match &self.project {
+            Some(project) => format!(" for the project {project}"),
+            None => String::new(),
+        }
+    }
+
+    fn ask(&mut self, text: String) {
+        self.messages
+            .push(PromptMessage::new_text(PromptMessageRole::User, text));
+    }
+
+    fn daily_note(&mut self, date: NaiveDate) -> Result<()> {
+        let path = self.vault.daily_path(date);
+        if !self.embed(&path)? {
+            self.ask(format!("There is no daily note for {date}."));
+        }
+        Ok(())
+    }
+
+    /// Attach a note as an embedded resource; `false` if it does not exist.
+    fn embed(&mut self, path: &Path) -> Result<bool> {
+        if !path.is_file() {
+            return Ok(false);
+        }
+        let text = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let relative = walk::relative_path(self.ctx.vault_root(), path);
+        self.messages.push(PromptMessage::new_resource(
+            PromptMessageRole::User,
+            resources::note_uri(&relative),
+            Some("text/markdown".to_string()),
+            Some(text),
+            None,
+            None,
+            None,
+        ));
+        Ok(true)
+    }
+
+    /// Add the tasks matching `filter` as a Markdown list under `title`.
+    fn task_list(&mut self, title: String, filter: impl Fn(&TaskEntry) -> bool) {
+        let lines: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|task| filter(task))
+            .map(|task| format!("{} ({})", task.raw, task.source))
+            .collect();
+        let body = if lines.is_empty() {
+            "None.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        self.ask(format!("{title}:\n{body}"));
+    }
+
+    fn finish(self, description: String) -> GetPromptResult {
+        GetPromptResult {
+            description: Some(description),
+            messages: self.messages,
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Days, Local, NaiveDate, Utc};
+use rmcp::model::{
+    AnnotateAble, Annotations, ListResourcesResult, RawResource, RawResourceTemplate,
+    ReadResourceResult, ResourceContents, ResourceTemplate,
+};
+
+use crate::config::AppContext;
+use crate::core::vault::VaultService;
+use crate::core::walk;
+use crate::error::Error;
+
+const NOTE_PREFIX: &str = "obsctl://note/";
+const DAILY_PREFIX: &str = "obsctl://daily/";
+const MIME_TYPE: &str = "text/markdown";
+/// Notes per `resources/list` page.
+const PAGE_SIZE: usize = 200;
+/// How often subscribed notes are checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The `obsctl://note/<path>` URI of a vault-relative note path, with
+/// characters outside the URI path set percent-encoded.
+pub fn note_uri(relative: &str) -> String {
+    let mut uri = NOTE_PREFIX.to_string();
+    for byte in relative.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+/// One page of vault notes, ordered by path. The cursor is the offset of
+/// the next page.
+pub fn list(ctx: &AppContext, cursor: Option<&str>) -> Result<ListResourcesResult> {
+    let root = ctx.vault_root();
+    let offset = match cursor {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .map_err(|_| Error::usage(format!("invalid cursor: {cursor}")))?,
+        None => 0,
+    };
+    let mut files = walk::markdown_files(root)?;
+    files.sort();
+    let resources = files
+        .iter()
+        .skip(offset)
+        .take(PAGE_SIZE)
+        .map(|path| {
+            let relative = walk::relative_path(root, path);
+            let metadata = fs::metadata(path).ok();
+            let title = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string());
+            let raw = RawResource {
+                uri: note_uri(&relative),
+                name: relative,
+                title,
+                description: None,
+                mime_type: Some(MIME_TYPE.to_string()),
+                size: metadata.as_ref().and_then(|m| u32::try_from(m.len()).ok()),
+                icons: None,
+            };
+            let modified = metadata.and_then(|m| m.modified().ok());
+            raw.optional_annotate(modified.map(|time| Annotations {
+                last_modified: Some(DateTime::<Utc>::from(time)),
+                ..Default::default()
+            }))
+        })
+        .collect();
+    let next = offset + PAGE_SIZE;
+    Ok(ListResourcesResult {
+        resources,
+        next_cursor: (next < files.len()).then(|| next.to_string()),
+    })
+}
+
+pub fn templates() -> Vec<ResourceTemplate> {
+    vec![
+        RawResourceTemplate {
+            uri_template: format!("{NOTE_PREFIX}{{path}}"),
+            name: "note".to_string(),
+            title: Some("Vault note".to_string()),
+            description: Some("A note by its path relative to the vault root".to_string()),
+            mime_type: Some(MIME_TYPE.to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: format!("{DAILY_PREFIX}{{date}}"),
+            name: "daily".to_string(),
+            title: Some("Daily note".to_string()),
+            description: Some(
+                "The daily note for a YYYY-MM-DD date, or today, yesterday or tomorrow".to_string(),
+            ),
+            mime_type: Some(MIME_TYPE.to_string()),
+        }
+        .no_annotation(),
+    ]
+}
+
+/// The file a resource URI stands for. The file may not exist yet.
+pub fn resolve(ctx: &AppContext, uri: &str) -> Result<PathBuf> {
+    if let Some(encoded) = uri.strip_prefix(NOTE_PREFIX) {
+        let relative =
+            decode(encoded).ok_or_else(|| Error::usage(format!("invalid resource URI: {uri}")))?;
+        let path = Path::new(&relative);
+        if relative.is_empty() || !walk::is_relative_inside(path) || !walk::is_markdown(path) {
+            anyhow::bail!(Error::usage(format!("invalid note path in {uri}")));
+        }
+        return VaultService::new(ctx)?.vault_path(&relative);
+    }
+    if let Some(date) = uri.strip_prefix(DAILY_PREFIX) {
+        let date =
+            parse_date(date).ok_or_else(|| Error::usage(format!("invalid date in {uri}")))?;
+        return Ok(VaultService::new(ctx)?.daily_path(date));
+    }
+    anyhow::bail!(Error::not_found(format!("unknown resource: {uri}")))
+}
+
+pub fn read(ctx: &AppContext, uri: &str) -> Result<ReadResourceResult> {
+    let path = resolve(ctx, uri)?;
+    if !path.is_file() {
+        anyhow::bail!(Error::not_found(format!("no note for {uri}")));
+    }
+    let text = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(MIME_TYPE.to_string()),
+            text,
+            meta: None,
+        }],
+    })
+}
+
+/// Subscribed URIs with the modification time last seen for each (`None`
+/// while the file does not exist).
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    seen: HashMap<String, Option<SystemTime>>,
+    /// Whether the polling task has been started.
+    pub watching: bool,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, ctx: &AppContext, uri: &str) -> Result<()> {
+        let modified = modified(&resolve(ctx, uri)?);
+        self.seen.insert(uri.to_string(), modified);
+        Ok(())
+    }
+
+    pub fn unsubscribe(&mut self, uri: &str) {
+        self.seen.remove(uri);
+    }
+
+    /// URIs whose file was created, changed or deleted since the last call.
+    /// Daily URIs such as `today` are re-resolved, so they follow the date.
+    pub fn changed(&mut self, ctx: &AppContext) -> Vec<String> {
+        let mut changed = Vec::new();
+        for (uri, seen) in self.seen.iter_mut() {
+            let Ok(path) = resolve(ctx, uri) else {
+                continue;
+            };
+            let modified = modified(&path);
+            if modified != *seen {
+                *seen = modified;
+                changed.push(uri.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// A `YYYY-MM-DD` date, or `today`, `yesterday` or `tomorrow`.
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    let today = Local::now().date_naive();
+    match text.trim() {
+        "today" => Some(today),
+        "yesterday" => Some(today - Days::new(1)),
+        "tomorrow" => Some(today + Days::new(1)),
+        text => NaiveDate::parse_from_str(text, "%Y-%m-%d").ok(),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Undo percent-encoding; `None` for malformed escapes or invalid UTF-8.
+fn decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = text.get(idx + 1..idx + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            idx += 3;
+        } else {
+            out.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
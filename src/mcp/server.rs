@@ -1,13 +1,21 @@
-use std::{fs, sync::Arc};
+use std::{
+    fs,
+    sync::{Arc, Mutex, MutexGuard},
+};
 
-use anyhow::Result as AnyResult;
+use anyhow::{Context, Result as AnyResult};
+use chrono::{DateTime, NaiveDate, Utc};
 use rmcp::{
     handler::server::{tool::parse_json_object, ServerHandler},
     model::{
-        CallToolRequestParam, CallToolResult, Content, Implementation, JsonObject, ListToolsResult,
-        PaginatedRequestParam, ProtocolVersion, ServerCapabilities, ServerInfo, Tool,
-        ToolAnnotations,
+        CallToolRequestParam, CallToolResult, Content, GetPromptRequestParam, GetPromptResult,
+        Implementation, JsonObject, ListPromptsResult, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, PaginatedRequestParam, ProtocolVersion,
+        ReadResourceRequestParam, ReadResourceResult, ResourceUpdatedNotificationParam,
+        ServerCapabilities, ServerInfo, SubscribeRequestParam, Tool, ToolAnnotations,
+        UnsubscribeRequestParam,
     },
+    service::{Peer, RoleServer},
     ErrorData as McpError,
 };
 use serde::Deserialize;
@@ -16,34 +24,221 @@ use serde_json::json;
 use crate::{
     config::AppContext,
     core::{
-        tasks::{TaskEntry, TaskService},
+        frontmatter::PropertyFilter,
+        links::LinkGraph,
+        patch::NotePatch,
+        tags,
+        tasks::{
+            normalize_id, NewTask, Priority, Recurrence, TaskEdit, TaskEntry, TaskQuery,
+            TaskService,
+        },
         vault::VaultService,
+        walk,
     },
+    error::ErrorKind,
     search::SearchService,
 };
 
+use super::prompts;
+use super::resources::{self, Subscriptions};
+
 #[derive(Clone)]
 pub struct ObsctlMcpServer {
     ctx: Arc<AppContext>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
 }
 
 impl ObsctlMcpServer {
     pub fn new(ctx: AppContext) -> Self {
-        Self { ctx: Arc::new(ctx) }
+        Self {
+            ctx: Arc::new(ctx),
+            subscriptions: Arc::default(),
+        }
+    }
+
+    /// Run `work` on the blocking thread pool. Handlers read notes and wait
+    /// for the vault lock, which would otherwise stall the runtime threads
+    /// that every HTTP session shares.
+    async fn blocking<T, F>(&self, work: F) -> Result<T, McpError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Self) -> Result<T, McpError> + Send + 'static,
+    {
+        let server = self.clone();
+        tokio::task::spawn_blocking(move || work(&server))
+            .await
+            .map_err(|err| McpError::internal_error(format!("handler failed: {err}"), None))?
+    }
+
+    fn run_tool(&self, request: CallToolRequestParam) -> Result<CallToolResult, McpError> {
+        let CallToolRequestParam { name, arguments } = request;
+        match name.as_ref() {
+            "append_daily_note" => {
+                let params: AppendDailyNoteParams =
+                    parse_json_object(arguments.clone().unwrap_or_default())?;
+                let message = self
+                    .append_daily(&params.entry, params.section.as_deref())
+                    .map_err(|err| internal_error("append daily note", err))?;
+                Ok(CallToolResult::success(vec![Content::text(message)]))
+            }
+            "update_task_status" => {
+                let params: UpdateTaskStatusParams =
+                    parse_json_object(arguments.clone().unwrap_or_default())?;
+                let message = self.update_task_status(params)?;
+                Ok(CallToolResult::success(vec![Content::text(message)]))
+            }
+            "add_task" => {
+                let params: AddTaskParams = parse_json_object(arguments.unwrap_or_default())?;
+                let task = self.add_task(params)?;
+                Ok(CallToolResult::structured(json!(task)))
+            }
+            "list_tasks" => {
+                let params: ListTasksParams = parse_json_object(arguments.unwrap_or_default())?;
+                let listing = self.list_tasks(params)?;
+                Ok(CallToolResult::structured(listing))
+            }
+            "edit_task" => {
+                let params: EditTaskParams = parse_json_object(arguments.unwrap_or_default())?;
+                let task = self.edit_task(params)?;
+                Ok(CallToolResult::structured(json!(task)))
+            }
+            "delete_task" => {
+                let params: TaskIdParams = parse_json_object(arguments.unwrap_or_default())?;
+                let task = self.delete_task(params)?;
+                Ok(CallToolResult::structured(json!(task)))
+            }
+            "move_task" => {
+                let params: MoveTaskParams = parse_json_object(arguments.unwrap_or_default())?;
+                let message = self.move_task(params)?;
+                Ok(CallToolResult::success(vec![Content::text(message)]))
+            }
+            "read_note" => {
+                let params: NoteParams = parse_json_object(arguments.unwrap_or_default())?;
+                let note = self.read_note(params)?;
+                Ok(CallToolResult::structured(note))
+            }
+            "create_note" => {
+                let params: CreateNoteParams = parse_json_object(arguments.unwrap_or_default())?;
+                let note = self.create_note(params)?;
+                Ok(CallToolResult::structured(note))
+            }
+            "list_notes" => {
+                let params: ListNotesParams = parse_json_object(arguments.unwrap_or_default())?;
+                let listing = self.list_notes(params)?;
+                Ok(CallToolResult::structured(listing))
+            }
+            "patch_note" => {
+                let params: PatchNoteParams = parse_json_object(arguments.unwrap_or_default())?;
+                let outcome = self.patch_note(params)?;
+                Ok(CallToolResult::structured(outcome))
+            }
+            "query_knowledge" => {
+                let params: QueryKnowledgeParams =
+                    parse_json_object(arguments.clone().unwrap_or_default())?;
+                let body = self.query_knowledge(params)?;
+                Ok(CallToolResult::success(vec![Content::text(body)]))
+            }
+            "query_tasks" => {
+                let params: QueryTasksParams = parse_json_object(arguments.unwrap_or_default())?;
+                let body = self.query_tasks(params)?;
+                Ok(CallToolResult::success(vec![Content::text(body)]))
+            }
+            "summarize_today" => {
+                let params: SummarizeTodayParams =
+                    parse_json_object(arguments.unwrap_or_default())?;
+                if let Some(scope) = params.scope {
+                    if scope.to_lowercase() != "today" {
+                        return Err(McpError::invalid_params(
+                            "scope must be \"today\"",
+                            Some(json!({ "scope": scope })),
+                        ));
+                    }
+                }
+                let summary = self.summarize_today()?;
+                Ok(CallToolResult::success(vec![Content::text(summary)]))
+            }
+            "note_links" | "note_backlinks" => {
+                let params: NoteParams = parse_json_object(arguments.unwrap_or_default())?;
+                let body = self.note_links(params, name == "note_backlinks")?;
+                Ok(CallToolResult::success(vec![Content::text(body)]))
+            }
+            "orphan_notes" => {
+                let body = self.orphan_notes()?;
+                Ok(CallToolResult::success(vec![Content::text(body)]))
+            }
+            "lookup_tags" => {
+                let params: LookupTagsParams = parse_json_object(arguments.unwrap_or_default())?;
+                let body = self.lookup_tags(params)?;
+                Ok(CallToolResult::success(vec![Content::text(body)]))
+            }
+            "broken_links" => {
+                let body = self.broken_links()?;
+                Ok(CallToolResult::success(vec![Content::text(body)]))
+            }
+            other => Err(McpError::invalid_params(
+                format!("unknown tool: {other}"),
+                Some(json!({ "tool": other })),
+            )),
+        }
+    }
+
+    fn subscriptions(&self) -> MutexGuard<'_, Subscriptions> {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Poll subscribed notes and notify the client of changes until the
+    /// connection closes.
+    fn watch(&self, peer: Peer<RoleServer>) {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(resources::POLL_INTERVAL);
+            while !peer.is_transport_closed() {
+                interval.tick().await;
+                // Polling stats the subscribed files under the std mutex.
+                let poll =
+                    server.blocking(|server| Ok(server.subscriptions().changed(&server.ctx)));
+                let Ok(changed) = poll.await else {
+                    return;
+                };
+                for uri in changed {
+                    let param = ResourceUpdatedNotificationParam { uri };
+                    if peer.notify_resource_updated(param).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
     }
 
     fn list_available_tools(&self) -> Vec<Tool> {
         vec![
             append_daily_tool(),
+            add_task_tool(),
+            list_tasks_tool(),
             update_task_tool(),
+            edit_task_tool(),
+            delete_task_tool(),
+            move_task_tool(),
+            read_note_tool(),
+            create_note_tool(),
+            list_notes_tool(),
+            patch_note_tool(),
             query_knowledge_tool(),
+            query_tasks_tool(),
             summarize_today_tool(),
+            note_links_tool(),
+            note_backlinks_tool(),
+            orphan_notes_tool(),
+            broken_links_tool(),
+            lookup_tags_tool(),
         ]
     }
 
-    fn append_daily(&self, entry: &str) -> AnyResult<String> {
+    fn append_daily(&self, entry: &str, section: Option<&str>) -> AnyResult<String> {
         let vault = VaultService::new(&self.ctx)?;
-        vault.append_today(entry)?;
+        vault.append_today(entry, section)?;
         Ok(format!(
             "Appended entry to {}",
             vault.today_path().display()
@@ -62,7 +257,8 @@ impl ObsctlMcpServer {
         let service =
             TaskService::new(&self.ctx).map_err(|err| internal_error("load tasks", err))?;
 
-        let target: Option<TaskEntry> = if let Some(id) = params.id {
+        let target: Option<TaskEntry> = if let Some(id) = params.id.as_deref() {
+            let id = normalize_id(id);
             service
                 .tasks()
                 .map_err(|err| internal_error("read tasks", err))?
@@ -83,18 +279,330 @@ impl ObsctlMcpServer {
             )
         })?;
 
-        service
-            .set_status(task.id, status_flag)
+        let update = service
+            .set_status(&task.id, status_flag)
             .map_err(|err| internal_error("update task status", err))?;
 
         let status_label = if status_flag { "done" } else { "open" };
-        Ok(format!("Task #{} marked as {}", task.id, status_label))
+        let mut message = format!("Task #{} marked as {}", task.id, status_label);
+        for parent in &update.completed_parents {
+            message.push_str(&format!("; parent task #{parent} completed"));
+        }
+        if let Some(next) = update.next {
+            message.push_str(&format!("; next occurrence is task #{}", next.id));
+            if let Some(due) = next.due {
+                message.push_str(&format!(" due {due}"));
+            }
+        }
+        Ok(message)
+    }
+
+    fn add_task(&self, params: AddTaskParams) -> Result<TaskEntry, McpError> {
+        let title = params.title.trim();
+        if title.is_empty() {
+            return Err(McpError::invalid_params("title must not be empty", None));
+        }
+        let recurrence = match params.recurrence.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(rule) => Some(
+                Recurrence::parse(rule)
+                    .map_err(|err| {
+                        McpError::invalid_params(
+                            format!("invalid recurrence: {err}"),
+                            Some(json!({ "recurrence": rule })),
+                        )
+                    })?
+                    .to_string(),
+            ),
+        };
+        let new_task = NewTask {
+            title: title.to_string(),
+            due_date: date_param("due", params.due.as_deref())?,
+            recurrence,
+            priority: priority_param(params.priority.as_deref())?,
+            parent: params.parent.filter(|parent| !parent.trim().is_empty()),
+        };
+        let service =
+            TaskService::new(&self.ctx).map_err(|err| internal_error("load tasks", err))?;
+        let id = service
+            .add_task(new_task)
+            .map_err(|err| request_error("add task", err))?;
+        service
+            .tasks()
+            .map_err(|err| internal_error("read tasks", err))?
+            .into_iter()
+            .find(|task| task.id == id)
+            .ok_or_else(|| McpError::internal_error(format!("task {id} vanished"), None))
+    }
+
+    fn list_tasks(&self, params: ListTasksParams) -> Result<serde_json::Value, McpError> {
+        let mut query = match params.query.as_deref().map(str::trim) {
+            None | Some("") => TaskQuery::default(),
+            Some(text) => TaskQuery::parse(text).map_err(|err| {
+                McpError::invalid_params(
+                    format!("invalid task query: {err}"),
+                    Some(json!({ "query": text })),
+                )
+            })?,
+        };
+        match params.status.as_deref().map(str::trim).unwrap_or("open") {
+            "open" => query = query.with_status(false),
+            "done" => query = query.with_status(true),
+            "all" => {}
+            other => {
+                return Err(McpError::invalid_params(
+                    format!("unknown status: {other}"),
+                    Some(json!({ "status": other })),
+                ))
+            }
+        }
+        if let Some(tag) = params.tag.as_deref().filter(|tag| !tag.trim().is_empty()) {
+            query = query.with_tag(tag);
+        }
+        if let Some(path) = params.path.as_deref().filter(|path| !path.is_empty()) {
+            query = query.with_path(path);
+        }
+        query = query.with_due_range(
+            date_param("due_from", params.due_from.as_deref())?,
+            date_param("due_to", params.due_to.as_deref())?,
+        );
+        if let Some(priority) = priority_param(params.priority.as_deref())? {
+            query = query.with_min_priority(priority);
+        }
+        let parent = params.parent.as_deref().map(normalize_id);
+        let service =
+            TaskService::new(&self.ctx).map_err(|err| internal_error("load tasks", err))?;
+        let groups = service
+            .query(&query)
+            .map_err(|err| internal_error("query tasks", err))?;
+
+        let mut tasks = Vec::new();
+        for group in groups {
+            for task in group.tasks {
+                if let Some(project) = &params.project {
+                    if !task
+                        .project()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(project.trim()))
+                    {
+                        continue;
+                    }
+                }
+                if parent.is_some() && task.parent != parent {
+                    continue;
+                }
+                let mut value = json!(task);
+                if let Some(name) = &group.name {
+                    value["group"] = json!(name);
+                }
+                tasks.push(value);
+            }
+        }
+        let total = tasks.len();
+        let limit = params.limit.unwrap_or(50).clamp(1, 500);
+        tasks.truncate(limit);
+        Ok(json!({
+            "tasks": tasks,
+            "total": total,
+            "truncated": total > limit,
+        }))
+    }
+
+    fn edit_task(&self, params: EditTaskParams) -> Result<TaskEntry, McpError> {
+        let edit = TaskEdit {
+            title: params.title.clone(),
+            due: clearable(params.due.as_deref(), |text| date_param("due", Some(text)))?,
+            scheduled: clearable(params.scheduled.as_deref(), |text| {
+                date_param("scheduled", Some(text))
+            })?,
+            start: clearable(params.start.as_deref(), |text| {
+                date_param("start", Some(text))
+            })?,
+            priority: clearable(params.priority.as_deref(), |text| {
+                priority_param(Some(text))
+            })?,
+            recurrence: params
+                .recurrence
+                .as_ref()
+                .map(|rule| Some(rule.trim().to_string()).filter(|rule| !rule.is_empty())),
+            add_tags: params.add_tags.clone(),
+            remove_tags: params.remove_tags.clone(),
+        };
+        let service =
+            TaskService::new(&self.ctx).map_err(|err| internal_error("load tasks", err))?;
+        service
+            .edit_task(&params.id, &edit)
+            .map_err(|err| request_error("edit task", err))
+    }
+
+    fn delete_task(&self, params: TaskIdParams) -> Result<TaskEntry, McpError> {
+        let service =
+            TaskService::new(&self.ctx).map_err(|err| internal_error("load tasks", err))?;
+        service
+            .delete_task(&params.id)
+            .map_err(|err| request_error("delete task", err))
+    }
+
+    fn move_task(&self, params: MoveTaskParams) -> Result<String, McpError> {
+        let service =
+            TaskService::new(&self.ctx).map_err(|err| internal_error("load tasks", err))?;
+        let task = service
+            .move_task(&params.id, &params.note, params.section.as_deref())
+            .map_err(|err| request_error("move task", err))?;
+        Ok(format!(
+            "Task #{} moved to {}:{}",
+            task.id, task.source, task.line_number
+        ))
+    }
+
+    fn vault(&self) -> Result<VaultService, McpError> {
+        VaultService::new(&self.ctx).map_err(|err| internal_error("open vault", err))
+    }
+
+    fn read_note(&self, params: NoteParams) -> Result<serde_json::Value, McpError> {
+        let path = self
+            .vault()?
+            .resolve_note(&params.note)
+            .map_err(|err| request_error("read note", err))?;
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("read note {}", path.display()))
+            .map_err(|err| internal_error("read note", err))?;
+        let relative = walk::relative_path(self.ctx.vault_root(), &path);
+        Ok(json!({
+            "path": relative,
+            "uri": resources::note_uri(&relative),
+            "content": content,
+        }))
+    }
+
+    fn create_note(&self, params: CreateNoteParams) -> Result<serde_json::Value, McpError> {
+        let vault = self.vault()?;
+        let path = match (&params.content, &params.template) {
+            (Some(_), Some(_)) => {
+                return Err(McpError::invalid_params(
+                    "provide either content or template, not both",
+                    None,
+                ))
+            }
+            (Some(content), None) => vault.create_note_with_content(&params.path, content),
+            (None, template) => vault.create_note(&params.path, template.as_deref()),
+        }
+        .map_err(|err| request_error("create note", err))?;
+        let relative = walk::relative_path(self.ctx.vault_root(), &path);
+        Ok(json!({
+            "path": relative,
+            "uri": resources::note_uri(&relative),
+        }))
+    }
+
+    fn list_notes(&self, params: ListNotesParams) -> Result<serde_json::Value, McpError> {
+        let root = self.ctx.vault_root();
+        let folder = match params.folder.as_deref().map(|f| f.trim().trim_matches('/')) {
+            None | Some("") => root.to_path_buf(),
+            Some(folder) => self
+                .vault()?
+                .vault_path(folder)
+                .map_err(|err| request_error("list notes", err))?,
+        };
+        if !folder.is_dir() {
+            return Err(McpError::invalid_params(
+                format!("folder not found: {}", walk::relative_path(root, &folder)),
+                None,
+            ));
+        }
+        let files =
+            walk::markdown_files(&folder).map_err(|err| internal_error("list notes", err))?;
+        let mut notes = Vec::new();
+        for path in files {
+            let relative = walk::relative_path(root, &path);
+            if let Some(glob) = params.glob.as_deref().filter(|glob| !glob.is_empty()) {
+                if !walk::glob_match(glob, &relative) {
+                    continue;
+                }
+            }
+            if let Some(tag) = params.tag.as_deref().filter(|tag| !tag.trim().is_empty()) {
+                let text = fs::read_to_string(&path).unwrap_or_default();
+                if !tags::note_tags(&text)
+                    .iter()
+                    .any(|found| tags::matches(&found.tag, tag))
+                {
+                    continue;
+                }
+            }
+            let metadata = fs::metadata(&path).ok();
+            notes.push(json!({
+                "path": relative,
+                "title": path.file_stem().map(|stem| stem.to_string_lossy()),
+                "size": metadata.as_ref().map(|m| m.len()),
+                "modified": metadata
+                    .and_then(|m| m.modified().ok())
+                    .map(DateTime::<Utc>::from),
+            }));
+        }
+        let total = notes.len();
+        let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+        notes.truncate(limit);
+        Ok(json!({
+            "notes": notes,
+            "total": total,
+            "truncated": total > limit,
+        }))
+    }
+
+    fn patch_note(&self, params: PatchNoteParams) -> Result<serde_json::Value, McpError> {
+        let required = |field: &str, value: Option<String>| {
+            value.ok_or_else(|| {
+                McpError::invalid_params(
+                    format!("{field} is required for {}", params.operation),
+                    None,
+                )
+            })
+        };
+        let patch = match params.operation.as_str() {
+            "replace_section" => NotePatch::ReplaceSection {
+                heading: required("heading", params.heading.clone())?,
+                content: required("content", params.content.clone())?,
+            },
+            "insert_after" => NotePatch::InsertAfter {
+                anchor: required("anchor", params.anchor.clone())?,
+                content: required("content", params.content.clone())?,
+            },
+            "unified_diff" => NotePatch::UnifiedDiff(required("diff", params.diff.clone())?),
+            other => {
+                return Err(McpError::invalid_params(
+                    format!("unknown operation: {other}"),
+                    Some(json!({ "operation": other })),
+                ))
+            }
+        };
+        let vault = self.vault()?;
+        let path = vault
+            .resolve_note(&params.note)
+            .map_err(|err| request_error("patch note", err))?;
+        let changed = vault
+            .patch_note(&path, &patch)
+            .map_err(|err| request_error("patch note", err))?;
+        Ok(json!({
+            "path": walk::relative_path(self.ctx.vault_root(), &path),
+            "changed": changed,
+        }))
     }
 
     fn query_knowledge(&self, params: QueryKnowledgeParams) -> Result<String, McpError> {
         let limit = params.limit.unwrap_or(5).max(1);
-        let service =
-            SearchService::new(&self.ctx).map_err(|err| internal_error("init search", err))?;
+        let filters = params
+            .properties
+            .iter()
+            .map(|text| PropertyFilter::parse(text))
+            .collect::<AnyResult<Vec<_>>>()
+            .map_err(|err| {
+                McpError::invalid_params(
+                    format!("invalid property filter: {err}"),
+                    Some(json!({ "properties": params.properties })),
+                )
+            })?;
+        let service = SearchService::new(&self.ctx)
+            .map_err(|err| internal_error("init search", err))?
+            .with_filters(filters);
         let matches = service
             .grep_matches(&params.query, limit)
             .map_err(|err| internal_error("run search", err))?;
@@ -105,6 +613,143 @@ impl ObsctlMcpServer {
         }
     }
 
+    fn query_tasks(&self, params: QueryTasksParams) -> Result<String, McpError> {
+        let text = params.query.as_deref().unwrap_or("not done");
+        let query = TaskQuery::parse(text).map_err(|err| {
+            McpError::invalid_params(
+                format!("invalid task query: {err}"),
+                Some(json!({ "query": text })),
+            )
+        })?;
+        let service =
+            TaskService::new(&self.ctx).map_err(|err| internal_error("load tasks", err))?;
+        let groups = service
+            .query(&query)
+            .map_err(|err| internal_error("query tasks", err))?;
+
+        let mut lines = Vec::new();
+        for group in groups {
+            if group.tasks.is_empty() {
+                continue;
+            }
+            if let Some(name) = group.name {
+                lines.push(format!("## {name}"));
+            }
+            lines.extend(group.tasks.into_iter().map(|task| task.raw));
+        }
+        if lines.is_empty() {
+            Ok(format!("No tasks match \"{text}\""))
+        } else {
+            Ok(lines.join("\n"))
+        }
+    }
+
+    fn link_graph(&self) -> Result<LinkGraph, McpError> {
+        LinkGraph::build(self.ctx.vault_root())
+            .map_err(|err| internal_error("build link graph", err))
+    }
+
+    fn note_links(&self, params: NoteParams, backlinks: bool) -> Result<String, McpError> {
+        let graph = self.link_graph()?;
+        let note = graph.find_note(&params.note).ok_or_else(|| {
+            McpError::invalid_params("note not found", Some(json!({ "note": params.note })))
+        })?;
+        let lines: Vec<String> = if backlinks {
+            graph
+                .backlinks(note)
+                .into_iter()
+                .map(|edge| format!("{}:{}: {}", edge.source, edge.link.line, edge.context))
+                .collect()
+        } else {
+            graph
+                .links(note)
+                .into_iter()
+                .map(|edge| edge.to_string())
+                .collect()
+        };
+        if lines.is_empty() {
+            let kind = if backlinks {
+                "backlinks to"
+            } else {
+                "links in"
+            };
+            Ok(format!("No {kind} {note}"))
+        } else {
+            Ok(lines.join("\n"))
+        }
+    }
+
+    fn orphan_notes(&self) -> Result<String, McpError> {
+        let graph = self.link_graph()?;
+        let orphans = graph.orphans();
+        if orphans.is_empty() {
+            Ok("Every note is linked from another note".to_string())
+        } else {
+            Ok(orphans.join("\n"))
+        }
+    }
+
+    fn broken_links(&self) -> Result<String, McpError> {
+        let graph = self.link_graph()?;
+        let broken: Vec<String> = graph
+            .broken_links()
+            .into_iter()
+            .map(|edge| edge.to_string())
+            .collect();
+        if broken.is_empty() {
+            Ok("No broken links".to_string())
+        } else {
+            Ok(broken.join("\n"))
+        }
+    }
+
+    fn lookup_tags(&self, params: LookupTagsParams) -> Result<String, McpError> {
+        let search =
+            SearchService::new(&self.ctx).map_err(|err| internal_error("init search", err))?;
+        let Some(tag) = params.tag.filter(|tag| !tags::normalize(tag).is_empty()) else {
+            let counts = search
+                .tag_counts()
+                .map_err(|err| internal_error("list tags", err))?;
+            if counts.is_empty() {
+                return Ok("No tags in the vault".to_string());
+            }
+            return Ok(counts
+                .iter()
+                .map(|entry| format!("#{} ({})", entry.tag, entry.count))
+                .collect::<Vec<_>>()
+                .join("\n"));
+        };
+
+        let notes = search
+            .tagged(&tag)
+            .map_err(|err| internal_error("look up tag", err))?;
+        let tasks: Vec<TaskEntry> = TaskService::new(&self.ctx)
+            .and_then(|service| service.tasks())
+            .map_err(|err| internal_error("read tasks", err))?
+            .into_iter()
+            .filter(|task| task.tags.iter().any(|t| tags::matches(t, &tag)))
+            .collect();
+        if notes.is_empty() && tasks.is_empty() {
+            return Ok(format!(
+                "No notes or tasks tagged #{}",
+                tags::normalize(&tag)
+            ));
+        }
+        let mut lines = vec!["## Notes".to_string()];
+        lines.extend(
+            notes
+                .iter()
+                .map(|hit| format!("{}:{}: {}", hit.path, hit.line, hit.context)),
+        );
+        lines.push("## Tasks".to_string());
+        lines.extend(
+            tasks
+                .iter()
+                .map(|task| format!("{}:{}: {}", task.source, task.line_number, task.raw)),
+        );
+        Ok(lines.join("\n"))
+    }
+
     fn summarize_today(&self) -> Result<String, McpError> {
         let vault =
             VaultService::new(&self.ctx).map_err(|err| internal_error("load vault", err))?;
@@ -143,86 +788,267 @@ impl ServerHandler for ObsctlMcpServer {
         request: CallToolRequestParam,
         _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let CallToolRequestParam { name, arguments } = request;
-        match name.as_ref() {
-            "append_daily_note" => {
-                let params: AppendDailyNoteParams =
-                    parse_json_object(arguments.clone().unwrap_or_default())?;
-                let message = self
-                    .append_daily(&params.entry)
-                    .map_err(|err| internal_error("append daily note", err))?;
-                Ok(CallToolResult::success(vec![Content::text(message)]))
-            }
-            "update_task_status" => {
-                let params: UpdateTaskStatusParams =
-                    parse_json_object(arguments.clone().unwrap_or_default())?;
-                let message = self.update_task_status(params)?;
-                Ok(CallToolResult::success(vec![Content::text(message)]))
-            }
-            "query_knowledge" => {
-                let params: QueryKnowledgeParams =
-                    parse_json_object(arguments.clone().unwrap_or_default())?;
-                let body = self.query_knowledge(params)?;
-                Ok(CallToolResult::success(vec![Content::text(body)]))
-            }
-            "summarize_today" => {
-                let params: SummarizeTodayParams =
-                    parse_json_object(arguments.unwrap_or_default())?;
-                if let Some(scope) = params.scope {
-                    if scope.to_lowercase() != "today" {
-                        return Err(McpError::invalid_params(
-                            "scope must be \"today\"",
-                            Some(json!({ "scope": scope })),
-                        ));
-                    }
-                }
-                let summary = self.summarize_today()?;
-                Ok(CallToolResult::success(vec![Content::text(summary)]))
-            }
-            other => Err(McpError::invalid_params(
-                format!("unknown tool: {other}"),
-                Some(json!({ "tool": other })),
-            )),
-        }
+        self.blocking(move |server| server.run_tool(request)).await
     }
 
-    fn get_info(&self) -> ServerInfo {
-        let capabilities = ServerCapabilities::builder()
-            .enable_tools()
-            .enable_tool_list_changed()
-            .build();
-        let mut info = Implementation::from_build_env();
-        info.title = Some("obsctl MCP server".to_string());
-        ServerInfo {
-            protocol_version: ProtocolVersion::default(),
-            capabilities,
-            server_info: info,
-            instructions: Some(
-                "Tools expose daily note append, task updates, search, and summaries.".to_string(),
-            ),
-        }
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult {
+            prompts: prompts::list(),
+            next_cursor: None,
+        })
     }
-}
 
-#[derive(Debug, Deserialize)]
-struct AppendDailyNoteParams {
-    entry: String,
-}
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        self.blocking(move |server| {
+            prompts::get(&server.ctx, &request.name, request.arguments.as_ref())
+                .map_err(|err| request_error("get prompt", err))
+        })
+        .await
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let cursor = request.and_then(|request| request.cursor);
+        self.blocking(move |server| {
+            resources::list(&server.ctx, cursor.as_deref())
+                .map_err(|err| resource_error("list resources", err))
+        })
+        .await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult {
+            resource_templates: resources::templates(),
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.blocking(move |server| {
+            resources::read(&server.ctx, &request.uri)
+                .map_err(|err| resource_error("read resource", err))
+        })
+        .await
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<(), McpError> {
+        let start = {
+            let mut subscriptions = self.subscriptions();
+            subscriptions
+                .subscribe(&self.ctx, &request.uri)
+                .map_err(|err| resource_error("subscribe", err))?;
+            !std::mem::replace(&mut subscriptions.watching, true)
+        };
+        if start {
+            self.watch(context.peer);
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<(), McpError> {
+        self.subscriptions().unsubscribe(&request.uri);
+        Ok(())
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        let capabilities = ServerCapabilities::builder()
+            .enable_prompts()
+            .enable_resources()
+            .enable_resources_subscribe()
+            .enable_tools()
+            .enable_tool_list_changed()
+            .build();
+        let mut info = Implementation::from_build_env();
+        info.title = Some("obsctl MCP server".to_string());
+        ServerInfo {
+            protocol_version: ProtocolVersion::default(),
+            capabilities,
+            server_info: info,
+            instructions: Some(
+                "Tools expose daily note append, task queries and updates, search, tags, summaries, and the wikilink graph (links, backlinks, orphans, broken links). Notes are resources at obsctl://note/<path> and obsctl://daily/<date>."
+                    .to_string(),
+            ),
+        }
+    }
+}
 
 #[derive(Debug, Deserialize)]
-struct UpdateTaskStatusParams {
+struct AppendDailyNoteParams {
+    entry: String,
     #[serde(default)]
-    id: Option<usize>,
+    section: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateTaskStatusParams {
+    #[serde(default, deserialize_with = "deserialize_task_id")]
+    id: Option<String>,
     #[serde(default)]
     title: Option<String>,
     status: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct AddTaskParams {
+    title: String,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    recurrence: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_task_id")]
+    parent: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListTasksParams {
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    tag: Option<String>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    due_from: Option<String>,
+    #[serde(default)]
+    due_to: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_task_id")]
+    parent: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Omitted fields stay unchanged; an empty string clears the field.
+#[derive(Debug, Deserialize)]
+struct EditTaskParams {
+    #[serde(deserialize_with = "deserialize_required_task_id")]
+    id: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    scheduled: Option<String>,
+    #[serde(default)]
+    start: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    recurrence: Option<String>,
+    #[serde(default)]
+    add_tags: Vec<String>,
+    #[serde(default)]
+    remove_tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskIdParams {
+    #[serde(deserialize_with = "deserialize_required_task_id")]
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveTaskParams {
+    #[serde(deserialize_with = "deserialize_required_task_id")]
+    id: String,
+    note: String,
+    #[serde(default)]
+    section: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateNoteParams {
+    path: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    template: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListNotesParams {
+    #[serde(default)]
+    folder: Option<String>,
+    #[serde(default)]
+    glob: Option<String>,
+    #[serde(default)]
+    tag: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatchNoteParams {
+    note: String,
+    operation: String,
+    #[serde(default)]
+    heading: Option<String>,
+    #[serde(default)]
+    anchor: Option<String>,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    diff: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct QueryKnowledgeParams {
     query: String,
     #[serde(default)]
     limit: Option<usize>,
+    #[serde(default)]
+    properties: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct QueryTasksParams {
+    #[serde(default)]
+    query: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoteParams {
+    note: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct LookupTagsParams {
+    #[serde(default)]
+    tag: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -238,6 +1064,10 @@ fn append_daily_tool() -> Tool {
             "entry": {
                 "type": "string",
                 "description": "Freeform text that will be appended to today's daily note."
+            },
+            "section": {
+                "type": "string",
+                "description": "Heading to insert the entry under, e.g. \"Highlights\". Created if missing; defaults to the end of the note."
             }
         },
         "required": ["entry"],
@@ -254,14 +1084,114 @@ fn append_daily_tool() -> Tool {
     )
 }
 
-fn update_task_tool() -> Tool {
+fn add_task_tool() -> Tool {
     let schema = json!({
         "type": "object",
         "properties": {
-            "id": {
+            "title": {
+                "type": "string",
+                "description": "Task description; may contain #tags"
+            },
+            "due": {
+                "type": "string",
+                "description": "Due date as YYYY-MM-DD, today or tomorrow"
+            },
+            "priority": {
+                "type": "string",
+                "enum": ["lowest", "low", "medium", "high", "highest"]
+            },
+            "recurrence": {
+                "type": "string",
+                "description": "Recurrence rule such as \"every week\" or \"every month on the 15th\""
+            },
+            "parent": {
+                "type": "string",
+                "description": "ID of a task to nest this one under as a subtask, in the parent's note"
+            }
+        },
+        "required": ["title"]
+    });
+    Tool::new(
+        "add_task",
+        "Add a task to Tasks/tasks.md, or as a subtask of another task; returns the new task as JSON",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Add Task")
+            .destructive(false)
+            .idempotent(false),
+    )
+}
+
+fn list_tasks_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "status": {
+                "type": "string",
+                "enum": ["open", "done", "all"],
+                "default": "open"
+            },
+            "tag": {
+                "type": "string",
+                "description": "Only tasks with this tag or a tag nested below it, e.g. \"#work\""
+            },
+            "project": {
+                "type": "string",
+                "description": "Only tasks of this project: a note under Projects/ or a #project/<name> tag"
+            },
+            "path": {
+                "type": "string",
+                "description": "Only tasks in notes whose path contains this text, e.g. \"Journal\""
+            },
+            "due_from": {
+                "type": "string",
+                "description": "Only tasks due on or after this date (YYYY-MM-DD, today, yesterday or tomorrow)"
+            },
+            "due_to": {
+                "type": "string",
+                "description": "Only tasks due on or before this date (YYYY-MM-DD, today, yesterday or tomorrow)"
+            },
+            "priority": {
+                "type": "string",
+                "enum": ["lowest", "low", "medium", "high", "highest"],
+                "description": "Minimum priority; tasks without one rank between low and medium"
+            },
+            "parent": {
+                "type": "string",
+                "description": "Only direct subtasks of this task ID"
+            },
+            "query": {
+                "type": "string",
+                "description": "Optional task query combined with the other filters, with the query_tasks syntax, e.g. \"overdue sort by due\""
+            },
+            "limit": {
                 "type": "integer",
                 "minimum": 1,
-                "description": "Task ID (provide either id or title)"
+                "maximum": 500,
+                "default": 50
+            }
+        }
+    });
+    Tool::new(
+        "list_tasks",
+        "List tasks as JSON objects with id, title, status, dates, priority, tags, source note and parent",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("List Tasks")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
+fn update_task_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "id": {
+                "type": "string",
+                "description": "Task ID, the 🆔 value such as \"k3x9q2\" (provide either id or title)"
             },
             "title": {
                 "type": "string",
@@ -287,6 +1217,234 @@ fn update_task_tool() -> Tool {
     )
 }
 
+fn edit_task_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "id": {
+                "type": "string",
+                "description": "Task ID, the 🆔 value such as \"k3x9q2\""
+            },
+            "title": { "type": "string", "description": "New task description" },
+            "due": {
+                "type": "string",
+                "description": "Due date as YYYY-MM-DD, today or tomorrow; an empty string removes it"
+            },
+            "scheduled": {
+                "type": "string",
+                "description": "Scheduled date as YYYY-MM-DD, today or tomorrow; an empty string removes it"
+            },
+            "start": {
+                "type": "string",
+                "description": "Start date as YYYY-MM-DD, today or tomorrow; an empty string removes it"
+            },
+            "priority": {
+                "type": "string",
+                "enum": ["", "lowest", "low", "medium", "high", "highest"],
+                "description": "Priority; an empty string removes it"
+            },
+            "recurrence": {
+                "type": "string",
+                "description": "Recurrence rule such as \"every week\"; an empty string removes it"
+            },
+            "add_tags": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Tags to add, with or without #"
+            },
+            "remove_tags": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Tags to remove, with or without #"
+            }
+        },
+        "required": ["id"]
+    });
+    Tool::new(
+        "edit_task",
+        "Change a task's title, dates, priority, recurrence or tags; omitted fields are left as they are. Returns the updated task as JSON",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Edit Task")
+            .destructive(false)
+            .idempotent(false),
+    )
+}
+
+fn delete_task_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "id": {
+                "type": "string",
+                "description": "Task ID, the 🆔 value such as \"k3x9q2\""
+            }
+        },
+        "required": ["id"]
+    });
+    Tool::new(
+        "delete_task",
+        "Delete a task and its subtasks from its note; returns the deleted task as JSON",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Delete Task")
+            .destructive(true)
+            .idempotent(false),
+    )
+}
+
+fn move_task_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "id": {
+                "type": "string",
+                "description": "Task ID, the 🆔 value such as \"k3x9q2\""
+            },
+            "note": {
+                "type": "string",
+                "description": "Destination note relative to the vault root, e.g. \"Projects/Garden\"; created if missing"
+            },
+            "section": {
+                "type": "string",
+                "description": "Heading to place the task under; created if missing"
+            }
+        },
+        "required": ["id", "note"]
+    });
+    Tool::new(
+        "move_task",
+        "Move a task with its subtasks to another note or heading",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Move Task")
+            .destructive(false)
+            .idempotent(true),
+    )
+}
+
+fn read_note_tool() -> Tool {
+    Tool::new("read_note", "Read a note's Markdown content", note_schema()).annotate(
+        ToolAnnotations::with_title("Read Note")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
+fn create_note_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "path": {
+                "type": "string",
+                "description": "Vault-relative path or title, e.g. \"Projects/Garden\"; .md is added if missing and folders are created"
+            },
+            "content": {
+                "type": "string",
+                "description": "Markdown content of the note (provide either content or template)"
+            },
+            "template": {
+                "type": "string",
+                "description": "Name of a note template to render instead of content (provide either content or template); defaults to the note template"
+            }
+        },
+        "required": ["path"]
+    });
+    Tool::new(
+        "create_note",
+        "Create a new note; fails if it already exists",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Create Note")
+            .destructive(false)
+            .idempotent(false),
+    )
+}
+
+fn list_notes_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "folder": {
+                "type": "string",
+                "description": "Only notes below this vault-relative folder, e.g. \"Projects\""
+            },
+            "glob": {
+                "type": "string",
+                "description": "Only paths matching this glob; * and ? stay within a folder, ** spans folders, and a pattern without / matches the file name, e.g. \"Journal/2025-*.md\""
+            },
+            "tag": {
+                "type": "string",
+                "description": "Only notes with this tag (inline or in frontmatter) or a tag nested below it"
+            },
+            "limit": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": 1000,
+                "default": 100
+            }
+        }
+    });
+    Tool::new(
+        "list_notes",
+        "List notes as JSON objects with path, title, size and modification time",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("List Notes")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
+fn patch_note_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "note": {
+                "type": "string",
+                "description": "Note path relative to the vault (\"Projects/alpha.md\") or its link name (\"alpha\")"
+            },
+            "operation": {
+                "type": "string",
+                "enum": ["replace_section", "insert_after", "unified_diff"],
+                "description": "replace_section replaces the body under `heading` with `content` (appending the section if missing); insert_after inserts `content` after the single line equal to `anchor`; unified_diff applies `diff`"
+            },
+            "heading": {
+                "type": "string",
+                "description": "Heading text for replace_section, without #"
+            },
+            "anchor": {
+                "type": "string",
+                "description": "Full text of the line to insert after, for insert_after; must match exactly one line"
+            },
+            "content": {
+                "type": "string",
+                "description": "Markdown to write, for replace_section and insert_after"
+            },
+            "diff": {
+                "type": "string",
+                "description": "Unified diff against the note's current content, for unified_diff"
+            }
+        },
+        "required": ["note", "operation"]
+    });
+    Tool::new(
+        "patch_note",
+        "Edit part of a note: replace a heading's section, insert after a line, or apply a unified diff",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Patch Note")
+            .destructive(true)
+            .idempotent(false),
+    )
+}
+
 fn query_knowledge_tool() -> Tool {
     let schema = json!({
         "type": "object",
@@ -297,6 +1455,11 @@ fn query_knowledge_tool() -> Tool {
                 "minimum": 1,
                 "maximum": 50,
                 "default": 5
+            },
+            "properties": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Frontmatter filters such as \"status=active\" or \"project\" (key present); all must match"
             }
         },
         "required": ["query"]
@@ -313,6 +1476,28 @@ fn query_knowledge_tool() -> Tool {
     )
 }
 
+fn query_tasks_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": "Task query. Filters: done, open, overdue, recurring, `due|scheduled|start|created|done before|after|on <YYYY-MM-DD|today|tomorrow|yesterday>`, `has|no due date`, `priority >= medium`, `tag:#work`, `title includes \"text\"`, `path includes Journal`, combined with and/or/not and parentheses. Clauses: `sort by <field> [reverse]`, `group by status|priority|due|tag|path|folder|bucket|project`, `limit N`. Example: \"overdue and priority >= high sort by due\". Defaults to \"not done\"."
+            }
+        }
+    });
+    Tool::new(
+        "query_tasks",
+        "Find tasks with a filter/sort/group query",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Query Tasks")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
 fn summarize_today_tool() -> Tool {
     let schema = json!({
         "type": "object",
@@ -336,6 +1521,93 @@ fn summarize_today_tool() -> Tool {
     )
 }
 
+fn note_schema() -> Arc<JsonObject> {
+    schema_arc(json!({
+        "type": "object",
+        "properties": {
+            "note": {
+                "type": "string",
+                "description": "Note path relative to the vault (\"Projects/alpha.md\") or its link name (\"alpha\")"
+            }
+        },
+        "required": ["note"]
+    }))
+}
+
+fn note_links_tool() -> Tool {
+    Tool::new(
+        "note_links",
+        "List the wikilinks and embeds in a note with the files they resolve to",
+        note_schema(),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Note Links")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
+fn note_backlinks_tool() -> Tool {
+    Tool::new(
+        "note_backlinks",
+        "List the notes linking to a note, with the linking line for context",
+        note_schema(),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Note Backlinks")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
+fn orphan_notes_tool() -> Tool {
+    Tool::new(
+        "orphan_notes",
+        "List notes that no other note links to",
+        schema_arc(json!({ "type": "object", "properties": {} })),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Orphan Notes")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
+fn broken_links_tool() -> Tool {
+    Tool::new(
+        "broken_links",
+        "List wikilinks whose note, heading or block does not exist",
+        schema_arc(json!({ "type": "object", "properties": {} })),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Broken Links")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
+fn lookup_tags_tool() -> Tool {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "tag": {
+                "type": "string",
+                "description": "Tag to look up, e.g. \"project\" or \"#project/alpha\"; nested tags are included. Omit to list every tag with its count."
+            }
+        }
+    });
+    Tool::new(
+        "lookup_tags",
+        "List vault tags with counts, or the notes and tasks carrying a tag",
+        schema_arc(schema),
+    )
+    .annotate(
+        ToolAnnotations::with_title("Lookup Tags")
+            .read_only(true)
+            .idempotent(true),
+    )
+}
+
 fn schema_arc(value: serde_json::Value) -> Arc<JsonObject> {
     Arc::new(rmcp::model::object(value))
 }
@@ -347,6 +1619,86 @@ fn internal_error(context: &str, err: anyhow::Error) -> McpError {
     )
 }
 
+/// Task ids are strings, but older clients still send legacy numeric ids.
+fn deserialize_task_id<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(id)) => Ok(Some(id)),
+        Some(serde_json::Value::Number(id)) => Ok(Some(id.to_string())),
+        Some(other) => Err(serde::de::Error::custom(format!(
+            "task id must be a string, got {other}"
+        ))),
+    }
+}
+
+fn deserialize_required_task_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_task_id(deserializer)?
+        .ok_or_else(|| serde::de::Error::custom("task id must not be null"))
+}
+
+/// Map resource failures to MCP errors: unknown resources and missing notes
+/// are `resource_not_found`, malformed URIs invalid params.
+fn resource_error(context: &str, err: anyhow::Error) -> McpError {
+    match ErrorKind::of(&err) {
+        ErrorKind::NotFound => McpError::resource_not_found(format!("{err:#}"), None),
+        ErrorKind::Usage => McpError::invalid_params(format!("{err:#}"), None),
+        _ => internal_error(context, err),
+    }
+}
+
+/// Report failures caused by the request (bad input, unknown task, note or
+/// prompt) as invalid params so clients can correct the call; anything
+/// else is an internal error.
+fn request_error(context: &str, err: anyhow::Error) -> McpError {
+    match ErrorKind::of(&err) {
+        ErrorKind::Usage | ErrorKind::NotFound | ErrorKind::Conflict => {
+            McpError::invalid_params(format!("{context} failed: {err:#}"), None)
+        }
+        _ => internal_error(context, err),
+    }
+}
+
+/// A `YYYY-MM-DD`, `today`, `yesterday` or `tomorrow` argument; an empty
+/// string counts as absent.
+fn date_param(field: &str, value: Option<&str>) -> Result<Option<NaiveDate>, McpError> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(text) => resources::parse_date(text).map(Some).ok_or_else(|| {
+            McpError::invalid_params(
+                format!("{field} must be YYYY-MM-DD, today, yesterday or tomorrow"),
+                Some(json!({ field: text })),
+            )
+        }),
+    }
+}
+
+fn priority_param(value: Option<&str>) -> Result<Option<Priority>, McpError> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(text) => Priority::from_label(text).map(Some).ok_or_else(|| {
+            McpError::invalid_params(
+                format!("unknown priority: {text}"),
+                Some(json!({ "priority": text })),
+            )
+        }),
+    }
+}
+
+/// An edit of a field that an empty string clears: `None` when omitted,
+/// else the parsed value, `Some(None)` for an empty string.
+fn clearable<T>(
+    value: Option<&str>,
+    parse: impl FnOnce(&str) -> Result<Option<T>, McpError>,
+) -> Result<Option<Option<T>>, McpError> {
+    value.map(parse).transpose()
+}
+
 fn normalize_status(input: &str) -> Result<bool, McpError> {
     match input.to_lowercase().as_str() {
         "done" | "complete" | "completed" | "finished" => Ok(true),
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, TransactionBehavior};
+use serde::Serialize;
+
+use crate::core::{tags, walk};
+
+const INDEX_DIR_NAME: &str = ".obsctl";
+const INDEX_FILE_NAME: &str = "index.sqlite3";
+const SCHEMA_VERSION: i64 = 2;
+/// How long to wait for another process (the CLI or the MCP server) that is
+/// refreshing the index at the same time.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS files (
+    path TEXT PRIMARY KEY,
+    mtime INTEGER NOT NULL,
+    size INTEGER NOT NULL
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS notes USING fts5(
+    path UNINDEXED,
+    body,
+    tokenize = 'unicode61'
+);
+CREATE TABLE IF NOT EXISTS tags (
+    path TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    context TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tags_by_tag ON tags (tag COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS tags_by_path ON tags (path);
+"#;
+
+/// SQLite FTS5 index of the vault's Markdown files, stored under
+/// `<vault>/.obsctl/` and refreshed incrementally from file mtimes.
+pub struct SearchIndex {
+    root: PathBuf,
+    conn: Connection,
+}
+
+/// A note matched by a full-text query, best matches first.
+#[derive(Debug, Clone)]
+pub struct IndexedNote {
+    pub path: String,
+    pub body: String,
+    pub score: f64,
+}
+
+/// A tag with the number of times it occurs across the vault.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+    pub notes: usize,
+}
+
+/// One occurrence of a tag in an indexed note.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaggedLine {
+    pub path: String,
+    pub tag: String,
+    pub line: usize,
+    pub context: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct RefreshStats {
+    pub indexed: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+impl SearchIndex {
+    pub fn open(root: &Path) -> Result<Self> {
+        let dir = root.join(INDEX_DIR_NAME);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(INDEX_FILE_NAME);
+        let conn = Connection::open(&path)
+            .with_context(|| format!("open search index {}", path.display()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS files; DROP TABLE IF EXISTS notes; DROP TABLE IF EXISTS tags;",
+            )?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        conn.execute_batch(SCHEMA)
+            .with_context(|| "initialize search index schema")?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            conn,
+        })
+    }
+
+    /// Bring the index up to date, re-reading only files whose mtime or size
+    /// changed since the last refresh.
+    pub fn refresh(&mut self) -> Result<RefreshStats> {
+        let files = walk::markdown_files(&self.root)?;
+        // Take the write lock up front: a deferred transaction that reads
+        // first cannot wait for a concurrent writer and fails with BUSY.
+        let tx = self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut known: HashMap<String, (i64, i64)> = {
+            let mut stmt = tx.prepare("SELECT path, mtime, size FROM files")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut stats = RefreshStats::default();
+        for path in files {
+            let rel = walk::relative_path(&self.root, &path);
+            let metadata = fs::metadata(&path)?;
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_nanos() as i64)
+                .unwrap_or(0);
+            let size = metadata.len() as i64;
+            if known.remove(&rel) == Some((mtime, size)) {
+                stats.unchanged += 1;
+                continue;
+            }
+            let bytes = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+            let body = String::from_utf8_lossy(&bytes);
+            tx.execute("DELETE FROM notes WHERE path = ?1", params![rel])?;
+            tx.execute(
+                "INSERT INTO notes (path, body) VALUES (?1, ?2)",
+                params![rel, body],
+            )?;
+            tx.execute("DELETE FROM tags WHERE path = ?1", params![rel])?;
+            for occurrence in tags::note_tags(&body) {
+                tx.execute(
+                    "INSERT INTO tags (path, tag, line, context) VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        rel,
+                        occurrence.tag,
+                        occurrence.line as i64,
+                        occurrence.context
+                    ],
+                )?;
+            }
+            tx.execute(
+                "INSERT INTO files (path, mtime, size) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(path) DO UPDATE SET mtime = excluded.mtime, size = excluded.size",
+                params![rel, mtime, size],
+            )?;
+            stats.indexed += 1;
+        }
+
+        for stale in known.keys() {
+            tx.execute("DELETE FROM notes WHERE path = ?1", params![stale])?;
+            tx.execute("DELETE FROM files WHERE path = ?1", params![stale])?;
+            tx.execute("DELETE FROM tags WHERE path = ?1", params![stale])?;
+            stats.removed += 1;
+        }
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    /// Drop every indexed document and index the vault from scratch.
+    pub fn rebuild(&mut self) -> Result<RefreshStats> {
+        self.conn
+            .execute_batch("DELETE FROM notes; DELETE FROM files; DELETE FROM tags;")?;
+        self.refresh()
+    }
+
+    /// Run a ranked (BM25) full-text query. Every whitespace-separated term
+    /// must appear in the note; terms match as prefixes.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<IndexedNote>> {
+        let Some(expr) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT path, body, bm25(notes) FROM notes WHERE notes MATCH ?1
+             ORDER BY bm25(notes) LIMIT ?2",
+        )?;
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let rows = stmt.query_map(params![expr, limit], |row| {
+            let rank: f64 = row.get(2)?;
+            Ok(IndexedNote {
+                path: row.get(0)?,
+                body: row.get(1)?,
+                score: -rank,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .with_context(|| format!("query search index for {query:?}"))
+    }
+
+    /// Every tag with its occurrence and note counts, most used first. Tags
+    /// differing only in case are counted together.
+    pub fn tag_counts(&self) -> Result<Vec<TagCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT MIN(tag), COUNT(*), COUNT(DISTINCT path) FROM tags
+             GROUP BY tag COLLATE NOCASE ORDER BY COUNT(*) DESC, MIN(tag) COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TagCount {
+                tag: row.get(0)?,
+                count: row.get::<_, i64>(1)? as usize,
+                notes: row.get::<_, i64>(2)? as usize,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Occurrences of `tag` and the tags nested below it (`tag/...`),
+    /// case-insensitively, ordered by note and line.
+    pub fn tagged(&self, tag: &str) -> Result<Vec<TaggedLine>> {
+        let tag = tags::normalize(tag).to_lowercase();
+        let mut stmt = self.conn.prepare(
+            "SELECT path, tag, line, context FROM tags
+             WHERE lower(tag) = ?1 OR substr(lower(tag), 1, length(?1) + 1) = ?1 || '/'
+             ORDER BY path, line",
+        )?;
+        let rows = stmt.query_map(params![tag], |row| {
+            Ok(TaggedLine {
+                path: row.get(0)?,
+                tag: row.get(1)?,
+                line: row.get::<_, i64>(2)? as usize,
+                context: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// All indexed note paths, relative to the vault root.
+    pub fn paths(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT path FROM files ORDER BY path")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+    }
+}
+
+/// Split a free-form query into terms usable for line highlighting.
+pub fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+/// Quote each term so user input never leaks FTS5 operators, and allow prefix
+/// matches so partial words still hit.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter(|term| term.chars().any(char::is_alphanumeric))
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
@@ -1,24 +1,230 @@
-use std::io;
+mod index;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
-use anyhow::{anyhow, Result};
-use serde::Deserialize;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 use crate::config::AppContext;
+use crate::core::frontmatter::{Document, PropertyFilter};
+use crate::error::Error;
+
+pub use index::{RefreshStats, SearchIndex, TagCount, TaggedLine};
+
+/// Upper bound on hits printed by `search grep` when using the native index.
+pub const DEFAULT_LIMIT: usize = 50;
+
+/// Which engine answers search queries, selected by `search.tool` in config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchBackend {
+    /// Built-in SQLite FTS5 index; needs no external binaries.
+    Native,
+    /// Shell out to `rg` (and `fzf` for fuzzy finding).
+    Ripgrep,
+}
+
+impl SearchBackend {
+    pub fn from_tool(tool: &str) -> Result<Self> {
+        match tool.to_lowercase().as_str() {
+            "native" | "index" | "builtin" => Ok(SearchBackend::Native),
+            "ripgrep" | "rg" => Ok(SearchBackend::Ripgrep),
+            other => Err(Error::config(format!(
+                "unknown search tool `{other}` (expected `native` or `ripgrep`)"
+            ))
+            .into()),
+        }
+    }
+}
+
+/// A single matching line inside a note.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+impl fmt::Display for SearchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path, self.line_number, self.line)
+    }
+}
 
 pub struct SearchService {
     root: PathBuf,
+    backend: SearchBackend,
+    filters: Vec<PropertyFilter>,
+    /// Filter verdicts per path, so each note's frontmatter is read once.
+    verdicts: RefCell<HashMap<String, bool>>,
 }
 
 impl SearchService {
     pub fn new(ctx: &AppContext) -> Result<Self> {
         Ok(Self {
             root: ctx.vault_root().to_path_buf(),
+            backend: SearchBackend::from_tool(&ctx.config().search.tool)?,
+            filters: Vec::new(),
+            verdicts: RefCell::new(HashMap::new()),
         })
     }
 
-    pub fn grep(&self, query: &str) -> Result<()> {
+    /// Only return notes whose frontmatter satisfies every filter.
+    pub fn with_filters(mut self, filters: Vec<PropertyFilter>) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    pub fn backend(&self) -> SearchBackend {
+        self.backend
+    }
+
+    pub fn grep(&self, query: &str, limit: usize) -> Result<()> {
+        match self.backend {
+            SearchBackend::Native => {
+                for hit in self.search(query, limit)? {
+                    println!("{hit}");
+                }
+                Ok(())
+            }
+            SearchBackend::Ripgrep if !self.filters.is_empty() => {
+                for hit in self.rg_search(query, usize::MAX)? {
+                    println!("{hit}");
+                }
+                Ok(())
+            }
+            SearchBackend::Ripgrep => self.rg_grep(query),
+        }
+    }
+
+    pub fn fuzzy(&self, query: &str, limit: usize) -> Result<()> {
+        match self.backend {
+            SearchBackend::Native => {
+                for path in self.fuzzy_paths(query, limit)? {
+                    println!("{path}");
+                }
+                Ok(())
+            }
+            SearchBackend::Ripgrep => self.rg_fzf(query),
+        }
+    }
+
+    pub fn grep_matches(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        Ok(self
+            .search(query, limit)?
+            .into_iter()
+            .map(|hit| hit.to_string())
+            .collect())
+    }
+
+    /// Return up to `limit` matching lines, best-ranked notes first.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        match self.backend {
+            SearchBackend::Native => self.index_search(query, limit),
+            SearchBackend::Ripgrep => self.rg_search(query, limit),
+        }
+    }
+
+    /// Rank vault paths against `query` as an in-process fuzzy finder.
+    pub fn fuzzy_paths(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        let index = self.open_index()?;
+        let needle = query.to_lowercase();
+        let mut scored: Vec<(i64, String)> = index
+            .paths()?
+            .into_iter()
+            .filter(|path| self.passes_filters(path))
+            .filter_map(|path| fuzzy_score(&path.to_lowercase(), &needle).map(|s| (s, path)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        Ok(scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, path)| path)
+            .collect())
+    }
+
+    /// Tags across the vault with their counts, most used first.
+    pub fn tag_counts(&self) -> Result<Vec<TagCount>> {
+        self.open_index()?.tag_counts()
+    }
+
+    /// Note lines carrying `tag` or a tag nested below it.
+    pub fn tagged(&self, tag: &str) -> Result<Vec<TaggedLine>> {
+        self.open_index()?.tagged(tag)
+    }
+
+    /// Re-index the whole vault regardless of recorded mtimes.
+    pub fn reindex(&self) -> Result<RefreshStats> {
+        let mut index = SearchIndex::open(&self.root)?;
+        index.rebuild()
+    }
+
+    fn open_index(&self) -> Result<SearchIndex> {
+        let mut index = SearchIndex::open(&self.root)?;
+        index.refresh()?;
+        Ok(index)
+    }
+
+    /// Whether the note at `path` (relative to the vault) satisfies the
+    /// property filters.
+    fn passes_filters(&self, path: &str) -> bool {
+        if self.filters.is_empty() {
+            return true;
+        }
+        if let Some(verdict) = self.verdicts.borrow().get(path) {
+            return *verdict;
+        }
+        let verdict = fs::read_to_string(self.root.join(path))
+            .map(|text| self.document_passes(&Document::parse(&text)))
+            .unwrap_or(false);
+        self.verdicts.borrow_mut().insert(path.to_string(), verdict);
+        verdict
+    }
+
+    fn document_passes(&self, document: &Document) -> bool {
+        self.filters.iter().all(|filter| filter.matches(document))
+    }
+
+    fn index_search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let index = self.open_index()?;
+        let terms = index::query_terms(query);
+        let mut hits = Vec::new();
+        // Filters are applied per note, so rank every match before limiting.
+        let note_limit = if self.filters.is_empty() {
+            limit
+        } else {
+            usize::MAX
+        };
+        for note in index.search(query, note_limit)? {
+            if !self.document_passes(&Document::parse(&note.body)) {
+                continue;
+            }
+            for (idx, line) in note.body.lines().enumerate() {
+                let lower = line.to_lowercase();
+                if terms.iter().any(|term| lower.contains(term.as_str())) {
+                    hits.push(SearchHit {
+                        path: note.path.clone(),
+                        line_number: idx + 1,
+                        line: line.to_string(),
+                    });
+                    if hits.len() >= limit {
+                        return Ok(hits);
+                    }
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    fn rg_grep(&self, query: &str) -> Result<()> {
         let status = Command::new("rg")
             .arg("--hidden")
             .arg("--glob")
@@ -29,12 +235,15 @@ impl SearchService {
             .status()
             .map_err(|err| map_exec_error(err, "rg"))?;
         if !status.success() {
-            anyhow::bail!("ripgrep exited with {}", status);
+            anyhow::bail!(Error::external(format!("ripgrep exited with {status}")));
         }
         Ok(())
     }
 
-    pub fn fuzzy(&self, query: &str) -> Result<()> {
+    fn rg_fzf(&self, query: &str) -> Result<()> {
+        if !self.filters.is_empty() {
+            return self.rg_fzf_filtered(query);
+        }
         let mut rg = Command::new("rg")
             .args(["--files"])
             .current_dir(&self.root)
@@ -57,18 +266,54 @@ impl SearchService {
         let status_fzf = fzf.wait()?;
         let status_rg = rg.wait()?;
         if !status_rg.success() {
-            anyhow::bail!("ripgrep --files exited with {}", status_rg);
+            anyhow::bail!(Error::external(format!(
+                "ripgrep --files exited with {status_rg}"
+            )));
         }
         if !status_fzf.success() {
-            anyhow::bail!("fzf exited with {}", status_fzf);
+            anyhow::bail!(Error::external(format!("fzf exited with {status_fzf}")));
         }
         Ok(())
     }
 
-    pub fn grep_matches(&self, query: &str, limit: usize) -> Result<Vec<String>> {
-        if limit == 0 {
-            return Ok(Vec::new());
+    /// Feed fzf only the files that pass the property filters.
+    fn rg_fzf_filtered(&self, query: &str) -> Result<()> {
+        let output = Command::new("rg")
+            .args(["--files"])
+            .current_dir(&self.root)
+            .output()
+            .map_err(|err| map_exec_error(err, "rg"))?;
+        if !output.status.success() {
+            anyhow::bail!(Error::external(format!(
+                "ripgrep --files exited with {}",
+                output.status
+            )));
+        }
+        let files: Vec<&str> = std::str::from_utf8(&output.stdout)?
+            .lines()
+            .filter(|path| self.passes_filters(path))
+            .collect();
+
+        let mut fzf = Command::new("fzf")
+            .arg("--ansi")
+            .arg("--query")
+            .arg(query)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|err| map_exec_error(err, "fzf"))?;
+        if let Some(mut stdin) = fzf.stdin.take() {
+            for path in files {
+                writeln!(stdin, "{path}")?;
+            }
+        }
+        let status_fzf = fzf.wait()?;
+        if !status_fzf.success() {
+            anyhow::bail!(Error::external(format!("fzf exited with {status_fzf}")));
         }
+        Ok(())
+    }
+
+    fn rg_search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
         let output = Command::new("rg")
             .arg("--hidden")
             .arg("--glob")
@@ -82,14 +327,14 @@ impl SearchService {
         let success = output.status.success();
         let code = output.status.code();
         if !success && code != Some(1) {
-            anyhow::bail!(
+            anyhow::bail!(Error::external(format!(
                 "ripgrep exited with {}",
                 output
                     .status
                     .code()
                     .map(|c| c.to_string())
                     .unwrap_or_else(|| "unknown".into())
-            );
+            )));
         }
         let mut results = Vec::new();
         for line in output.stdout.split(|b| *b == b'\n') {
@@ -104,19 +349,45 @@ impl SearchService {
                 Err(_) => continue,
             };
             if let Some(data) = parsed.match_data() {
-                let formatted = format!(
-                    "{}:{}: {}",
-                    data.path,
-                    data.line_number,
-                    data.line.trim_end_matches('\n')
-                );
-                results.push(formatted);
+                if !self.passes_filters(&data.path) {
+                    continue;
+                }
+                results.push(SearchHit {
+                    path: data.path,
+                    line_number: data.line_number,
+                    line: data.line.trim_end_matches('\n').to_string(),
+                });
             }
         }
         Ok(results)
     }
 }
 
+/// Score `candidate` as a case-folded subsequence match of `needle`, rewarding
+/// consecutive characters and matches at path/word boundaries.
+fn fuzzy_score(candidate: &str, needle: &str) -> Option<i64> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut score = 0i64;
+    let mut pos = 0usize;
+    let mut last_match: Option<usize> = None;
+    for wanted in needle.chars().filter(|c| !c.is_whitespace()) {
+        let found = (pos..chars.len()).find(|&i| chars[i] == wanted)?;
+        score += 1;
+        if last_match.map(|last| last + 1 == found).unwrap_or(false) {
+            score += 5;
+        }
+        if found == 0 || matches!(chars[found - 1], '/' | '-' | '_' | ' ' | '.') {
+            score += 3;
+        }
+        last_match = Some(found);
+        pos = found + 1;
+    }
+    Some(score * 10 - chars.len() as i64)
+}
+
 #[derive(Debug, Deserialize)]
 struct RgMessage {
     #[serde(rename = "type")]
@@ -159,13 +430,15 @@ struct RgMatch {
 
 fn map_exec_error(err: io::Error, tool: &str) -> anyhow::Error {
     if err.kind() == io::ErrorKind::NotFound {
-        anyhow!(
+        Error::external(format!(
             r#"`{tool}` was not found in PATH. Please install it before using `obsctl search`. For example:
   • macOS (Homebrew): brew install {tool}
   • Ubuntu/Debian:   sudo apt-get install {tool}
-  • Arch Linux:      sudo pacman -S {tool}"#
-        )
+  • Arch Linux:      sudo pacman -S {tool}
+Alternatively set `tool = "native"` under `[search]` to use the built-in index."#
+        ))
+        .into()
     } else {
-        anyhow::Error::new(err).context(format!("failed to execute {tool}"))
+        anyhow::Error::new(err).context(Error::external(format!("failed to execute {tool}")))
     }
 }
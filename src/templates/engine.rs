@@ -0,0 +1,605 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, Timelike};
+
+use crate::config::AppConfig;
+use crate::core::periodic::Period;
+
+/// Nested `{{> partial}}` includes deeper than this are treated as a cycle.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Answers a `{{prompt:Question}}` placeholder.
+pub type Prompt = dyn Fn(&str) -> Result<String>;
+
+/// Values available while rendering a template.
+pub struct TemplateContext<'a> {
+    /// The date the note is for; `{{date}}` and date math start here.
+    pub date: NaiveDate,
+    /// The current time, for `{{time}}`.
+    pub now: NaiveDateTime,
+    /// The note name, for `{{title}}`.
+    pub title: String,
+    /// Config and caller supplied `{{variables}}`.
+    pub variables: BTreeMap<String, String>,
+    /// Daily note format, used for `{{yesterday}}` and `{{tomorrow}}` links.
+    pub daily_format: String,
+    /// Folder `{{> partial}}` names are resolved in.
+    pub templates_dir: PathBuf,
+    /// Asks for `{{prompt:Question}}` values; prompts render empty without it.
+    pub prompt: Option<&'a Prompt>,
+}
+
+impl<'a> TemplateContext<'a> {
+    /// A context for a note dated `date`, with the variables from
+    /// `[templates.variables]`.
+    pub fn new(config: &AppConfig, vault_root: &Path, date: NaiveDate, title: &str) -> Self {
+        Self {
+            date,
+            now: Local::now().naive_local(),
+            title: title.to_string(),
+            variables: config.templates.variables.clone(),
+            daily_format: config.daily.format(Period::Day).to_string(),
+            templates_dir: vault_root.join("templates"),
+            prompt: None,
+        }
+    }
+
+    pub fn with_variables(mut self, variables: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.variables.extend(variables);
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: &'a Prompt) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+}
+
+/// Render a template. Supported syntax, following Obsidian core templates
+/// and a subset of Templater:
+///
+/// - `{{date}}`, `{{time}}`, `{{date:YYYY-MM-DD}}`, `{{time:HH:mm}}` (moment
+///   or chrono `%` formats) and date math such as `{{date+1d}}`,
+///   `{{date-2w:dddd}}` (units `d`, `w`, `m`, `y`)
+/// - `{{title}}`, `{{weekday}}`, `{{yesterday}}` / `{{today}}` /
+///   `{{tomorrow}}` daily note links, and user variables `{{name}}`
+/// - `{{prompt:Question}}`, asked once per question
+/// - `{{#if name}}...{{else}}...{{/if}}`, also `{{#if name == "value"}}`,
+///   `!=` and `{{#if !name}}`
+/// - `{{> partial}}` includes `templates/partial.md`
+/// - `<% tp.date.now("YYYY-MM-DD", -1) %>`, `tp.date.tomorrow`,
+///   `tp.date.yesterday`, `tp.file.title` and `tp.system.prompt("Question")`
+///
+/// Unknown tags are left as written.
+pub fn render(template: &str, ctx: &TemplateContext<'_>) -> Result<String> {
+    let renderer = Renderer {
+        ctx,
+        answers: RefCell::new(HashMap::new()),
+    };
+    renderer.render(template, 0)
+}
+
+/// Format a date and time with a moment.js format (`YYYY-MM-DD`, `dddd`,
+/// `HH:mm`) or, when it contains `%`, a chrono format.
+pub fn format_datetime(format: &str, value: NaiveDateTime) -> Result<String> {
+    if format.contains('%') {
+        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+            bail!("invalid date format `{format}`");
+        }
+        let mut out = String::new();
+        write!(out, "{}", value.format(format))
+            .map_err(|_| anyhow!("invalid date format `{format}`"))?;
+        return Ok(out);
+    }
+
+    const TOKENS: &[(&str, &str)] = &[
+        ("YYYY", "%Y"),
+        ("YY", "%y"),
+        ("MMMM", "%B"),
+        ("MMM", "%b"),
+        ("MM", "%m"),
+        ("M", "%-m"),
+        ("DDDD", "%j"),
+        ("DD", "%d"),
+        ("D", "%-d"),
+        ("dddd", "%A"),
+        ("ddd", "%a"),
+        ("d", "%w"),
+        ("E", "%u"),
+        ("GGGG", "%G"),
+        ("WW", "%V"),
+        ("W", "%-V"),
+        ("HH", "%H"),
+        ("H", "%-H"),
+        ("hh", "%I"),
+        ("h", "%-I"),
+        ("mm", "%M"),
+        ("m", "%-M"),
+        ("ss", "%S"),
+        ("s", "%-S"),
+        ("A", "%p"),
+        ("a", "%P"),
+        ("X", "%s"),
+        ("Z", "%:z"),
+    ];
+    let mut out = String::new();
+    let mut rest = format;
+    'outer: while let Some(ch) = rest.chars().next() {
+        // `[text]` is literal in moment formats.
+        if ch == '[' {
+            if let Some(end) = rest.find(']') {
+                out.push_str(&rest[1..end]);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        if let Some(tail) = rest.strip_prefix("Do") {
+            let day = value.day();
+            let suffix = match (day % 10, day % 100) {
+                (_, 11..=13) => "th",
+                (1, _) => "st",
+                (2, _) => "nd",
+                (3, _) => "rd",
+                _ => "th",
+            };
+            write!(out, "{day}{suffix}")?;
+            rest = tail;
+            continue;
+        }
+        if let Some(tail) = rest.strip_prefix('Q') {
+            write!(out, "{}", value.month0() / 3 + 1)?;
+            rest = tail;
+            continue;
+        }
+        for (token, spec) in TOKENS {
+            if let Some(tail) = rest.strip_prefix(token) {
+                write!(out, "{}", value.format(spec))?;
+                rest = tail;
+                continue 'outer;
+            }
+        }
+        out.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+    Ok(out)
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    /// A `{{...}}` or `<% ... %>` tag with its original text.
+    Tag {
+        raw: String,
+        expr: Expr,
+    },
+    If {
+        condition: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+#[derive(Debug)]
+enum Expr {
+    Mustache(String),
+    Templater(String),
+}
+
+enum Token {
+    Text(String),
+    Tag { raw: String, expr: Expr },
+    If(String),
+    Else,
+    EndIf,
+}
+
+struct Renderer<'a, 'b> {
+    ctx: &'a TemplateContext<'b>,
+    answers: RefCell<HashMap<String, String>>,
+}
+
+impl Renderer<'_, '_> {
+    fn render(&self, template: &str, depth: usize) -> Result<String> {
+        let nodes = parse(template)?;
+        let mut out = String::with_capacity(template.len());
+        self.render_nodes(&nodes, depth, &mut out)?;
+        Ok(out)
+    }
+
+    fn render_nodes(&self, nodes: &[Node], depth: usize, out: &mut String) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Tag { raw, expr } => {
+                    let value = match expr {
+                        Expr::Mustache(inner) => self.mustache(inner, depth)?,
+                        Expr::Templater(inner) => self.templater(inner)?,
+                    };
+                    out.push_str(value.as_deref().unwrap_or(raw));
+                }
+                Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    let branch = if self.condition(condition)? {
+                        then
+                    } else {
+                        otherwise
+                    };
+                    self.render_nodes(branch, depth, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Value of a `{{...}}` tag, or `None` to keep it as written.
+    fn mustache(&self, inner: &str, depth: usize) -> Result<Option<String>> {
+        let inner = inner.trim();
+        if let Some(name) = inner.strip_prefix('>') {
+            return self.include(name.trim(), depth).map(Some);
+        }
+        if let Some(question) = inner.strip_prefix("prompt:") {
+            return self.ask(question.trim()).map(Some);
+        }
+        self.value(inner)
+    }
+
+    /// Built-in and user variables, including date and time formatting.
+    fn value(&self, name: &str) -> Result<Option<String>> {
+        let ctx = self.ctx;
+        let (head, format) = match name.split_once(':') {
+            Some((head, format)) => (head.trim(), Some(format)),
+            None => (name, None),
+        };
+        let value = match head {
+            "title" => ctx.title.clone(),
+            "weekday" => ctx.date.format("%A").to_string(),
+            "yesterday" => self.daily_link(-1),
+            "today" => self.daily_link(0),
+            "tomorrow" => self.daily_link(1),
+            _ if head.starts_with("date") || head.starts_with("time") => {
+                let is_date = head.starts_with("date");
+                let Some(offset) = parse_offset(&head[4..]) else {
+                    return Ok(ctx.variables.get(name).cloned());
+                };
+                let base = if is_date {
+                    ctx.date.and_time(ctx.now.time())
+                } else {
+                    ctx.now
+                };
+                let Some(value) = offset.apply(base) else {
+                    bail!("date out of range in {{{{{name}}}}}");
+                };
+                let default = if is_date { "YYYY-MM-DD" } else { "HH:mm" };
+                format_datetime(format.unwrap_or(default), value)?
+            }
+            _ => return Ok(ctx.variables.get(name).cloned()),
+        };
+        Ok(Some(value))
+    }
+
+    /// Value of a `<% ... %>` Templater tag, or `None` to keep it as written.
+    fn templater(&self, inner: &str) -> Result<Option<String>> {
+        let inner = inner.trim_matches(['-', '_']).trim();
+        if inner.starts_with('*') {
+            return Ok(None);
+        }
+        if inner == "tp.file.title" {
+            return Ok(Some(self.ctx.title.clone()));
+        }
+        let Some((function, args)) = inner
+            .strip_suffix(')')
+            .and_then(|call| call.split_once('('))
+        else {
+            return Ok(None);
+        };
+        let Some(args) = parse_args(args) else {
+            return Ok(None);
+        };
+        let format = args.first().map(String::as_str).unwrap_or("YYYY-MM-DD");
+        let now = self.ctx.date.and_time(self.ctx.now.time());
+        let days = match (function.trim(), args.len()) {
+            ("tp.system.prompt", 1..) => return self.ask(&args[0]).map(Some),
+            ("tp.date.now", 0..=1) => 0,
+            ("tp.date.now", 2) => match args[1].parse::<i64>() {
+                Ok(days) => days,
+                Err(_) => return Ok(None),
+            },
+            ("tp.date.tomorrow", 0..=1) => 1,
+            ("tp.date.yesterday", 0..=1) => -1,
+            _ => return Ok(None),
+        };
+        let Some(value) = (Offset {
+            amount: days,
+            unit: 'd',
+        })
+        .apply(now) else {
+            bail!("date out of range in <% {inner} %>");
+        };
+        format_datetime(format, value).map(Some)
+    }
+
+    fn condition(&self, condition: &str) -> Result<bool> {
+        let condition = condition.trim();
+        for (op, equal) in [("==", true), ("!=", false)] {
+            if let Some((name, expected)) = condition.split_once(op) {
+                let actual = self.value(name.trim())?.unwrap_or_default();
+                let expected = expected.trim().trim_matches(['"', '\'']);
+                return Ok((actual == expected) == equal);
+            }
+        }
+        if let Some(name) = condition.strip_prefix('!') {
+            return Ok(!self.condition(name)?);
+        }
+        let value = match condition.strip_prefix("prompt:") {
+            Some(question) => self.ask(question.trim())?,
+            None => self.value(condition)?.unwrap_or_default(),
+        };
+        let falsy = ["false", "no", "n", "0"]
+            .iter()
+            .any(|word| value.trim().eq_ignore_ascii_case(word));
+        Ok(!value.trim().is_empty() && !falsy)
+    }
+
+    fn include(&self, name: &str, depth: usize) -> Result<String> {
+        if depth >= MAX_INCLUDE_DEPTH {
+            bail!("template includes nest too deeply at `{{{{> {name}}}}}`");
+        }
+        let file = if name.ends_with(".md") {
+            name.to_string()
+        } else {
+            format!("{name}.md")
+        };
+        let path = self.ctx.templates_dir.join(file);
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("read partial template {}", path.display()))?;
+        let rendered = self.render(&text, depth + 1)?;
+        // An include on its own line should not add a blank line.
+        Ok(rendered.strip_suffix('\n').unwrap_or(&rendered).to_string())
+    }
+
+    fn ask(&self, question: &str) -> Result<String> {
+        if let Some(answer) = self.answers.borrow().get(question) {
+            return Ok(answer.clone());
+        }
+        let answer = match (self.ctx.variables.get(question), self.ctx.prompt) {
+            (Some(value), _) => value.clone(),
+            (None, Some(prompt)) => prompt(question)?,
+            (None, None) => String::new(),
+        };
+        self.answers
+            .borrow_mut()
+            .insert(question.to_string(), answer.clone());
+        Ok(answer)
+    }
+
+    fn daily_link(&self, days: i64) -> String {
+        let date = (Offset {
+            amount: days,
+            unit: 'd',
+        })
+        .apply(self.ctx.date.and_time(self.ctx.now.time()))
+        .map(|value| value.date())
+        .unwrap_or(self.ctx.date);
+        let name = Period::format(&self.ctx.daily_format, date);
+        let name = name.rsplit('/').next().unwrap_or(&name);
+        format!("[[{name}]]")
+    }
+}
+
+/// Date math such as `+1d` or `-2w`.
+#[derive(Debug, Clone, Copy)]
+struct Offset {
+    amount: i64,
+    unit: char,
+}
+
+impl Offset {
+    fn apply(self, value: NaiveDateTime) -> Option<NaiveDateTime> {
+        let (days, months) = match self.unit {
+            'd' => (self.amount, 0),
+            'w' => (self.amount.checked_mul(7)?, 0),
+            'm' => (0, self.amount),
+            'y' => (0, self.amount.checked_mul(12)?),
+            _ => return None,
+        };
+        let date = value.date();
+        let date = if days >= 0 {
+            date.checked_add_days(Days::new(days.unsigned_abs()))?
+        } else {
+            date.checked_sub_days(Days::new(days.unsigned_abs()))?
+        };
+        let months_abs = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+        let date = if months >= 0 {
+            date.checked_add_months(months_abs)?
+        } else {
+            date.checked_sub_months(months_abs)?
+        };
+        date.and_hms_opt(value.hour(), value.minute(), value.second())
+    }
+}
+
+/// Parse the part after `date`/`time`: empty or `+Nd`, `-Nw`, ...
+fn parse_offset(text: &str) -> Option<Offset> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(Offset {
+            amount: 0,
+            unit: 'd',
+        });
+    }
+    let sign = match text.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let unit = text.chars().last()?;
+    if !matches!(unit, 'd' | 'w' | 'm' | 'y') {
+        return None;
+    }
+    let amount: i64 = text[1..text.len() - 1].trim().parse().ok()?;
+    Some(Offset {
+        amount: sign * amount,
+        unit,
+    })
+}
+
+/// Split Templater call arguments: quoted strings and numbers.
+fn parse_args(args: &str) -> Option<Vec<String>> {
+    let mut out = Vec::new();
+    let mut rest = args.trim();
+    while !rest.is_empty() {
+        let (value, tail) = match rest.chars().next()? {
+            quote @ ('"' | '\'') => {
+                let end = rest[1..].find(quote)? + 1;
+                (rest[1..end].to_string(), &rest[end + 1..])
+            }
+            _ => {
+                let end = rest.find(',').unwrap_or(rest.len());
+                (rest[..end].trim().to_string(), &rest[end..])
+            }
+        };
+        out.push(value);
+        rest = tail.trim_start();
+        match rest.strip_prefix(',') {
+            Some(tail) => rest = tail.trim_start(),
+            None if rest.is_empty() => {}
+            None => return None,
+        }
+    }
+    Some(out)
+}
+
+fn parse(template: &str) -> Result<Vec<Node>> {
+    let tokens = tokenize(template);
+    // Stack of open `{{#if}}` blocks: condition, then-branch, else-branch
+    // once `{{else}}` was seen.
+    let mut stack: Vec<(String, Vec<Node>, Option<Vec<Node>>)> = Vec::new();
+    let mut root = Vec::new();
+    for token in tokens {
+        let current = match stack.last_mut() {
+            Some((_, then, None)) => then,
+            Some((_, _, Some(otherwise))) => otherwise,
+            None => &mut root,
+        };
+        match token {
+            Token::Text(text) => current.push(Node::Text(text)),
+            Token::Tag { raw, expr } => current.push(Node::Tag { raw, expr }),
+            Token::If(condition) => stack.push((condition, Vec::new(), None)),
+            Token::Else => match stack.last_mut() {
+                Some((_, _, otherwise @ None)) => *otherwise = Some(Vec::new()),
+                _ => bail!("`{{{{else}}}}` outside of `{{{{#if}}}}`"),
+            },
+            Token::EndIf => {
+                let (condition, then, otherwise) = stack
+                    .pop()
+                    .ok_or_else(|| anyhow!("`{{{{/if}}}}` without `{{{{#if}}}}`"))?;
+                let node = Node::If {
+                    condition,
+                    then,
+                    otherwise: otherwise.unwrap_or_default(),
+                };
+                match stack.last_mut() {
+                    Some((_, then, None)) => then.push(node),
+                    Some((_, _, Some(otherwise))) => otherwise.push(node),
+                    None => root.push(node),
+                }
+            }
+        }
+    }
+    if let Some((condition, _, _)) = stack.last() {
+        bail!("unclosed `{{{{#if {condition}}}}}`");
+    }
+    Ok(root)
+}
+
+fn tokenize(template: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    loop {
+        let next = [rest.find("{{"), rest.find("<%")]
+            .into_iter()
+            .flatten()
+            .min();
+        let Some(start) = next else {
+            text.push_str(rest);
+            break;
+        };
+        let (close, templater) = if rest[start..].starts_with("{{") {
+            ("}}", false)
+        } else {
+            ("%>", true)
+        };
+        let Some(len) = rest[start + 2..].find(close) else {
+            text.push_str(rest);
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let inner = &rest[start + 2..end - 2];
+        text.push_str(&rest[..start]);
+        rest = &rest[end..];
+
+        let block = if templater {
+            None
+        } else {
+            let trimmed = inner.trim();
+            match trimmed {
+                "else" => Some(Token::Else),
+                "/if" => Some(Token::EndIf),
+                _ => trimmed
+                    .strip_prefix("#if ")
+                    .map(|condition| Token::If(condition.trim().to_string())),
+            }
+        };
+        match block {
+            Some(block) => {
+                // A block tag alone on its line takes the line with it.
+                let line_start = text.rfind('\n').map(|i| i + 1).unwrap_or(0);
+                let before_blank = text[line_start..].trim().is_empty();
+                let after = rest.find('\n').map(|i| i + 1).unwrap_or(rest.len());
+                let after_blank = rest[..after].trim().is_empty();
+                if before_blank && after_blank {
+                    text.truncate(line_start);
+                    rest = &rest[after..];
+                }
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(block);
+            }
+            None => {
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                let raw = template_slice(inner, templater);
+                let expr = if templater {
+                    Expr::Templater(inner.to_string())
+                } else {
+                    Expr::Mustache(inner.to_string())
+                };
+                tokens.push(Token::Tag { raw, expr });
+            }
+        }
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    tokens
+}
+
+fn template_slice(inner: &str, templater: bool) -> String {
+    if templater {
+        format!("<%{inner}%>")
+    } else {
+        format!("{{{{{inner}}}}}")
+    }
+}
@@ -1,8 +1,18 @@
+mod engine;
+
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
+use crate::config::AppConfig;
+use crate::core::periodic::Period;
+use crate::core::storage;
+use crate::core::walk;
+use crate::error::Error;
+
+pub use engine::{format_datetime, render, Prompt, TemplateContext};
+
 const DAILY_TEMPLATE: &str = r#"# Daily {{date}}
 
 ## Highlights
@@ -18,6 +28,36 @@ const DAILY_TEMPLATE: &str = r#"# Daily {{date}}
 - 
 "#;
 
+const WEEKLY_TEMPLATE: &str = r#"# Week {{title}}
+
+## Days
+
+{{days}}
+
+## Review
+
+- 
+"#;
+
+const PERIOD_TEMPLATE: &str = r#"# {{title}}
+
+## Goals
+
+- 
+
+## Review
+
+- 
+"#;
+
+const NEW_TEMPLATE: &str = r#"# {{title}}
+
+Created {{date}} {{time}}
+
+"#;
+
+const NOTE_TEMPLATE: &str = "# {{title}}\n";
+
 const TASK_TEMPLATE: &str = r#"# Tasks
 
 - [ ] Example task
@@ -31,19 +71,210 @@ pub fn install_defaults(vault_root: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn load_daily_template(vault_root: &Path) -> Result<String> {
-    let path = vault_root.join("templates/daily.md");
-    load_template(&path, DAILY_TEMPLATE)
+/// What a template is used for. Each kind has a default template and may
+/// have named alternatives under `[templates.named.<kind>]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateKind {
+    Periodic(Period),
+    /// The initial content of `Tasks/tasks.md`.
+    Task,
+    /// Notes created with `note new`.
+    Note,
+}
+
+impl TemplateKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            TemplateKind::Periodic(period) => period.name(),
+            TemplateKind::Task => "task",
+            TemplateKind::Note => "note",
+        }
+    }
+
+    fn builtin(self) -> &'static str {
+        match self {
+            TemplateKind::Periodic(Period::Day) => DAILY_TEMPLATE,
+            TemplateKind::Periodic(Period::Week) => WEEKLY_TEMPLATE,
+            TemplateKind::Periodic(_) => PERIOD_TEMPLATE,
+            TemplateKind::Task => TASK_TEMPLATE,
+            TemplateKind::Note => NOTE_TEMPLATE,
+        }
+    }
+}
+
+/// Load the template for `kind`, or its alternative called `name`.
+///
+/// A name is looked up in `[templates.named.<kind>]`, then as
+/// `templates/<name>.md`. Without a name, the configured template is used
+/// (`[daily] template` or `templates.daily`, `templates.task`, ...), then
+/// `templates/<kind>.md`, then the built-in default.
+pub fn load(
+    config: &AppConfig,
+    vault_root: &Path,
+    kind: TemplateKind,
+    name: Option<&str>,
+) -> Result<String> {
+    match resolve(config, vault_root, kind, name)? {
+        Some(path) => {
+            fs::read_to_string(&path).with_context(|| format!("read template {}", path.display()))
+        }
+        None => Ok(kind.builtin().to_string()),
+    }
+}
+
+/// Check that every template path in the config exists, so a typo fails at
+/// startup rather than when a note is first created.
+pub fn validate(config: &AppConfig, vault_root: &Path) -> Result<()> {
+    let missing: Vec<String> = configured(config)
+        .into_iter()
+        .map(|(setting, value)| (setting, config_path(vault_root, &value)))
+        .filter(|(_, path)| !path.is_file())
+        .map(|(setting, path)| format!("  {setting} = {}", path.display()))
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(Error::config(format!(
+            "configured templates not found:\n{}\nCreate them or fix the paths in config.toml",
+            missing.join("\n")
+        )));
+    }
+    Ok(())
+}
+
+/// The `templates/` folder and every configured template inside the vault,
+/// as paths below `vault_root`, for vault scans to skip.
+pub fn vault_paths(config: &AppConfig, vault_root: &Path) -> Vec<PathBuf> {
+    let root = vault_root
+        .canonicalize()
+        .unwrap_or_else(|_| vault_root.to_path_buf());
+    let mut paths = vec![vault_root.join("templates")];
+    for (_, value) in configured(config) {
+        let path = config_path(vault_root, &value);
+        let path = path.canonicalize().unwrap_or(path);
+        if let Ok(relative) = path.strip_prefix(&root) {
+            paths.push(vault_root.join(relative));
+        }
+    }
+    paths
 }
 
-pub fn load_task_template(vault_root: &Path) -> Result<String> {
-    let path = vault_root.join("templates/task.md");
-    load_template(&path, TASK_TEMPLATE)
+/// A template path from the config: absolute, or relative to the vault.
+pub fn config_path(vault_root: &Path, value: &str) -> PathBuf {
+    let path = Path::new(value);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        vault_root.join(path)
+    }
 }
 
-fn load_template(path: &Path, default: &str) -> Result<String> {
-    ensure_file(path, default)?;
-    fs::read_to_string(path).with_context(|| format!("read template {}", path.display()))
+fn resolve(
+    config: &AppConfig,
+    vault_root: &Path,
+    kind: TemplateKind,
+    name: Option<&str>,
+) -> Result<Option<PathBuf>> {
+    if let Some(name) = name {
+        let name = name.trim().trim_end_matches(".md");
+        let named = config
+            .templates
+            .named
+            .get(kind.name())
+            .and_then(|named| named.get(name));
+        let path = match named {
+            Some(value) => config_path(vault_root, value),
+            None => vault_root.join("templates").join(format!("{name}.md")),
+        };
+        if !path.is_file() {
+            anyhow::bail!(Error::not_found(format!(
+                "{} template `{name}` not found: {}",
+                kind.name(),
+                path.display()
+            )));
+        }
+        return Ok(Some(path));
+    }
+
+    let configured = match kind {
+        TemplateKind::Periodic(period) => config
+            .periodic(period)
+            .template
+            .as_deref()
+            .or((period == Period::Day).then_some(config.templates.daily.as_str())),
+        TemplateKind::Task => Some(config.templates.task.as_str()),
+        TemplateKind::Note => None,
+    };
+    if let Some(value) = configured {
+        let path = config_path(vault_root, value);
+        if !path.is_file() {
+            anyhow::bail!(Error::not_found(format!(
+                "{} template not found: {}",
+                kind.name(),
+                path.display()
+            )));
+        }
+        return Ok(Some(path));
+    }
+    let fallback = vault_root
+        .join("templates")
+        .join(format!("{}.md", kind.name()));
+    Ok(fallback.is_file().then_some(fallback))
+}
+
+/// Every template path set in the config, with the setting it comes from.
+fn configured(config: &AppConfig) -> Vec<(String, String)> {
+    let mut paths = vec![
+        (
+            "templates.daily".to_string(),
+            config.templates.daily.clone(),
+        ),
+        ("templates.task".to_string(), config.templates.task.clone()),
+    ];
+    for period in Period::ALL {
+        if let Some(template) = &config.periodic(period).template {
+            paths.push((format!("{}.template", period.name()), template.clone()));
+        }
+    }
+    for (kind, named) in &config.templates.named {
+        for (name, template) in named {
+            paths.push((format!("templates.named.{kind}.{name}"), template.clone()));
+        }
+    }
+    paths
+}
+
+/// Names of the templates in `templates/`, e.g. `daily` or `meetings/1on1`.
+pub fn list_templates(vault_root: &Path) -> Result<Vec<String>> {
+    let dir = vault_root.join("templates");
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    Ok(walk::markdown_files(&dir)?
+        .iter()
+        .map(|path| {
+            let relative = walk::relative_path(&dir, path);
+            relative.trim_end_matches(".md").to_string()
+        })
+        .collect())
+}
+
+/// Create `templates/<name>.md` with a starter template.
+pub fn create_template(vault_root: &Path, name: &str) -> Result<PathBuf> {
+    let name = name.trim().trim_end_matches(".md");
+    if name.is_empty() || !walk::is_relative_inside(Path::new(name)) {
+        anyhow::bail!(Error::usage(format!("invalid template name: {name}")));
+    }
+    let path = vault_root.join("templates").join(format!("{name}.md"));
+    if path.exists() {
+        anyhow::bail!(Error::conflict(format!(
+            "template already exists: {}",
+            path.display()
+        )));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    storage::write_atomic(&path, NEW_TEMPLATE)?;
+    Ok(path)
 }
 
 fn ensure_file(path: &Path, default: &str) -> Result<()> {
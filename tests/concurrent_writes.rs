@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use obsctl::config::{AppContext, ConfigManager};
+use obsctl::core::tasks::{NewTask, TaskFilter, TaskService};
+use obsctl::core::vault::VaultService;
+use obsctl::search::SearchIndex;
+use tempfile::TempDir;
+
+const THREADS: usize = 8;
+const PER_THREAD: usize = 10;
+
+fn context(dir: &TempDir) -> AppContext {
+    let config_path = dir.path().join(".obsctl/config.toml");
+    ConfigManager::new(config_path.clone())
+        .ensure_initialized(Some(&dir.path().join("vault")))
+        .unwrap();
+    AppContext::load_from(config_path).unwrap()
+}
+
+fn new_task(title: String) -> NewTask {
+    NewTask {
+        title,
+        due_date: None,
+        recurrence: None,
+        priority: None,
+        parent: None,
+    }
+}
+
+fn assert_no_temp_files(dir: &Path) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(!name.ends_with(".tmp"), "leftover temp file {name}");
+        if path.is_dir() {
+            assert_no_temp_files(&path);
+        }
+    }
+}
+
+#[test]
+fn threads_adding_and_completing_tasks_lose_nothing() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir);
+
+    let ids: Vec<String> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let ctx = &ctx;
+                scope.spawn(move || {
+                    let service = TaskService::new(ctx).unwrap();
+                    (0..PER_THREAD)
+                        .map(|i| service.add_task(new_task(format!("task {t}-{i}"))).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap())
+            .collect()
+    });
+
+    let unique: HashSet<&String> = ids.iter().collect();
+    assert_eq!(unique.len(), THREADS * PER_THREAD, "duplicate ids issued");
+
+    let service = TaskService::new(&ctx).unwrap();
+    let tasks = service.tasks().unwrap();
+    assert_eq!(tasks.len(), THREADS * PER_THREAD);
+    for t in 0..THREADS {
+        for i in 0..PER_THREAD {
+            let title = format!("task {t}-{i}");
+            assert!(tasks.iter().any(|task| task.title == title), "{title} lost");
+        }
+    }
+
+    thread::scope(|scope| {
+        for chunk in ids.chunks(PER_THREAD) {
+            let ctx = &ctx;
+            scope.spawn(move || {
+                let service = TaskService::new(ctx).unwrap();
+                for id in chunk {
+                    service.mark_done(id).unwrap();
+                }
+            });
+        }
+    });
+
+    let done = service.list_tasks(TaskFilter::Done).unwrap();
+    assert_eq!(done.len(), THREADS * PER_THREAD);
+    assert_no_temp_files(ctx.vault_root());
+}
+
+#[test]
+fn threads_appending_to_the_daily_note_keep_every_line() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir);
+
+    thread::scope(|scope| {
+        for t in 0..THREADS {
+            let ctx = &ctx;
+            scope.spawn(move || {
+                let vault = VaultService::new(ctx).unwrap();
+                for i in 0..PER_THREAD {
+                    vault.append_today(&format!("entry {t}-{i}"), None).unwrap();
+                }
+            });
+        }
+    });
+
+    let vault = VaultService::new(&ctx).unwrap();
+    let content = fs::read_to_string(vault.today_path()).unwrap();
+    for t in 0..THREADS {
+        for i in 0..PER_THREAD {
+            let entry = format!("entry {t}-{i}");
+            assert!(content.lines().any(|l| l == entry), "{entry} lost");
+        }
+    }
+    assert_no_temp_files(ctx.vault_root());
+}
+
+#[test]
+fn concurrent_index_refreshes_wait_for_each_other() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir);
+    let root = ctx.vault_root();
+    for i in 0..THREADS * PER_THREAD {
+        fs::write(root.join(format!("note {i}.md")), format!("body {i}")).unwrap();
+    }
+
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(move || {
+                let mut index = SearchIndex::open(root).unwrap();
+                index.refresh().unwrap();
+            });
+        }
+    });
+
+    let mut index = SearchIndex::open(root).unwrap();
+    let stats = index.refresh().unwrap();
+    assert_eq!(stats.indexed, 0);
+    assert!(stats.unchanged >= THREADS * PER_THREAD);
+}
+
+#[test]
+fn concurrent_cli_processes_issue_unique_ids() {
+    let home = TempDir::new().unwrap();
+    cargo_bin_cmd!("obsctl")
+        .env("HOME", home.path())
+        .args(["task", "list"])
+        .assert()
+        .success();
+
+    thread::scope(|scope| {
+        for t in 0..THREADS {
+            let home = home.path();
+            scope.spawn(move || {
+                for i in 0..PER_THREAD / 2 {
+                    cargo_bin_cmd!("obsctl")
+                        .env("HOME", home)
+                        .args(["task", "add", &format!("process {t}-{i}")])
+                        .assert()
+                        .success();
+                }
+            });
+        }
+    });
+
+    let tasks_file = home.path().join(".obsctl/vault/Tasks/tasks.md");
+    let content = fs::read_to_string(tasks_file).unwrap();
+    let ids: Vec<&str> = content
+        .lines()
+        .filter_map(|line| line.split("🆔 ").nth(1))
+        .collect();
+    assert_eq!(ids.len(), THREADS * (PER_THREAD / 2));
+    let unique: HashSet<&str> = ids.iter().copied().collect();
+    assert_eq!(unique.len(), ids.len(), "duplicate ids issued");
+}
@@ -0,0 +1,294 @@
+use std::time::Duration;
+
+use obsctl::config::{AppContext, ConfigManager, McpConfig};
+use obsctl::mcp::http;
+use rmcp::model::{CallToolRequestParam, ErrorCode, ReadResourceRequestParam, ResourceContents};
+use rmcp::service::{RoleClient, RunningService, ServiceError, ServiceExt};
+use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
+use rmcp::transport::StreamableHttpClientTransport;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct TestServer {
+    dir: TempDir,
+    url: String,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<anyhow::Result<()>>,
+}
+
+async fn start(token: Option<&str>) -> TestServer {
+    start_with(|mcp| mcp.auth_token = token.map(str::to_string)).await
+}
+
+fn context(dir: &TempDir, configure: impl FnOnce(&mut McpConfig)) -> AppContext {
+    let config_path = dir.path().join(".obsctl/config.toml");
+    let manager = ConfigManager::new(config_path.clone());
+    manager
+        .ensure_initialized(Some(&dir.path().join("vault")))
+        .unwrap();
+    let mut config = manager.load().unwrap();
+    configure(&mut config.mcp);
+    manager.save(&config).unwrap();
+    AppContext::load_from(config_path).unwrap()
+}
+
+async fn start_with(configure: impl FnOnce(&mut McpConfig)) -> TestServer {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir, configure);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!(
+        "http://{}{}",
+        listener.local_addr().unwrap(),
+        http::MCP_PATH
+    );
+    let (shutdown, signal) = oneshot::channel();
+    let handle = tokio::spawn(http::serve(ctx, listener, async {
+        let _ = signal.await;
+    }));
+    TestServer {
+        dir,
+        url,
+        shutdown,
+        handle,
+    }
+}
+
+async fn connect(url: &str, token: Option<&str>) -> anyhow::Result<RunningService<RoleClient, ()>> {
+    let mut config = StreamableHttpClientTransportConfig::with_uri(url);
+    if let Some(token) = token {
+        config = config.auth_header(token);
+    }
+    let transport = StreamableHttpClientTransport::from_config(config);
+    Ok(tokio::time::timeout(TIMEOUT, ().serve(transport)).await??)
+}
+
+/// Status code of a bare `POST /mcp` sent with `headers`.
+async fn status(url: &str, headers: &[(&str, &str)]) -> u16 {
+    let authority = url
+        .trim_start_matches("http://")
+        .trim_end_matches(http::MCP_PATH);
+    let mut request = format!("POST {} HTTP/1.1\r\n", http::MCP_PATH);
+    for (name, value) in headers {
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    request.push_str("Content-Length: 0\r\nConnection: close\r\n\r\n");
+    let mut stream = TcpStream::connect(authority).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .unwrap()
+}
+
+async fn call(client: &RunningService<RoleClient, ()>, name: &str, arguments: Value) -> Value {
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: arguments.as_object().cloned(),
+        })
+        .await
+        .unwrap();
+    result.structured_content.expect("structured result")
+}
+
+#[tokio::test]
+async fn serves_tools_and_resources_over_http() {
+    let server = start(None).await;
+    let client = connect(&server.url, None).await.unwrap();
+
+    let tools = client.list_all_tools().await.unwrap();
+    assert!(tools.iter().any(|tool| tool.name == "add_task"));
+
+    let task = call(
+        &client,
+        "add_task",
+        json!({ "title": "Water plants", "priority": "high" }),
+    )
+    .await;
+    assert_eq!(task["priority"], "high");
+    let listed = call(&client, "list_tasks", json!({})).await;
+    assert_eq!(listed["tasks"][0]["id"], task["id"]);
+
+    call(
+        &client,
+        "create_note",
+        json!({ "path": "Projects/Garden", "content": "# Garden\n" }),
+    )
+    .await;
+    let read = client
+        .read_resource(ReadResourceRequestParam {
+            uri: "obsctl://note/Projects/Garden.md".to_string(),
+        })
+        .await
+        .unwrap();
+    match &read.contents[0] {
+        ResourceContents::TextResourceContents { text, .. } => assert_eq!(text, "# Garden\n"),
+        other => panic!("unexpected contents: {other:?}"),
+    }
+
+    client.cancel().await.unwrap();
+    server.shutdown.send(()).unwrap();
+    server.handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn rejects_bad_recurrence_as_invalid_params() {
+    let server = start(None).await;
+    let client = connect(&server.url, None).await.unwrap();
+
+    let task = call(&client, "add_task", json!({ "title": "Water plants" })).await;
+    let err = client
+        .call_tool(CallToolRequestParam {
+            name: "edit_task".into(),
+            arguments: json!({ "id": task["id"], "recurrence": "every blue moon" })
+                .as_object()
+                .cloned(),
+        })
+        .await
+        .unwrap_err();
+    match err {
+        ServiceError::McpError(err) => assert_eq!(err.code, ErrorCode::INVALID_PARAMS),
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    client.cancel().await.unwrap();
+    server.shutdown.send(()).unwrap();
+    server.handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn caps_list_tasks_at_the_advertised_maximum() {
+    let server = start(None).await;
+    let tasks: String = (0..600)
+        .map(|n| format!("- [ ] chore {n} 🆔 c{n:05}\n"))
+        .collect();
+    std::fs::write(server.dir.path().join("vault/Tasks/tasks.md"), tasks).unwrap();
+    let client = connect(&server.url, None).await.unwrap();
+
+    let listed = call(&client, "list_tasks", json!({ "limit": 100000 })).await;
+    assert_eq!(listed["tasks"].as_array().unwrap().len(), 500);
+    assert_eq!(listed["total"], 600);
+    assert_eq!(listed["truncated"], true);
+
+    client.cancel().await.unwrap();
+    server.shutdown.send(()).unwrap();
+    server.handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn clients_share_one_server() {
+    let server = start(None).await;
+    let first = connect(&server.url, None).await.unwrap();
+    let second = connect(&server.url, None).await.unwrap();
+
+    let task = call(&first, "add_task", json!({ "title": "Shared" })).await;
+    let listed = call(&second, "list_tasks", json!({})).await;
+    assert_eq!(listed["total"], 1);
+    assert_eq!(listed["tasks"][0]["id"], task["id"]);
+
+    first.cancel().await.unwrap();
+    second.cancel().await.unwrap();
+    server.shutdown.send(()).unwrap();
+    server.handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn requires_the_configured_bearer_token() {
+    let server = start(Some("s3cret")).await;
+    assert!(connect(&server.url, None).await.is_err());
+    assert!(connect(&server.url, Some("wrong")).await.is_err());
+    let unauthorized = 401;
+    for (authorization, refused) in [
+        ("bearer s3cret", false),
+        ("BEARER  s3cret", false),
+        ("Basic s3cret", true),
+        ("Bearers3cret", true),
+        ("Bearer s3cre", true),
+    ] {
+        let headers = [("Host", "localhost"), ("Authorization", authorization)];
+        let code = status(&server.url, &headers).await;
+        assert_eq!(code == unauthorized, refused, "{authorization}");
+    }
+
+    let client = connect(&server.url, Some("s3cret")).await.unwrap();
+    let listed = call(&client, "list_tasks", json!({})).await;
+    assert_eq!(listed["total"], 0);
+
+    client.cancel().await.unwrap();
+    server.shutdown.send(()).unwrap();
+    server.handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn shuts_down_with_clients_connected() {
+    let server = start(None).await;
+    let client = connect(&server.url, None).await.unwrap();
+    client.list_all_tools().await.unwrap();
+
+    server.shutdown.send(()).unwrap();
+    tokio::time::timeout(TIMEOUT, server.handle)
+        .await
+        .expect("server stops while a session is open")
+        .unwrap()
+        .unwrap();
+    drop(client);
+}
+
+#[tokio::test]
+async fn refuses_foreign_hosts_and_origins() {
+    let server = start_with(|mcp| {
+        mcp.allowed_hosts = vec!["obsctl.lan".to_string()];
+        mcp.allowed_origins = vec!["https://app.example.com".to_string()];
+    })
+    .await;
+    let forbidden = 403;
+
+    assert_eq!(
+        status(&server.url, &[("Host", "evil.example:8765")]).await,
+        forbidden
+    );
+    assert_eq!(
+        status(
+            &server.url,
+            &[("Host", "localhost"), ("Origin", "https://evil.example")]
+        )
+        .await,
+        forbidden
+    );
+    assert_eq!(
+        status(&server.url, &[("Host", "localhost"), ("Origin", "null")]).await,
+        forbidden
+    );
+    for headers in [
+        &[("Host", "localhost:8765")][..],
+        &[("Host", "[::1]:8765"), ("Origin", "http://127.0.0.1:3000")],
+        &[
+            ("Host", "obsctl.lan:8765"),
+            ("Origin", "https://app.example.com"),
+        ],
+    ] {
+        assert_ne!(status(&server.url, headers).await, forbidden, "{headers:?}");
+    }
+
+    server.shutdown.send(()).unwrap();
+    server.handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn refuses_public_addresses_without_a_token() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir, |_| {});
+    let listener = TcpListener::bind("0.0.0.0:0").await.unwrap();
+    let err = http::serve(ctx, listener, async {}).await.unwrap_err();
+    assert!(err.to_string().contains("auth_token"), "{err}");
+}
@@ -0,0 +1,132 @@
+use std::fs;
+
+use obsctl::config::{AppContext, ConfigManager};
+use obsctl::core::tasks::{NewTask, TaskService};
+use tempfile::TempDir;
+
+fn context(dir: &TempDir, named_template: Option<&str>) -> AppContext {
+    let config_path = dir.path().join(".obsctl/config.toml");
+    let manager = ConfigManager::new(config_path.clone());
+    manager
+        .ensure_initialized(Some(&dir.path().join("vault")))
+        .unwrap();
+    if let Some(template) = named_template {
+        let mut config = manager.load().unwrap();
+        config
+            .templates
+            .named
+            .entry("note".to_string())
+            .or_default()
+            .insert("meeting".to_string(), template.to_string());
+        manager.save(&config).unwrap();
+    }
+    AppContext::load_from(config_path).unwrap()
+}
+
+#[test]
+fn listing_tasks_writes_nothing() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir, None);
+    let service = TaskService::new(&ctx).unwrap();
+    let note = ctx.vault_root().join("Inbox.md");
+    let text = "- [ ] call the plumber\n- [ ] call the plumber\n";
+    fs::write(&note, text).unwrap();
+    let registry = ctx.vault_root().join(".obsctl/task-ids");
+    let before = fs::read_to_string(&registry).unwrap_or_default();
+
+    let listed = service.tasks().unwrap();
+    let ids: Vec<&str> = listed
+        .iter()
+        .filter(|task| task.source == "Inbox.md")
+        .map(|task| task.id.as_str())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(fs::read_to_string(&note).unwrap(), text);
+    assert_eq!(fs::read_to_string(&registry).unwrap_or_default(), before);
+
+    // The id shown before the note was labelled is the one a write uses.
+    service.mark_done(ids[1]).unwrap();
+    let labelled = fs::read_to_string(&note).unwrap();
+    assert!(labelled.contains(&format!("- [ ] call the plumber 🆔 {}", ids[0])));
+    assert!(labelled.contains(&format!("- [x] call the plumber 🆔 {}", ids[1])));
+}
+
+#[test]
+fn skips_configured_templates() {
+    let dir = TempDir::new().unwrap();
+    let vault = dir.path().join("vault");
+    fs::create_dir_all(vault.join("Templates")).unwrap();
+    let template = vault.join("Templates/Meeting.md");
+    fs::write(&template, "- [ ] send notes for {{title}}\n").unwrap();
+    let ctx = context(&dir, Some("Templates/Meeting.md"));
+    let service = TaskService::new(&ctx).unwrap();
+
+    service.migrate_ids().unwrap();
+    let tasks = service.tasks().unwrap();
+    assert!(tasks
+        .iter()
+        .all(|task| task.source != "Templates/Meeting.md"));
+    assert_eq!(
+        fs::read_to_string(&template).unwrap(),
+        "- [ ] send notes for {{title}}\n"
+    );
+}
+
+#[test]
+fn writes_label_only_the_note_they_change() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir, None);
+    let service = TaskService::new(&ctx).unwrap();
+    let inbox = ctx.vault_root().join("Inbox.md");
+    let project = ctx.vault_root().join("Projects/alpha.md");
+    fs::create_dir_all(project.parent().unwrap()).unwrap();
+    fs::write(&inbox, "- [ ] call the plumber\n- [ ] buy stamps\n").unwrap();
+    fs::write(&project, "# Alpha\n- [ ] draft the brief\n").unwrap();
+    let listed = service.tasks().unwrap();
+    let id_of = |title: &str| {
+        listed
+            .iter()
+            .find(|task| task.title == title)
+            .map(|task| task.id.clone())
+            .unwrap()
+    };
+    let (plumber, stamps, brief) = (
+        id_of("call the plumber"),
+        id_of("buy stamps"),
+        id_of("draft the brief"),
+    );
+
+    service
+        .add_task(NewTask {
+            title: "water plants".to_string(),
+            due_date: None,
+            recurrence: None,
+            priority: None,
+            parent: None,
+        })
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&inbox).unwrap(),
+        "- [ ] call the plumber\n- [ ] buy stamps\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&project).unwrap(),
+        "# Alpha\n- [ ] draft the brief\n"
+    );
+
+    service.mark_done(&stamps).unwrap();
+    let text = fs::read_to_string(&inbox).unwrap();
+    assert!(text.starts_with(&format!("- [ ] call the plumber 🆔 {plumber}\n")));
+    assert!(text.contains(&format!("- [x] buy stamps 🆔 {stamps}")));
+    assert_eq!(
+        fs::read_to_string(&project).unwrap(),
+        "# Alpha\n- [ ] draft the brief\n"
+    );
+
+    service.migrate_ids().unwrap();
+    assert_eq!(
+        fs::read_to_string(&project).unwrap(),
+        format!("# Alpha\n- [ ] draft the brief 🆔 {brief}\n")
+    );
+}
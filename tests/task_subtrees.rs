@@ -0,0 +1,105 @@
+use std::fs;
+
+use obsctl::config::{AppContext, ConfigManager};
+use obsctl::core::tasks::TaskService;
+use tempfile::TempDir;
+
+const PROJECT: &str = "\
+# Garden
+- [ ] sow 🆔 sow001
+- [ ] build beds 🆔 bed001
+\t- [ ] buy wood 🆔 wood01
+\t\t- [ ] measure 🆔 meas01
+\t  ask the neighbours first
+- [ ] water 🆔 wat001
+";
+
+fn context(dir: &TempDir) -> AppContext {
+    let config_path = dir.path().join(".obsctl/config.toml");
+    ConfigManager::new(config_path.clone())
+        .ensure_initialized(Some(&dir.path().join("vault")))
+        .unwrap();
+    AppContext::load_from(config_path).unwrap()
+}
+
+#[test]
+fn deleting_a_task_removes_its_subtasks() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir);
+    let note = ctx.vault_root().join("Garden.md");
+    fs::write(&note, PROJECT).unwrap();
+    let service = TaskService::new(&ctx).unwrap();
+
+    service.delete_task("bed001").unwrap();
+    assert_eq!(
+        fs::read_to_string(&note).unwrap(),
+        "# Garden\n- [ ] sow 🆔 sow001\n- [ ] water 🆔 wat001\n"
+    );
+}
+
+#[test]
+fn moving_a_subtask_brings_its_children_and_reindents_them() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir);
+    let note = ctx.vault_root().join("Garden.md");
+    fs::write(&note, PROJECT).unwrap();
+    let service = TaskService::new(&ctx).unwrap();
+
+    let moved = service
+        .move_task("wood01", "Shopping", Some("Todo"))
+        .unwrap();
+    assert_eq!(moved.source, "Shopping.md");
+    assert_eq!(moved.parent, None);
+    assert_eq!(
+        fs::read_to_string(ctx.vault_root().join("Shopping.md")).unwrap(),
+        "## Todo\n\n- [ ] buy wood 🆔 wood01\n\t- [ ] measure 🆔 meas01\n  ask the neighbours first\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&note).unwrap(),
+        "# Garden\n- [ ] sow 🆔 sow001\n- [ ] build beds 🆔 bed001\n- [ ] water 🆔 wat001\n"
+    );
+    let tasks = service.tasks().unwrap();
+    let measure = tasks.iter().find(|task| task.id == "meas01").unwrap();
+    assert_eq!(measure.parent.as_deref(), Some("wood01"));
+    let water = tasks.iter().find(|task| task.id == "wat001").unwrap();
+    assert_eq!(water.parent, None);
+}
+
+#[test]
+fn completing_a_recurring_parent_keeps_its_subtasks() {
+    let dir = TempDir::new().unwrap();
+    let ctx = context(&dir);
+    let note = ctx.vault_root().join("Routines.md");
+    fs::write(
+        &note,
+        "- [ ] Weekly review 🔁 every week 📅 2025-03-10 🆔 rev001\n\
+         \t- [ ] Inbox zero 🆔 inb001\n\
+         \t- [ ] Archive mail 🆔 arc001\n\
+         - [ ] Stretch 🆔 str001\n",
+    )
+    .unwrap();
+    let service = TaskService::new(&ctx).unwrap();
+
+    let update = service.mark_done("rev001").unwrap();
+    let next = update.next.unwrap();
+    assert_eq!(next.line_number, 4);
+    let text = fs::read_to_string(&note).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("- [x] Weekly review"));
+    assert_eq!(lines[1], "\t- [ ] Inbox zero 🆔 inb001");
+    assert_eq!(lines[2], "\t- [ ] Archive mail 🆔 arc001");
+    assert_eq!(
+        lines[3],
+        format!(
+            "- [ ] Weekly review 🔁 every week 📅 2025-03-17 🆔 {}",
+            next.id
+        )
+    );
+    assert_eq!(lines[4], "- [ ] Stretch 🆔 str001");
+
+    let tasks = service.tasks().unwrap();
+    let inbox = tasks.iter().find(|task| task.id == "inb001").unwrap();
+    assert_eq!(inbox.parent.as_deref(), Some("rev001"));
+    let repeated = tasks.iter().find(|task| task.id == next.id).unwrap();
+    assert_eq!(repeated.progress, None);
+}